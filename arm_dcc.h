@@ -0,0 +1,29 @@
+/* C interface of the `arm-dcc` crate; requires its `c-api` feature */
+
+#ifndef ARM_DCC_H
+#define ARM_DCC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Writes a single word to the DCC */
+void dcc_write(uint32_t word);
+
+/* Writes a NUL terminated string to the DCC; `string` may be NULL */
+void dcc_write_str(const char *string);
+
+/* Writes `len` bytes to the DCC */
+void dcc_write_bytes(const void *bytes, size_t len);
+
+/* Writes a single character to the DCC and returns it, like `putchar` */
+int dcc_putc(int c);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ARM_DCC_H */
@@ -0,0 +1,63 @@
+  /* EmbeddedICE comms channel of ARMv4T / ARMv5 cores (e.g. ARM7TDMI, ARM926EJ-S) */
+
+  .syntax unified
+
+  /* NOTE Thumb-1 has no coprocessor instructions; Thumb callers reach these routines through
+     interworking branches */
+  .arm
+
+  .section .text.__dcc_write
+  .global __dcc_write
+  .type __dcc_write, %function
+__dcc_write:
+1:  mrc     p14, 0, r1, c0, c0, 0
+    tst     r1, #2              /* W bit */
+    bne     1b
+    mcr     p14, 0, r0, c1, c0, 0
+    bx      lr
+
+  /* sends the r1 bytes at r0, each one word-extended */
+  .section .text.__dcc_write_bytes
+  .global __dcc_write_bytes
+  .type __dcc_write_bytes, %function
+__dcc_write_bytes:
+    cmp     r1, #0
+    beq     3f
+    add     r1, r0, r1
+1:  ldrb    r2, [r0], #1
+2:  mrc     p14, 0, r3, c0, c0, 0
+    tst     r3, #2              /* W bit */
+    bne     2b
+    mcr     p14, 0, r2, c1, c0, 0
+    cmp     r0, r1
+    bne     1b
+3:  bx      lr
+
+  /* NOTE this returns the raw comms control register; W is bit 1 and R is bit 0 */
+  .section .text.__dcc_dscr
+  .global __dcc_dscr
+  .type __dcc_dscr, %function
+__dcc_dscr:
+    mrc     p14, 0, r0, c0, c0, 0
+    bx      lr
+
+  .section .text.__dcc_dtrtx
+  .global __dcc_dtrtx
+  .type __dcc_dtrtx, %function
+__dcc_dtrtx:
+    mcr     p14, 0, r0, c1, c0, 0
+    bx      lr
+
+  .section .text.__dcc_dtrrx
+  .global __dcc_dtrrx
+  .type __dcc_dtrrx, %function
+__dcc_dtrrx:
+    mrc     p14, 0, r0, c1, c0, 0
+    bx      lr
+
+  /* NOTE these cores have no WFE instruction */
+  .section .text.__dcc_wfe
+  .global __dcc_wfe
+  .type __dcc_wfe, %function
+__dcc_wfe:
+    bx      lr
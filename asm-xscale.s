@@ -0,0 +1,56 @@
+  /* CP14 debug channel of XScale cores (e.g. PXA255, PXA270, IXP4xx) */
+
+  .syntax unified
+
+  /* NOTE Thumb-1 has no coprocessor instructions; Thumb callers reach these routines through
+     interworking branches */
+  .arm
+
+  .section .text.__dcc_xscale_write
+  .global __dcc_xscale_write
+  .type __dcc_xscale_write, %function
+__dcc_xscale_write:
+1:  mrc     p14, 0, r1, c14, c0, 0
+    tst     r1, #0x10000000     /* TR bit */
+    bne     1b
+    mcr     p14, 0, r0, c8, c0, 0
+    bx      lr
+
+  /* sends the r1 bytes at r0, each one word-extended */
+  .section .text.__dcc_xscale_write_bytes
+  .global __dcc_xscale_write_bytes
+  .type __dcc_xscale_write_bytes, %function
+__dcc_xscale_write_bytes:
+    cmp     r1, #0
+    beq     3f
+    add     r1, r0, r1
+1:  ldrb    r2, [r0], #1
+2:  mrc     p14, 0, r3, c14, c0, 0
+    tst     r3, #0x10000000     /* TR bit */
+    bne     2b
+    mcr     p14, 0, r2, c8, c0, 0
+    cmp     r0, r1
+    bne     1b
+3:  bx      lr
+
+  /* NOTE this returns the raw TXRXCTRL register; TR is bit 28 and RR is bit 31 */
+  .section .text.__dcc_xscale_dscr
+  .global __dcc_xscale_dscr
+  .type __dcc_xscale_dscr, %function
+__dcc_xscale_dscr:
+    mrc     p14, 0, r0, c14, c0, 0
+    bx      lr
+
+  .section .text.__dcc_xscale_dtrtx
+  .global __dcc_xscale_dtrtx
+  .type __dcc_xscale_dtrtx, %function
+__dcc_xscale_dtrtx:
+    mcr     p14, 0, r0, c8, c0, 0
+    bx      lr
+
+  .section .text.__dcc_xscale_dtrrx
+  .global __dcc_xscale_dtrrx
+  .type __dcc_xscale_dtrrx, %function
+__dcc_xscale_dtrrx:
+    mrc     p14, 0, r0, c9, c0, 0
+    bx      lr
@@ -21,6 +21,60 @@ main() {
     arm-none-eabi-as -march=armv7-r -mbig-endian -mfloat-abi=hard -mfpu=vfpv3-d16 asm.s -o bin/$crate.o
     ar crs bin/armebv7r-none-eabihf.a bin/$crate.o
 
+    # NOTE Thumb-2 capable targets get Thumb-2 encoded routines
+    arm-none-eabi-as -march=armv7-r -mthumb -mlittle-endian -mfloat-abi=soft asm.s -o bin/$crate.o
+    ar crs bin/thumbv7r-none-eabi.a bin/$crate.o
+
+    arm-none-eabi-as -march=armv7-r -mthumb -mlittle-endian -mfloat-abi=hard -mfpu=vfpv3-d16 asm.s -o bin/$crate.o
+    ar crs bin/thumbv7r-none-eabihf.a bin/$crate.o
+
+    # NOTE the ARMv4T, ARMv5TE and ARMv6 Thumb targets use ARM state routines; Thumb-1 has no
+    # coprocessor instructions
+    arm-none-eabi-as -march=armv4t -mlittle-endian -mfloat-abi=soft asm-embeddedice.s -o bin/$crate.o
+    ar crs bin/armv4t-none-eabi.a bin/$crate.o
+    cp bin/armv4t-none-eabi.a bin/armv4t-unknown-linux-gnueabi.a
+    cp bin/armv4t-none-eabi.a bin/thumbv4t-none-eabi.a
+
+    arm-none-eabi-as -march=armv5te -mlittle-endian -mfloat-abi=soft asm-embeddedice.s -o bin/$crate.o
+    arm-none-eabi-as -march=armv5te -mlittle-endian -mfloat-abi=soft asm-xscale.s -o bin/xscale.o
+    ar crs bin/armv5te-none-eabi.a bin/$crate.o bin/xscale.o
+    rm bin/xscale.o
+    cp bin/armv5te-none-eabi.a bin/armv5te-unknown-linux-gnueabi.a
+    cp bin/armv5te-none-eabi.a bin/thumbv5te-none-eabi.a
+
+    # NOTE ARMv6K is needed for WFE, which plain ARMv6 cores execute as a NOP
+    arm-none-eabi-as -march=armv6k -mlittle-endian -mfloat-abi=soft asm.s -o bin/$crate.o
+    ar crs bin/armv6-none-eabi.a bin/$crate.o
+    cp bin/armv6-none-eabi.a bin/arm-unknown-linux-gnueabi.a
+    cp bin/armv6-none-eabi.a bin/thumbv6-none-eabi.a
+
+    arm-none-eabi-as -march=armv6k -mlittle-endian -mfloat-abi=hard -mfpu=vfp asm.s -o bin/$crate.o
+    ar crs bin/armv6-none-eabihf.a bin/$crate.o
+    cp bin/armv6-none-eabihf.a bin/arm-unknown-linux-gnueabihf.a
+
+    arm-none-eabi-as -march=armv7-a -mlittle-endian -mfloat-abi=soft asm.s -o bin/$crate.o
+    ar crs bin/armv7a-none-eabi.a bin/$crate.o
+    cp bin/armv7a-none-eabi.a bin/armv7-unknown-linux-gnueabi.a
+
+    arm-none-eabi-as -march=armv7-a -mlittle-endian -mfloat-abi=hard -mfpu=vfpv3-d16 asm.s -o bin/$crate.o
+    ar crs bin/armv7a-none-eabihf.a bin/$crate.o
+    cp bin/armv7a-none-eabihf.a bin/armv7-unknown-linux-gnueabihf.a
+
+    arm-none-eabi-as -march=armv7-a -mthumb -mlittle-endian -mfloat-abi=soft asm.s -o bin/$crate.o
+    ar crs bin/thumbv7a-none-eabi.a bin/$crate.o
+
+    arm-none-eabi-as -march=armv7-a -mthumb -mlittle-endian -mfloat-abi=hard -mfpu=vfpv3-d16 asm.s -o bin/$crate.o
+    ar crs bin/thumbv7a-none-eabihf.a bin/$crate.o
+
+    arm-none-eabi-as -march=armv7-a -mthumb -mlittle-endian -mfloat-abi=hard -mfpu=neon asm.s -o bin/$crate.o
+    ar crs bin/thumbv7neon-unknown-linux-gnueabihf.a bin/$crate.o
+
+    arm-none-eabi-as -march=armv8-r -mlittle-endian -mfloat-abi=hard -mfpu=fpv5-sp-d16 asm.s -o bin/$crate.o
+    ar crs bin/armv8r-none-eabihf.a bin/$crate.o
+
+    arm-none-eabi-as -march=armv8-r -mthumb -mlittle-endian -mfloat-abi=hard -mfpu=fpv5-sp-d16 asm.s -o bin/$crate.o
+    ar crs bin/thumbv8r-none-eabihf.a bin/$crate.o
+
     rm bin/$crate.o
 }
 
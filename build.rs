@@ -1,18 +1,168 @@
 use std::{env, fs, path::PathBuf};
 
+/// Targets for which `assemble.sh` produces a `bin/$TARGET.a` blob; used with `extern-asm`
+const BLOBS: &[&str] = &[
+    "arm-unknown-linux-gnueabi",
+    "arm-unknown-linux-gnueabihf",
+    "armebv7r-none-eabi",
+    "armebv7r-none-eabihf",
+    "armv4t-none-eabi",
+    "armv4t-unknown-linux-gnueabi",
+    "armv5te-none-eabi",
+    "armv5te-unknown-linux-gnueabi",
+    "armv6-none-eabi",
+    "armv6-none-eabihf",
+    "armv7-unknown-linux-gnueabi",
+    "armv7-unknown-linux-gnueabihf",
+    "armv7a-none-eabi",
+    "armv7a-none-eabihf",
+    "armv7r-none-eabi",
+    "armv7r-none-eabihf",
+    "armv8r-none-eabihf",
+    "thumbv4t-none-eabi",
+    "thumbv5te-none-eabi",
+    "thumbv6-none-eabi",
+    "thumbv7a-none-eabi",
+    "thumbv7a-none-eabihf",
+    "thumbv7neon-unknown-linux-gnueabihf",
+    "thumbv7r-none-eabi",
+    "thumbv7r-none-eabihf",
+    "thumbv8r-none-eabihf",
+];
+
+/// Targets whose cores have the EmbeddedICE comms channel instead of the ARMv6+ CP14 interface
+const EMBEDDEDICE: &[&str] = &[
+    "armv4t-none-eabi",
+    "armv4t-unknown-linux-gnueabi",
+    "armv5te-none-eabi",
+    "armv5te-unknown-linux-gnueabi",
+    "thumbv4t-none-eabi",
+    "thumbv5te-none-eabi",
+];
+
+/// Targets whose blobs also contain the routines for the XScale debug channel
+const XSCALE: &[&str] = &[
+    "armv5te-none-eabi",
+    "armv5te-unknown-linux-gnueabi",
+    "thumbv5te-none-eabi",
+];
+
+/// ARMv6 targets that run in ARM state
+const ARMV6: &[&str] = &[
+    "arm-unknown-linux-gnueabi",
+    "arm-unknown-linux-gnueabihf",
+    "armv6-none-eabi",
+    "armv6-none-eabihf",
+];
+
+/// Thumb targets whose instruction set (Thumb-1) has no coprocessor instructions
+const THUMB1: &[&str] = &[
+    "thumbv4t-none-eabi",
+    "thumbv5te-none-eabi",
+    "thumbv6-none-eabi",
+];
+
+/// Prefixes of the M-profile targets; these cores have no DCC so the ITM is used instead
+const M_PROFILE: &[&str] = &["thumbv6m-", "thumbv7em-", "thumbv7m-", "thumbv8m."];
+
+/// Names of the `LevelFilter` variants
+const LEVELS: &[&str] = &["Off", "Error", "Warn", "Info", "Debug", "Trace"];
+
 fn main() {
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let target = env::var("TARGET").unwrap();
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+
+    let embeddedice = EMBEDDEDICE.contains(&&*target);
+    let itm = M_PROFILE.iter().any(|prefix| target.starts_with(prefix));
+
+    if arch == "arm" && env::var_os("CARGO_FEATURE_XSCALE").is_some() && !XSCALE.contains(&&*target)
+    {
+        panic!(
+            "the `xscale` feature is not supported on target `{}`; \
+             XScale cores use one of the ARMv5TE targets",
+            target
+        );
+    }
+
+    // the prebuilt blobs are only used with the (compatibility) `extern-asm` feature
+    let extern_asm = env::var_os("CARGO_FEATURE_NOP").is_none()
+        && env::var_os("CARGO_FEATURE_EXTERN_ASM").is_some();
+
+    println!("cargo:rustc-check-cfg=cfg(dcc_embeddedice)");
+    if embeddedice {
+        println!("cargo:rustc-cfg=dcc_embeddedice");
+    }
 
-    if target.starts_with("armv") {
-        fs::copy(format!("bin/{}.a", target), out_dir.join("libdcc.a")).unwrap();
-        println!("cargo:rustc-link-lib=static=dcc");
-        println!("cargo:rustc-link-search={}", out_dir.display());
+    println!("cargo:rustc-check-cfg=cfg(dcc_itm)");
+    if itm {
+        println!("cargo:rustc-cfg=dcc_itm");
     }
 
-    println!("cargo:rerun-if-changed=bin/armebv7r-none-eabi.a");
-    println!("cargo:rerun-if-changed=bin/armebv7r-none-eabihf.a");
-    println!("cargo:rerun-if-changed=bin/armv7r-none-eabi.a");
-    println!("cargo:rerun-if-changed=bin/armv7r-none-eabihf.a");
+    println!("cargo:rustc-check-cfg=cfg(dcc_armv6)");
+    if ARMV6.contains(&&*target) {
+        println!("cargo:rustc-cfg=dcc_armv6");
+    }
+
+    println!("cargo:rustc-check-cfg=cfg(dcc_thumb1)");
+    if THUMB1.contains(&&*target) {
+        println!("cargo:rustc-cfg=dcc_thumb1");
+    }
+
+    // targets without atomic instructions, like `armv4t-none-eabi`, get single core replacements
+    let atomics = env::var("CARGO_CFG_TARGET_HAS_ATOMIC").unwrap_or_default();
+    println!("cargo:rustc-check-cfg=cfg(dcc_no_atomics)");
+    if !atomics.split(',').any(|width| width == "32") {
+        println!("cargo:rustc-cfg=dcc_no_atomics");
+    }
+
+    if extern_asm {
+        if BLOBS.contains(&&*target) {
+            fs::copy(format!("bin/{}.a", target), out_dir.join("libdcc.a")).unwrap();
+            println!("cargo:rustc-link-lib=static=dcc");
+            println!("cargo:rustc-link-search={}", out_dir.display());
+        } else if arch == "arm" && !itm {
+            panic!(
+                "the external assembly has not been built for target `{}`; \
+                 disable the `extern-asm` feature to use this crate on it",
+                target
+            );
+        }
+    }
+
+    // the `ARM_DCC_LOG` module filters, as a `&[ModuleFilter]` expression; see `set_module_filters`
+    let mut filters = String::from("&[");
+    for entry in env::var("ARM_DCC_LOG").unwrap_or_default().split(',') {
+        let mut parts = entry.splitn(2, '=');
+        let module = parts.next().unwrap_or("").trim();
+        let level = match parts.next().map(str::trim) {
+            Some(name) => match LEVELS.iter().find(|level| level.eq_ignore_ascii_case(name)) {
+                Some(level) => level,
+                None => {
+                    println!(
+                        "cargo:warning=ARM_DCC_LOG: unknown level `{}`; ignoring `{}`",
+                        name,
+                        entry.trim()
+                    );
+                    continue;
+                }
+            },
+            None => "Trace",
+        };
+
+        if !module.is_empty() {
+            filters.push_str(&format!(
+                "ModuleFilter {{ module: {:?}, level: LevelFilter::{} }},",
+                module, level
+            ));
+        }
+    }
+    filters.push(']');
+    fs::write(out_dir.join("module_filters.rs"), filters).unwrap();
+
+    for blob in BLOBS {
+        println!("cargo:rerun-if-changed=bin/{}.a", blob);
+    }
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=ARM_DCC_LOG");
 }
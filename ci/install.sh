@@ -2,7 +2,7 @@ set -euxo pipefail
 
 main() {
     case $TARGET in
-        arm*v7r-none-eabi*)
+        arm*v7r-none-eabi* | armv4t-* | armv5te-* | thumbv4t-* | thumbv5te-* | thumbv6-none-eabi | thumbv7a-none-eabi* | thumbv7neon-unknown-linux-gnueabihf | thumbv7r-none-eabi* | thumbv8r-none-eabihf | armv6-none-eabi* | arm-unknown-linux-gnueabi* | armv7a-none-eabi* | armv7-unknown-linux-gnueabi* | armv8r-none-eabihf | thumbv6m-none-eabi | thumbv7m-none-eabi | thumbv7em-none-eabi* | thumbv8m.* | aarch64-unknown-none)
             rustup target add $TARGET
             ;;
         *)
@@ -3,18 +3,34 @@ set -euxo pipefail
 main() {
     cargo check --target $TARGET
     cargo check --features nop --target $TARGET
+    cargo check --features packed --target $TARGET
+    cargo check --features stats --target $TARGET
 
-    if [ $TRAVIS_RUST_VERSION = nightly ]; then
-        cargo check --target $TARGET --features inline-asm
-        cargo check --target $TARGET --features 'inline-asm nop'
-    fi
+    case $TARGET in
+        # heapless doesn't compile on targets without atomic instructions
+        armv4t-none-eabi | armv5te-none-eabi | thumbv4t-none-eabi | thumbv5te-none-eabi)
+            ;;
+        *)
+            cargo check --features heapless --target $TARGET
+            ;;
+    esac
+
+    case $TARGET in
+        armv5te-* | thumbv5te-none-eabi)
+            cargo check --features xscale --target $TARGET
+            ;;
+    esac
+
+    cargo check --features extern-asm --target $TARGET
+    cargo check --features 'extern-asm nop' --target $TARGET
 
     case $TARGET in
-        arm*v7r-none-eabi*)
+        arm*v7r-none-eabi* | armv4t-* | armv5te-* | thumbv4t-* | thumbv5te-* | thumbv6-none-eabi | thumbv7a-none-eabi* | thumbv7neon-unknown-linux-gnueabihf | thumbv7r-none-eabi* | thumbv8r-none-eabihf | armv6-none-eabi* | arm-unknown-linux-gnueabi* | armv7a-none-eabi* | armv7-unknown-linux-gnueabi* | armv8r-none-eabihf | thumbv6m-none-eabi | thumbv7m-none-eabi | thumbv7em-none-eabi* | thumbv8m.* | aarch64-unknown-none)
             ;;
 
         *)
             cargo test --target $TARGET
+            cargo test --features std --target $TARGET
 
             ./check-blobs.sh
             ;;
@@ -0,0 +1,103 @@
+//! Parsing of the core dumps produced by `arm_dcc::coredump::write`
+
+use std::fmt;
+
+use arm_dcc::coredump::{Checksum, Reason, FLAG_BIG_ENDIAN, MAGIC, REGISTERS, VERSION};
+
+/// A core dump
+pub struct Dump {
+    /// Whether the memory words (and the registers) are big endian
+    pub big_endian: bool,
+    pub reason: Reason,
+    /// r0 - r15 and CPSR
+    pub registers: [u32; REGISTERS],
+    /// Address and contents of each memory region
+    pub regions: Vec<(u32, Vec<u32>)>,
+}
+
+pub enum Error {
+    NotFound,
+    Truncated,
+    Version(u32),
+    Reason(u32),
+    Checksum { expected: u32, computed: u32 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NotFound => f.write_str("no core dump found"),
+            Error::Truncated => f.write_str("the core dump is truncated"),
+            Error::Version(version) => write!(f, "unsupported core dump version {}", version),
+            Error::Reason(reason) => write!(f, "unknown core dump reason {}", reason),
+            Error::Checksum { expected, computed } => write!(
+                f,
+                "corrupted core dump: its CRC is {:#010x} but the computed one is {:#010x}",
+                expected, computed
+            ),
+        }
+    }
+}
+
+/// Cursor over the words of a core dump that keeps its checksum
+struct Words<'a> {
+    words: &'a [u32],
+    checksum: Checksum,
+}
+
+impl Words<'_> {
+    fn next(&mut self) -> Result<u32, Error> {
+        let (word, rest) = self.words.split_first().ok_or(Error::Truncated)?;
+        self.words = rest;
+        self.checksum.update(*word);
+        Ok(*word)
+    }
+}
+
+/// Parses the first core dump found in `words`, the binary words received from the DCC
+pub fn parse(words: &[u32]) -> Result<Dump, Error> {
+    let start = words
+        .iter()
+        .position(|word| *word == MAGIC)
+        .ok_or(Error::NotFound)?;
+    let mut words = Words {
+        words: &words[start..],
+        checksum: Checksum::new(),
+    };
+
+    words.next()?;
+    let version = words.next()?;
+    if version != VERSION {
+        return Err(Error::Version(version));
+    }
+    let flags = words.next()?;
+    let reason = words.next()?;
+    let reason = Reason::from_u32(reason).ok_or(Error::Reason(reason))?;
+    let nregions = words.next()?;
+
+    let mut registers = [0; REGISTERS];
+    for register in registers.iter_mut() {
+        *register = words.next()?;
+    }
+
+    let mut regions = vec![];
+    for _ in 0..nregions {
+        let address = words.next()?;
+        let len = words.next()?;
+        let contents = (0..len).map(|_| words.next()).collect::<Result<_, _>>()?;
+        regions.push((address, contents));
+    }
+
+    let computed = words.checksum.finish();
+    let expected = words.next()?;
+    if expected != computed {
+        return Err(Error::Checksum { expected, computed });
+    }
+
+    Ok(Dump {
+        big_endian: flags & FLAG_BIG_ENDIAN != 0,
+        reason,
+        registers,
+        regions,
+    })
+}
@@ -0,0 +1,154 @@
+//! ELF core files, in the format GDB expects from 32-bit ARM Linux
+//!
+//! The file has a `PT_NOTE` segment with a single `NT_PRSTATUS` note, which holds the registers,
+//! followed by a `PT_LOAD` segment per memory region.
+
+use arm_dcc::coredump::Reason;
+
+use crate::dump::Dump;
+
+const EHDR_SIZE: usize = 52;
+const PHDR_SIZE: usize = 32;
+
+const ET_CORE: u16 = 4;
+const EM_ARM: u16 = 40;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_RWX: u32 = 0b111;
+const NT_PRSTATUS: u32 = 1;
+
+/// Size of `struct elf_prstatus` on 32-bit ARM Linux
+const PRSTATUS_SIZE: usize = 148;
+/// Offset of `pr_reg` in `struct elf_prstatus`
+const PRSTATUS_REG: usize = 72;
+const NOTE_NAME: &[u8; 8] = b"CORE\0\0\0\0";
+const NOTE_SIZE: usize = 12 + NOTE_NAME.len() + PRSTATUS_SIZE;
+
+/// Little or big endian output buffer
+struct Out {
+    bytes: Vec<u8>,
+    big_endian: bool,
+}
+
+impl Out {
+    fn u8(&mut self, byte: u8) {
+        self.bytes.push(byte)
+    }
+
+    fn u16(&mut self, half: u16) {
+        let bytes = if self.big_endian {
+            half.to_be_bytes()
+        } else {
+            half.to_le_bytes()
+        };
+        self.bytes.extend_from_slice(&bytes)
+    }
+
+    fn u32(&mut self, word: u32) {
+        let bytes = if self.big_endian {
+            word.to_be_bytes()
+        } else {
+            word.to_le_bytes()
+        };
+        self.bytes.extend_from_slice(&bytes)
+    }
+
+    fn zeros(&mut self, n: usize) {
+        self.bytes.resize(self.bytes.len() + n, 0)
+    }
+}
+
+/// The signal GDB reports as the cause of the crash
+fn signal(reason: Reason) -> u16 {
+    match reason {
+        // SIGABRT
+        Reason::Panic => 6,
+        // SIGILL
+        Reason::UndefinedInstruction => 4,
+        // SIGSEGV
+        Reason::PrefetchAbort | Reason::DataAbort => 11,
+    }
+}
+
+/// Builds the ELF core file of `dump`
+pub fn core(dump: &Dump) -> Vec<u8> {
+    let mut out = Out {
+        bytes: vec![],
+        big_endian: dump.big_endian,
+    };
+    let phnum = 1 + dump.regions.len();
+
+    // ELF header
+    out.bytes.extend_from_slice(b"\x7fELF");
+    out.u8(1); // ELFCLASS32
+    out.u8(if dump.big_endian { 2 } else { 1 }); // ELFDATA2MSB / ELFDATA2LSB
+    out.u8(1); // EV_CURRENT
+    out.zeros(9); // ELFOSABI_NONE, padding
+    out.u16(ET_CORE);
+    out.u16(EM_ARM);
+    out.u32(1); // e_version
+    out.u32(0); // e_entry
+    out.u32(EHDR_SIZE as u32); // e_phoff
+    out.u32(0); // e_shoff
+    out.u32(0x0500_0000); // e_flags: EABI version 5
+    out.u16(EHDR_SIZE as u16);
+    out.u16(PHDR_SIZE as u16);
+    out.u16(phnum as u16);
+    out.u16(40); // e_shentsize
+    out.u16(0); // e_shnum
+    out.u16(0); // e_shstrndx
+
+    // program headers
+    let mut offset = EHDR_SIZE + phnum * PHDR_SIZE;
+    out.u32(PT_NOTE);
+    out.u32(offset as u32);
+    out.zeros(8); // p_vaddr, p_paddr
+    out.u32(NOTE_SIZE as u32); // p_filesz
+    out.u32(0); // p_memsz
+    out.u32(0); // p_flags
+    out.u32(4); // p_align
+    offset += NOTE_SIZE;
+
+    for (address, contents) in &dump.regions {
+        let size = 4 * contents.len() as u32;
+
+        out.u32(PT_LOAD);
+        out.u32(offset as u32);
+        out.u32(*address); // p_vaddr
+        out.u32(*address); // p_paddr
+        out.u32(size); // p_filesz
+        out.u32(size); // p_memsz
+        out.u32(PF_RWX);
+        out.u32(4); // p_align
+        offset += size as usize;
+    }
+
+    // NT_PRSTATUS note
+    out.u32(5); // n_namesz, "CORE\0"
+    out.u32(PRSTATUS_SIZE as u32);
+    out.u32(NT_PRSTATUS);
+    out.bytes.extend_from_slice(NOTE_NAME);
+    let prstatus = out.bytes.len();
+    let signal = signal(dump.reason);
+    out.u32(u32::from(signal)); // pr_info.si_signo
+    out.zeros(8); // pr_info.si_code, pr_info.si_errno
+    out.u16(signal); // pr_cursig
+    out.zeros(2 + 8); // padding, pr_sigpend, pr_sighold
+    out.u32(1); // pr_pid
+    out.zeros(PRSTATUS_REG - (out.bytes.len() - prstatus));
+    for register in dump.registers.iter() {
+        out.u32(*register);
+    }
+    out.u32(dump.registers[0]); // ORIG_r0
+    out.u32(0); // pr_fpvalid
+    debug_assert_eq!(out.bytes.len() - prstatus, PRSTATUS_SIZE);
+
+    // memory
+    for (_, contents) in &dump.regions {
+        for word in contents {
+            out.u32(*word);
+        }
+    }
+
+    out.bytes
+}
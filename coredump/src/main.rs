@@ -0,0 +1,125 @@
+//! Converts core dumps received over the Debug Communication Channel (DCC) into ELF core files
+//!
+//! Usage: `dcc-coredump [--text] <words> <core>`
+//!
+//! `<words>` holds the raw words received from the DCC, each one as 4 little endian bytes. The
+//! first core dump found among them (see the `arm_dcc::coredump` module) is written to `<core>`
+//! as an ELF core file that GDB (12 or newer) can load along with the program:
+//! `arm-none-eabi-gdb app.elf <core>`.
+//!
+//! With `--text` the input is a plain text DCC log instead, and the core dump is the first one
+//! printed by `arm_dcc::coredump::write_text`.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use std::{env, fs, process};
+
+use arm_dcc::{
+    coredump::{TEXT_BEGIN, TEXT_END},
+    decode::{Decoder, Item},
+    framing::base64,
+};
+
+mod dump;
+mod elf;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let (text, input, core) = match &args[..] {
+        [input, core] => (false, input, core),
+        [flag, input, core] if flag == "--text" => (true, input, core),
+        _ => return Err("usage: dcc-coredump [--text] <words> <core>".to_string()),
+    };
+
+    let bytes = fs::read(input).map_err(|e| format!("couldn't read {}: {}", input, e))?;
+    let words = if text {
+        text_words(&bytes)?
+    } else {
+        binary_words(&bytes)
+    };
+
+    let dump = dump::parse(&words).map_err(|e| e.to_string())?;
+    eprintln!(
+        "found a core dump ({:?}) with {} memory regions",
+        dump.reason,
+        dump.regions.len()
+    );
+
+    fs::write(core, elf::core(&dump)).map_err(|e| format!("couldn't write {}: {}", core, e))
+}
+
+// keeps only the binary words; the core dump is sent in binary frames
+fn binary_words(bytes: &[u8]) -> Vec<u32> {
+    let mut decoder = Decoder::new();
+    let mut corrupted = 0;
+    let words = bytes
+        .chunks_exact(4)
+        .filter_map(|chunk| {
+            let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+
+            match decoder.push(word) {
+                Some(Item::Word(word)) => Some(word),
+                Some(Item::BadCrc) => {
+                    corrupted += 1;
+                    None
+                }
+                Some(Item::Lost(frames)) => {
+                    corrupted += frames;
+                    None
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if corrupted != 0 {
+        eprintln!(
+            "warning: {} frames were corrupted or lost; the core dump may be incomplete",
+            corrupted
+        );
+    }
+
+    words
+}
+
+// decodes the Base64 lines between the first `TEXT_BEGIN` line and the next `TEXT_END` line
+fn text_words(bytes: &[u8]) -> Result<Vec<u32>, String> {
+    let log = String::from_utf8_lossy(bytes);
+    let mut lines = log.lines().map(str::trim_end);
+    // NOTE capture tools may prefix lines with e.g. a timestamp; Base64 has no whitespace so the
+    // payload is the last whitespace separated token of each line
+    if !lines.any(|line| line.ends_with(TEXT_BEGIN)) {
+        return Err(format!("no `{}` line found", TEXT_BEGIN));
+    }
+
+    let mut encoded = String::new();
+    loop {
+        match lines.next() {
+            Some(line) if line.ends_with(TEXT_END) => break,
+            Some(line) => encoded.push_str(line.split_whitespace().last().unwrap_or("")),
+            None => {
+                return Err(format!(
+                    "the core dump is truncated: no `{}` line found",
+                    TEXT_END
+                ))
+            }
+        }
+    }
+
+    let mut payload = vec![0; encoded.len() / 4 * 3];
+    let n = base64::decode(encoded.as_bytes(), &mut payload)
+        .map_err(|e| format!("the core dump is not valid Base64 ({:?})", e))?;
+
+    Ok(payload[..n]
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
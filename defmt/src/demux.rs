@@ -0,0 +1,31 @@
+use arm_dcc::decode::{Decoder, Item};
+use defmt_decoder::StreamDecoder;
+
+/// Splits the DCC word stream into `defmt` data and everything else
+#[derive(Clone, Debug, Default)]
+pub struct Demux {
+    decoder: Decoder,
+}
+
+impl Demux {
+    /// Creates a new demultiplexer
+    pub const fn new() -> Self {
+        Demux {
+            decoder: Decoder::new(),
+        }
+    }
+
+    /// Feeds the next word received from the DCC into the demultiplexer
+    ///
+    /// `defmt` bytes are passed to `stream`; call `stream.decode()` to get the decoded frames. The
+    /// other items found in the word stream, e.g. text, are returned.
+    pub fn push(&mut self, word: u32, stream: &mut dyn StreamDecoder) -> Option<Item> {
+        match self.decoder.push(word) {
+            Some(Item::Bytes(chunk)) => {
+                stream.received(chunk.as_bytes());
+                None
+            }
+            item => item,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! A `defmt` global logger that uses the Debug Communication Channel (DCC)
+//!
+//! Linking this crate registers a `#[defmt::global_logger]` that sends the (rzCOBS encoded)
+//! `defmt` frames to the host as binary byte frames; see `arm_dcc::write_bytes_framed`. Text
+//! printed with the `arm_dcc` API can be freely mixed with `defmt` logs: host decoders tell them
+//! apart.
+//!
+//! The logger runs in a critical section provided by the `critical-section` crate; the application
+//! (or one of its dependencies) must provide an implementation for the target.
+//!
+//! # Example
+//!
+//! ## Device side
+//!
+//! ``` ignore
+//! use defmt_dcc as _;
+//!
+//! fn main() {
+//!     defmt::info!("booted in {} ms", 12);
+//! }
+//! ```
+//!
+//! ## Host side
+//!
+//! The host needs the raw words sent over the DCC and the ELF file of the program. With the
+//! `decoder` feature enabled this crate provides `Demux`, which extracts the `defmt` bytes from
+//! the word stream and feeds them into a `defmt_decoder::StreamDecoder`.
+//!
+//! ``` ignore
+//! use arm_dcc::decode::Item;
+//! use defmt_dcc::Demux;
+//! use defmt_decoder::{DecodeError, Table};
+//!
+//! let table = Table::parse(&elf)?.expect(".defmt data not found");
+//! let mut stream = table.new_stream_decoder();
+//! let mut demux = Demux::new();
+//!
+//! for word in words {
+//!     match demux.push(word, &mut *stream) {
+//!         Some(Item::Text(chunk)) => print!("{}", String::from_utf8_lossy(chunk.as_bytes())),
+//!         _ => {}
+//!     }
+//!
+//!     loop {
+//!         match stream.decode() {
+//!             Ok(frame) => println!("{}", frame.display(true)),
+//!             Err(DecodeError::UnexpectedEof) => break,
+//!             Err(DecodeError::Malformed) => eprintln!("malformed defmt frame"),
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! # Optional features
+//!
+//! ## `decoder`
+//!
+//! Adds `Demux`, the host side glue. This feature pulls in `defmt-decoder` and requires `std`, so
+//! it's meant for host tooling only.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+#![no_std]
+
+#[cfg(feature = "decoder")]
+extern crate std;
+
+#[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+mod logger;
+
+#[cfg(feature = "decoder")]
+mod demux;
+
+#[cfg(feature = "decoder")]
+pub use crate::demux::Demux;
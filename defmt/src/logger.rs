@@ -0,0 +1,69 @@
+use core::cell::UnsafeCell;
+
+use arm_dcc::BufferedBytesWriter;
+use critical_section::RestoreState;
+use defmt::Encoder;
+
+struct State {
+    taken: bool,
+    restore: RestoreState,
+    encoder: Encoder,
+    // NOTE the encoder hands out small pieces of the frame, often a single byte
+    output: BufferedBytesWriter<64>,
+}
+
+struct Global(UnsafeCell<State>);
+
+// NOTE(unsafe) `State` is only accessed from within the critical section taken by `acquire`
+unsafe impl Sync for Global {}
+
+static STATE: Global = Global(UnsafeCell::new(State {
+    taken: false,
+    restore: RestoreState::invalid(),
+    encoder: Encoder::new(),
+    output: BufferedBytesWriter::new(),
+}));
+
+#[defmt::global_logger]
+struct Logger;
+
+unsafe impl defmt::Logger for Logger {
+    fn acquire() {
+        // NOTE(unsafe) the critical section ends in `release`
+        let restore = unsafe { critical_section::acquire() };
+
+        // NOTE(unsafe) we are in the critical section
+        let state = unsafe { &mut *STATE.0.get() };
+        if state.taken {
+            panic!("defmt logger taken reentrantly")
+        }
+
+        state.taken = true;
+        state.restore = restore;
+
+        let output = &mut state.output;
+        state.encoder.start_frame(|bytes| output.write(bytes));
+    }
+
+    unsafe fn flush() {
+        (*STATE.0.get()).output.flush()
+    }
+
+    unsafe fn release() {
+        let state = &mut *STATE.0.get();
+
+        let output = &mut state.output;
+        state.encoder.end_frame(|bytes| output.write(bytes));
+        output.flush();
+
+        state.taken = false;
+        critical_section::release(state.restore);
+    }
+
+    unsafe fn write(bytes: &[u8]) {
+        let state = &mut *STATE.0.get();
+
+        let output = &mut state.output;
+        state.encoder.write(bytes, |bytes| output.write(bytes));
+    }
+}
@@ -0,0 +1,54 @@
+  /* Entry points of the undefined instruction, prefetch abort and data abort exceptions */
+
+  .syntax unified
+
+  /* NOTE exceptions are always taken in ARM state on these cores (SCTLR.TE = 0) */
+  .arm
+
+  .section .text.UndefinedInstruction
+  .global UndefinedInstruction
+  .type UndefinedInstruction, %function
+UndefinedInstruction:
+    push    {r0-r12, lr}
+    mov     r0, #0
+    b       __dcc_exception_entry
+
+  .section .text.PrefetchAbort
+  .global PrefetchAbort
+  .type PrefetchAbort, %function
+PrefetchAbort:
+    push    {r0-r12, lr}
+    mov     r0, #1
+    b       __dcc_exception_entry
+
+  .section .text.DataAbort
+  .global DataAbort
+  .type DataAbort, %function
+DataAbort:
+    push    {r0-r12, lr}
+    mov     r0, #2
+    b       __dcc_exception_entry
+
+  /* Completes the `Frame` and calls `__dcc_exception_report(kind, &frame, spsr)`; r0 = kind */
+  .section .text.__dcc_exception_entry
+  .type __dcc_exception_entry, %function
+__dcc_exception_entry:
+    mrs     r3, spsr
+    /* read the banked SP and LR of the interrupted mode; User mode shares them with System mode,
+       which unlike User mode can switch back to this mode */
+    and     r4, r3, #0x1f
+    cmp     r4, #0x10
+    moveq   r4, #0x1f
+    mrs     r5, cpsr
+    bic     r6, r5, #0x1f
+    orr     r6, r6, r4
+    msr     cpsr_c, r6
+    /* NOTE r8-r12 are banked in FIQ mode so only r0-r7 can carry values out of it */
+    mov     r6, sp
+    mov     r7, lr
+    msr     cpsr_c, r5
+    /* NOTE the frame is 16 words so the stack stays 8-byte aligned */
+    push    {r6, r7}
+    mov     r1, sp
+    mov     r2, r3
+    bl      __dcc_exception_report
@@ -0,0 +1,37 @@
+use std::env;
+
+/// Prefixes of the M-profile targets, which have a different exception model
+const M_PROFILE: &[&str] = &["thumbv6m-", "thumbv7em-", "thumbv7m-", "thumbv8m."];
+
+/// Prefixes of the ARMv7-A, ARMv7-R and ARMv8-R targets, whose cores have the fault status and
+/// fault address registers and MPIDR; these also match the ARMv7-M targets, which are filtered out
+/// beforehand
+const ARMV7: &[&str] = &["armebv7r-", "armv7", "armv8r-", "thumbv7", "thumbv8r-"];
+
+fn main() {
+    let target = env::var("TARGET").unwrap();
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let os = env::var("CARGO_CFG_TARGET_OS").unwrap();
+
+    // the handlers are only provided for bare metal A-profile and R-profile cores; on Linux the
+    // kernel handles these exceptions
+    let handlers =
+        arch == "arm" && os == "none" && !M_PROFILE.iter().any(|prefix| target.starts_with(prefix));
+    println!("cargo:rustc-check-cfg=cfg(dcc_exception_handlers)");
+    if handlers {
+        println!("cargo:rustc-cfg=dcc_exception_handlers");
+    }
+
+    println!("cargo:rustc-check-cfg=cfg(dcc_exception_armv7)");
+    if handlers && ARMV7.iter().any(|prefix| target.starts_with(prefix)) {
+        println!("cargo:rustc-cfg=dcc_exception_armv7");
+    }
+
+    // R-profile cores have an MPU so their fault status codes differ from the A-profile ones
+    println!("cargo:rustc-check-cfg=cfg(dcc_exception_pmsa)");
+    if target.contains("v7r-") || target.contains("v8r-") {
+        println!("cargo:rustc-cfg=dcc_exception_pmsa");
+    }
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
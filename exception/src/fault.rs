@@ -0,0 +1,224 @@
+//! Decoding of the fault status and fault address registers (DFSR, DFAR, IFSR and IFAR)
+
+use core::{arch::asm, fmt};
+
+#[cfg(dcc_exception_pmsa)]
+use crate::mpu::{Operation, Violation};
+
+/// The fault status and fault address of an abort
+pub struct Fault {
+    status: u32,
+    address: u32,
+    /// `true` for prefetch aborts (IFSR / IFAR), `false` for data aborts (DFSR / DFAR)
+    instruction: bool,
+}
+
+/// What a fault status code means
+struct Cause {
+    name: &'static str,
+    /// Translation table level (or MPU lookup) that faulted, if the code encodes one
+    level: Option<u32>,
+    /// Whether the fault address register (and the WnR bit) hold valid values
+    precise: bool,
+    /// Whether the MPU rejected the access
+    #[cfg_attr(not(dcc_exception_pmsa), allow(dead_code))]
+    mpu: bool,
+}
+
+impl Cause {
+    const fn new(name: &'static str, precise: bool) -> Self {
+        Cause {
+            name,
+            level: None,
+            precise,
+            mpu: false,
+        }
+    }
+
+    const fn mpu(name: &'static str) -> Self {
+        Cause {
+            name,
+            level: None,
+            precise: true,
+            mpu: true,
+        }
+    }
+
+    const fn level(name: &'static str, level: u32) -> Self {
+        Cause {
+            name,
+            level: Some(level),
+            precise: true,
+            mpu: false,
+        }
+    }
+}
+
+/// Fault status register bit that selects the long-descriptor format
+const LPAE: u32 = 1 << 9;
+/// DFSR bit that is set when the abort was caused by a write
+const WNR: u32 = 1 << 11;
+
+impl Fault {
+    /// Reads DFSR and DFAR
+    pub fn data() -> Self {
+        let status: u32;
+        let address: u32;
+        unsafe {
+            asm!(
+                "mrc p15, 0, {}, c5, c0, 0",
+                "mrc p15, 0, {}, c6, c0, 0",
+                out(reg) status,
+                out(reg) address,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+
+        Fault {
+            status,
+            address,
+            instruction: false,
+        }
+    }
+
+    /// Reads IFSR and IFAR
+    pub fn instruction() -> Self {
+        let status: u32;
+        let address: u32;
+        unsafe {
+            asm!(
+                "mrc p15, 0, {}, c5, c0, 1",
+                "mrc p15, 0, {}, c6, c0, 2",
+                out(reg) status,
+                out(reg) address,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+
+        Fault {
+            status,
+            address,
+            instruction: true,
+        }
+    }
+
+    /// The access that the MPU rejected, if that's what caused the abort
+    #[cfg(dcc_exception_pmsa)]
+    pub fn violation(&self, privileged: bool) -> Option<Violation> {
+        if !self.cause().mpu {
+            return None;
+        }
+
+        let operation = if self.instruction {
+            Operation::Fetch
+        } else if self.status & WNR != 0 {
+            Operation::Write
+        } else {
+            Operation::Read
+        };
+
+        Some(Violation {
+            address: self.address,
+            operation,
+            privileged,
+        })
+    }
+
+    fn cause(&self) -> Cause {
+        if self.status & LPAE != 0 {
+            long_descriptor(self.status & 0x3f)
+        } else {
+            short_descriptor(((self.status >> 6) & 0x10) | (self.status & 0xf))
+        }
+    }
+}
+
+/// Decodes the 5-bit FS field of the short-descriptor format (ARMv7-A without LPAE, ARMv7-R)
+fn short_descriptor(fs: u32) -> Cause {
+    // NOTE the MPU of the R-profile cores reuses some of the A-profile codes
+    let pmsa = cfg!(dcc_exception_pmsa);
+
+    match fs {
+        0b00000 if pmsa => Cause::mpu("background fault"),
+        0b01101 if pmsa => Cause::mpu("permission fault"),
+        0b00101 if !pmsa => Cause::level("translation fault", 1),
+        0b00111 if !pmsa => Cause::level("translation fault", 2),
+        0b00011 if !pmsa => Cause::level("access flag fault", 1),
+        0b00110 if !pmsa => Cause::level("access flag fault", 2),
+        0b01001 if !pmsa => Cause::level("domain fault", 1),
+        0b01011 if !pmsa => Cause::level("domain fault", 2),
+        0b01101 if !pmsa => Cause::level("permission fault", 1),
+        0b01111 if !pmsa => Cause::level("permission fault", 2),
+        0b01100 if !pmsa => Cause::level("external abort on translation table walk", 1),
+        0b01110 if !pmsa => Cause::level("external abort on translation table walk", 2),
+        0b11100 if !pmsa => Cause::level("parity error on translation table walk", 1),
+        0b11110 if !pmsa => Cause::level("parity error on translation table walk", 2),
+        0b00100 if !pmsa => Cause::new("instruction cache maintenance fault", true),
+        0b10000 if !pmsa => Cause::new("TLB conflict abort", true),
+        0b00001 => Cause::new("alignment fault", true),
+        0b00010 => Cause::new("debug event", false),
+        0b01000 => Cause::new("synchronous external abort", true),
+        0b11001 => Cause::new("synchronous parity error", true),
+        0b10110 => Cause::new("asynchronous external abort", false),
+        0b11000 => Cause::new("asynchronous parity error", false),
+        0b10100 => Cause::new("lockdown abort", false),
+        0b11010 => Cause::new("coprocessor abort", false),
+        _ => Cause::new("unknown fault", false),
+    }
+}
+
+/// Decodes the 6-bit STATUS field of the long-descriptor format (ARMv7-A with LPAE, ARMv8-R)
+fn long_descriptor(status: u32) -> Cause {
+    let pmsa = cfg!(dcc_exception_pmsa);
+    let level = status & 0b11;
+
+    match status {
+        // NOTE on ARMv8-R level 0 translation and permission faults come from the MPU
+        0b000100 if pmsa => Cause::mpu("background fault"),
+        0b001100 if pmsa => Cause::mpu("permission fault"),
+        0b000100..=0b000111 => Cause::level("translation fault", level),
+        0b001000..=0b001011 => Cause::level("access flag fault", level),
+        0b001100..=0b001111 => Cause::level("permission fault", level),
+        0b010100..=0b010111 => Cause::level("external abort on translation table walk", level),
+        0b011100..=0b011111 => Cause::level("parity error on translation table walk", level),
+        0b111100..=0b111111 => Cause::level("domain fault", level),
+        0b100001 => Cause::new("alignment fault", true),
+        0b100010 => Cause::new("debug event", false),
+        0b010000 => Cause::new("synchronous external abort", true),
+        0b011000 => Cause::new("synchronous parity error", true),
+        0b010001 => Cause::new("asynchronous external abort", false),
+        0b011001 => Cause::new("asynchronous parity error", false),
+        0b110000 => Cause::new("TLB conflict abort", true),
+        0b110100 => Cause::new("lockdown abort", false),
+        0b111010 => Cause::new("coprocessor abort", false),
+        _ => Cause::new("unknown fault", false),
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cause = self.cause();
+
+        f.write_str(cause.name)?;
+        if let Some(level) = cause.level {
+            write!(f, " (level {})", level)?;
+        }
+
+        // NOTE the fault address and the access direction are UNKNOWN for the asynchronous
+        // aborts and the debug events
+        if cause.precise {
+            write!(f, " at {:#010x}", self.address)?;
+
+            if self.instruction {
+                f.write_str(", instruction fetch")?;
+            } else if self.status & WNR != 0 {
+                f.write_str(", write access")?;
+            } else {
+                f.write_str(", read access")?;
+            }
+        }
+
+        let name = if self.instruction { "IFSR" } else { "DFSR" };
+        writeln!(f, " ({} = {:#010x})", name, self.status)
+    }
+}
@@ -0,0 +1,171 @@
+//! Exception entry points and the report they print
+
+use core::{
+    fmt::{self, Write},
+    sync::atomic::{self, Ordering},
+};
+
+use arm_dcc::Writer;
+
+#[cfg(dcc_exception_armv7)]
+use crate::{fault::Fault, mpidr::CoreId};
+use crate::{instruction::Instruction, state::State};
+
+// NOTE `raw` because the register lists in braces are not format placeholders
+core::arch::global_asm!(include_str!("../asm.s"), options(raw));
+
+/// Names of the reported registers, in report order
+const NAMES: [&str; 17] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc", "spsr",
+];
+
+/// The exceptions that have an entry point in `asm.s`; the discriminant is the `kind` argument of
+/// `__dcc_exception_report`
+#[derive(Clone, Copy)]
+#[repr(u32)]
+enum Kind {
+    UndefinedInstruction = 0,
+    PrefetchAbort = 1,
+    DataAbort = 2,
+}
+
+impl Kind {
+    fn from_u32(kind: u32) -> Self {
+        match kind {
+            0 => Kind::UndefinedInstruction,
+            1 => Kind::PrefetchAbort,
+            _ => Kind::DataAbort,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::UndefinedInstruction => "undefined instruction",
+            Kind::PrefetchAbort => "prefetch abort",
+            Kind::DataAbort => "data abort",
+        }
+    }
+}
+
+/// Registers saved by the entry points in `asm.s`
+#[repr(C)]
+struct Frame {
+    /// SP of the interrupted mode
+    sp: u32,
+    /// LR of the interrupted mode
+    lr: u32,
+    r: [u32; 13],
+    /// LR of the exception mode
+    return_address: u32,
+}
+
+/// What the interrupted code was doing when the exception was taken
+struct Report<'a> {
+    kind: Kind,
+    frame: &'a Frame,
+    spsr: u32,
+    /// Fault status and address of the aborts
+    #[cfg(dcc_exception_armv7)]
+    fault: Option<Fault>,
+}
+
+impl Report<'_> {
+    /// Address of the instruction that caused the exception
+    fn pc(&self) -> u32 {
+        // the offset between the return address and the faulting instruction depends on the
+        // exception and, for undefined instructions, on the instruction set state (SPSR.T)
+        let offset = match self.kind {
+            Kind::UndefinedInstruction if State(self.spsr).thumb() => 2,
+            Kind::UndefinedInstruction | Kind::PrefetchAbort => 4,
+            Kind::DataAbort => 8,
+        };
+
+        self.frame.return_address.wrapping_sub(offset)
+    }
+}
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pc = self.pc();
+        let state = State(self.spsr);
+        #[cfg(dcc_exception_armv7)]
+        write!(f, "{}", CoreId::read())?;
+        writeln!(f, "{} at {:#010x} ({})", self.kind.as_str(), pc, state)?;
+        if let Kind::UndefinedInstruction = self.kind {
+            // NOTE(unsafe) the core has just fetched the instruction at `pc`
+            let instruction = unsafe { Instruction::read(pc, &state) };
+            writeln!(f, "instruction {}", instruction)?;
+        }
+        #[cfg(dcc_exception_armv7)]
+        if let Some(fault) = &self.fault {
+            write!(f, "{}", fault)?;
+
+            #[cfg(dcc_exception_pmsa)]
+            if let Some(violation) = fault.violation(state.privileged()) {
+                write!(f, "{}", violation)?;
+            }
+        }
+
+        let frame = self.frame;
+        let rest = [frame.sp, frame.lr, pc, self.spsr];
+        let regs = frame.r.iter().chain(rest.iter());
+        for (i, (name, value)) in NAMES.iter().zip(regs).enumerate() {
+            let sep = if i % 4 == 3 || i == NAMES.len() - 1 {
+                "\n"
+            } else {
+                "  "
+            };
+            write!(f, "{:>4} = {:#010x}{}", name, value, sep)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[no_mangle]
+extern "C" fn __dcc_exception_report(kind: u32, frame: &Frame, spsr: u32) -> ! {
+    let kind = Kind::from_u32(kind);
+    let report = Report {
+        kind,
+        frame,
+        spsr,
+        #[cfg(dcc_exception_armv7)]
+        fault: match kind {
+            Kind::UndefinedInstruction => None,
+            Kind::PrefetchAbort => Some(Fault::instruction()),
+            Kind::DataAbort => Some(Fault::data()),
+        },
+    };
+
+    // NOTE this operation never returns `Err`
+    write!(Writer, "{}", report).ok();
+
+    #[cfg(feature = "addresses")]
+    arm_dcc::address::write_addresses(&[report.pc(), frame.lr]);
+
+    #[cfg(feature = "coredump")]
+    {
+        use arm_dcc::coredump::{self, Reason, REGISTERS};
+
+        let mut registers = [0; REGISTERS];
+        registers[..13].copy_from_slice(&frame.r);
+        registers[13] = frame.sp;
+        registers[14] = frame.lr;
+        registers[15] = report.pc();
+        registers[16] = spsr;
+
+        let reason = match kind {
+            Kind::UndefinedInstruction => Reason::UndefinedInstruction,
+            Kind::PrefetchAbort => Reason::PrefetchAbort,
+            Kind::DataAbort => Reason::DataAbort,
+        };
+        coredump::write(reason, &registers);
+    }
+
+    loop {
+        // NOTE the compiler_fence prevents this loop from turning into an abort instruction when
+        // this crate is compiled with optimizations
+        atomic::compiler_fence(Ordering::SeqCst)
+    }
+}
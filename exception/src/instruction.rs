@@ -0,0 +1,50 @@
+//! The instruction that raised an undefined instruction exception
+
+use core::{fmt, ptr};
+
+use crate::state::State;
+
+/// An ARM or Thumb instruction, as it's encoded in memory
+pub enum Instruction {
+    Arm(u32),
+    Thumb16(u16),
+    /// The first halfword is in the upper 16 bits
+    Thumb32(u32),
+}
+
+impl Instruction {
+    /// Reads the instruction at `address`, which was executed in the given `state`
+    ///
+    /// # Safety
+    ///
+    /// `address` must be the address of an instruction
+    pub unsafe fn read(address: u32, state: &State) -> Self {
+        // NOTE instructions are always little endian; on big endian (BE-8) cores only data is
+        // big endian
+        if state.thumb() {
+            let first = u16::from_le(ptr::read_volatile(address as *const u16));
+
+            // the first halfword of a 32-bit Thumb instruction starts with 0b11101, 0b11110 or
+            // 0b11111
+            if first >> 11 >= 0b11101 {
+                let second =
+                    u16::from_le(ptr::read_volatile(address.wrapping_add(2) as *const u16));
+
+                Instruction::Thumb32(u32::from(first) << 16 | u32::from(second))
+            } else {
+                Instruction::Thumb16(first)
+            }
+        } else {
+            Instruction::Arm(u32::from_le(ptr::read_volatile(address as *const u32)))
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Arm(word) | Instruction::Thumb32(word) => write!(f, "{:#010x}", word),
+            Instruction::Thumb16(halfword) => write!(f, "{:#06x}", halfword),
+        }
+    }
+}
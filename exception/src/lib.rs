@@ -0,0 +1,112 @@
+//! Report processor exceptions to the host using the Debug Communication Channel (DCC)
+//!
+//! This crate provides handlers for the undefined instruction, prefetch abort and data abort
+//! exceptions of A-profile and R-profile cores, e.g. Cortex-R5. Each handler reports the
+//! exception, the address of the instruction that caused it (the return address adjusted by the
+//! offset of the exception, so no arithmetic is needed on the host) and the instruction set state
+//! and mode the core was in, followed by a dump of r0 - r12, SP and LR of the interrupted code,
+//! the faulting PC and SPSR, and then halts the core by spinning forever. It's the exception
+//! counterpart of `panic-dcc`.
+//!
+//! The undefined instruction reports also include the encoding of the offending instruction,
+//! e.g. "instruction 0xe7f000f0". An unexpected state, or an encoding that doesn't match it,
+//! usually means that ARM code was called as Thumb code, or vice versa.
+//!
+//! On ARMv7 and ARMv8-R cores the first line of the report is tagged with the affinity fields of
+//! MPIDR, e.g. `[core 0.0.1]`, so the reports of the cores of a multi-core system (e.g. a
+//! Cortex-R5 in split mode) can be told apart when their logs are merged. The abort reports also
+//! include the decoded fault status register (DFSR or IFSR) and, when it's valid, the fault
+//! address (DFAR or IFAR), e.g. "translation fault (level 2) at 0x40010000, write access". Both
+//! the short-descriptor and the long-descriptor (LPAE, ARMv8-R) formats are understood. On
+//! R-profile cores the background and permission faults raised by the MPU can also be decoded
+//! against the MPU configuration of the application; see the `mpu` module.
+//!
+//! The handlers are the `UndefinedInstruction`, `PrefetchAbort` and `DataAbort` symbols; the
+//! vector table must branch to them. Linking this crate is enough to make them available.
+//!
+//! # Example
+//!
+//! ## Device side
+//!
+//! ``` ignore
+//! use dcc_exception as _;
+//!
+//! core::arch::global_asm!(
+//!     ".section .vector_table, \"ax\"",
+//!     ".arm",
+//!     "ldr pc, =Reset",
+//!     "ldr pc, =UndefinedInstruction",
+//!     "ldr pc, =SupervisorCall",
+//!     "ldr pc, =PrefetchAbort",
+//!     "ldr pc, =DataAbort",
+//!     "nop",
+//!     "ldr pc, =Irq",
+//!     "ldr pc, =Fiq",
+//! );
+//! ```
+//!
+//! ## Host side
+//!
+//! ``` text
+//! $ tail -f dcc.log
+//! [core 0.0.0] data abort at 0x00100a4c (ARM state, System mode)
+//! background fault at 0x40010000, write access (DFSR = 0x00000800)
+//!   r0 = 0x40010000    r1 = 0x0000002a    r2 = 0x00000012    r3 = 0x00000000
+//!   r4 = 0x0010c6d4    r5 = 0x00000000    r6 = 0x00000000    r7 = 0x0010ffd8
+//!   r8 = 0x00000000    r9 = 0x00000000   r10 = 0x00000000   r11 = 0x00000000
+//!  r12 = 0x00000000    sp = 0x0010ffb8    lr = 0x001003f0    pc = 0x00100a4c
+//! spsr = 0x6000001f
+//! ```
+//!
+//! See the documentation of `panic-dcc` for how to read the DCC on the host.
+//!
+//! # Requirements
+//!
+//! - The stack pointers of the Undefined and Abort modes must point to valid stacks; the handlers
+//!   use 64 bytes of them, plus what formatting the report needs.
+//!
+//! - Exceptions must be taken in ARM state (SCTLR.TE = 0), which is the reset value on most cores.
+//!
+//! # Supported Rust version
+//!
+//! This crate is guaranteed to compile on stable Rust 1.59 and up on the bare metal A-profile and
+//! R-profile targets (e.g. `armv7r-none-eabi`, `armv7a-none-eabihf`, `armv4t-none-eabi` and their
+//! Thumb variants). On other targets, including the Cortex-M and Linux ones, this crate provides
+//! no handlers.
+//!
+//! # Optional features
+//!
+//! ## `addresses`
+//!
+//! After the report, sends the faulting PC and the LR of the interrupted code as code addresses
+//! (see `arm_dcc::address`), which `arm-dcc-host --elf` prints as function names and source
+//! locations. These are binary frames so they show up as garbage in plain text DCC logs.
+//!
+//! ## `coredump`
+//!
+//! After the report, streams a core dump with the registers of the interrupted code and the
+//! memory regions registered with `arm_dcc::coredump::set_regions`; the `dcc-coredump` tool
+//! converts it into an ELF core file that GDB can load.
+//!
+//! ## `extern-asm`
+//!
+//! Enables the `extern-asm` feature of `arm-dcc`, which makes it call assembly routines that are
+//! shipped as prebuilt static libraries instead of using `asm!` and `global_asm!`. Note that the
+//! handlers of this crate use `global_asm!` regardless.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+#![no_std]
+
+// NOTE `dcc_exception_armv7` and `dcc_exception_handlers` are set by the build script
+#[cfg(dcc_exception_armv7)]
+mod fault;
+#[cfg(dcc_exception_handlers)]
+mod handlers;
+#[cfg(dcc_exception_handlers)]
+mod instruction;
+#[cfg(dcc_exception_armv7)]
+mod mpidr;
+pub mod mpu;
+#[cfg(dcc_exception_handlers)]
+mod state;
@@ -0,0 +1,35 @@
+//! Identification of the core that took the exception
+
+use core::{arch::asm, fmt};
+
+/// The affinity fields of the Multiprocessor Affinity Register (MPIDR)
+pub struct CoreId(u32);
+
+impl CoreId {
+    /// Reads MPIDR
+    pub fn read() -> Self {
+        let mpidr: u32;
+        unsafe {
+            asm!(
+                "mrc p15, 0, {}, c0, c0, 5",
+                out(reg) mpidr,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+
+        CoreId(mpidr)
+    }
+}
+
+/// Formats the tag that prefixes the report, `[core Aff2.Aff1.Aff0] `
+impl fmt::Display for CoreId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[core {}.{}.{}] ",
+            (self.0 >> 16) & 0xff,
+            (self.0 >> 8) & 0xff,
+            self.0 & 0xff
+        )
+    }
+}
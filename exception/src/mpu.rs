@@ -0,0 +1,172 @@
+//! Decoding of MPU faults against the application's MPU configuration
+//!
+//! The reports of the background faults and the permission faults raised by the MPU of R-profile
+//! cores can name the MPU region that the faulting access hit, e.g.
+//! "MPU: write to read-only region 3 (0x00200000 - 0x0020ffff)". For that the application must
+//! describe its MPU configuration with a `RegionTable` and register it with `set_region_table`.
+//!
+//! ```
+//! use dcc_exception::mpu::{self, Access, Region, RegionTable};
+//!
+//! struct Regions;
+//!
+//! static REGIONS: [Region; 2] = [
+//!     Region {
+//!         base: 0x0000_0000,
+//!         limit: 0x0003_ffff,
+//!         access: Access::ReadOnly,
+//!         execute_never: false,
+//!     },
+//!     Region {
+//!         base: 0x0008_0000,
+//!         limit: 0x0009_ffff,
+//!         access: Access::ReadWrite,
+//!         execute_never: true,
+//!     },
+//! ];
+//!
+//! impl RegionTable for Regions {
+//!     const REGIONS: usize = 2;
+//!
+//!     fn region(index: usize) -> Option<Region> {
+//!         REGIONS.get(index).cloned()
+//!     }
+//! }
+//!
+//! mpu::set_region_table::<Regions>();
+//! ```
+
+#[cfg(dcc_exception_pmsa)]
+use core::fmt;
+
+use arm_dcc::FnCell;
+
+// finds the region that contains an address; see `lookup`
+type Lookup = fn(u32) -> Option<(usize, Region)>;
+
+static LOOKUP: FnCell<Lookup> = FnCell::new();
+
+/// Access permissions of an MPU region, as encoded in the AP field of PMSAv7
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    /// No access
+    None,
+    /// Privileged read-write; no unprivileged access
+    PrivilegedReadWrite,
+    /// Privileged read-write; unprivileged read-only
+    PrivilegedReadWriteUserReadOnly,
+    /// Read-write
+    ReadWrite,
+    /// Privileged read-only; no unprivileged access
+    PrivilegedReadOnly,
+    /// Read-only
+    ReadOnly,
+}
+
+/// An enabled MPU region
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    /// Address of the first byte of the region
+    pub base: u32,
+    /// Address of the last byte of the region
+    pub limit: u32,
+    /// Access permissions
+    pub access: Access,
+    /// Whether instruction fetches from the region fault
+    pub execute_never: bool,
+}
+
+/// The MPU configuration of the application
+///
+/// As on the MPU, when several regions contain an address the one with the highest index wins.
+/// Subregions are not modeled; disabled regions are reported as `None`.
+pub trait RegionTable {
+    /// Number of regions
+    const REGIONS: usize;
+
+    /// Returns region `index`, or `None` if it's disabled
+    fn region(index: usize) -> Option<Region>;
+}
+
+/// Registers the MPU configuration that the abort handlers will use to decode MPU faults
+pub fn set_region_table<T>()
+where
+    T: RegionTable,
+{
+    LOOKUP.set(lookup::<T> as Lookup)
+}
+
+/// Unregisters the table set with `set_region_table`
+pub fn clear_region_table() {
+    LOOKUP.clear()
+}
+
+fn lookup<T>(address: u32) -> Option<(usize, Region)>
+where
+    T: RegionTable,
+{
+    (0..T::REGIONS).rev().find_map(|index| {
+        T::region(index)
+            .filter(|region| region.base <= address && address <= region.limit)
+            .map(|region| (index, region))
+    })
+}
+
+/// How the faulting access touched memory
+#[cfg(dcc_exception_pmsa)]
+#[derive(Clone, Copy)]
+pub(crate) enum Operation {
+    Read,
+    Write,
+    Fetch,
+}
+
+/// An access rejected by the MPU
+#[cfg(dcc_exception_pmsa)]
+pub(crate) struct Violation {
+    pub address: u32,
+    pub operation: Operation,
+    /// Whether the access was made from a privileged mode
+    pub privileged: bool,
+}
+
+#[cfg(dcc_exception_pmsa)]
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lookup = match LOOKUP.get() {
+            Some(lookup) => lookup,
+            None => return Ok(()),
+        };
+
+        f.write_str("MPU: ")?;
+        if !self.privileged {
+            f.write_str("unprivileged ")?;
+        }
+        f.write_str(match self.operation {
+            Operation::Read => "read from ",
+            Operation::Write => "write to ",
+            Operation::Fetch => "instruction fetch from ",
+        })?;
+
+        match lookup(self.address) {
+            Some((index, region)) => {
+                let kind = match (self.operation, region.access) {
+                    (Operation::Fetch, _) if region.execute_never => "execute-never",
+                    (_, Access::None) => "no-access",
+                    (_, Access::PrivilegedReadWrite) => "privileged",
+                    (_, Access::PrivilegedReadWriteUserReadOnly) => "user read-only",
+                    (_, Access::ReadWrite) => "read-write",
+                    (_, Access::PrivilegedReadOnly) => "privileged read-only",
+                    (_, Access::ReadOnly) => "read-only",
+                };
+
+                writeln!(
+                    f,
+                    "{} region {} ({:#010x} - {:#010x})",
+                    kind, index, region.base, region.limit
+                )
+            }
+            None => writeln!(f, "an address outside of every region"),
+        }
+    }
+}
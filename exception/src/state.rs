@@ -0,0 +1,59 @@
+//! Processor mode and instruction set state, as recorded in SPSR
+
+use core::fmt;
+
+/// SPSR bit that is set in Thumb (and ThumbEE) state
+const T: u32 = 1 << 5;
+/// SPSR bit that is set in Jazelle (and ThumbEE) state
+const J: u32 = 1 << 24;
+
+/// The mode and the instruction set state of the interrupted code
+pub struct State(pub u32);
+
+impl State {
+    /// Whether the interrupted code runs in Thumb (or ThumbEE) state
+    pub fn thumb(&self) -> bool {
+        self.0 & T != 0
+    }
+
+    /// Whether the interrupted code runs in a privileged mode, i.e. not in User mode
+    #[cfg(dcc_exception_pmsa)]
+    pub fn privileged(&self) -> bool {
+        self.0 & 0x1f != 0x10
+    }
+
+    fn instruction_set(&self) -> &'static str {
+        match (self.0 & J != 0, self.thumb()) {
+            (false, false) => "ARM",
+            (false, true) => "Thumb",
+            (true, false) => "Jazelle",
+            (true, true) => "ThumbEE",
+        }
+    }
+
+    fn mode(&self) -> Option<&'static str> {
+        Some(match self.0 & 0x1f {
+            0x10 => "User",
+            0x11 => "FIQ",
+            0x12 => "IRQ",
+            0x13 => "Supervisor",
+            0x16 => "Monitor",
+            0x17 => "Abort",
+            0x1a => "Hyp",
+            0x1b => "Undefined",
+            0x1f => "System",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} state, ", self.instruction_set())?;
+
+        match self.mode() {
+            Some(mode) => write!(f, "{} mode", mode),
+            None => write!(f, "unknown mode {:#04x}", self.0 & 0x1f),
+        }
+    }
+}
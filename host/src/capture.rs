@@ -0,0 +1,92 @@
+//! Capture file formats
+//!
+//! - `Format::Binary`: each word as 4 little endian bytes, e.g. what a debugger script that drains
+//!   the DCC writes to a file
+//! - `Format::Hex`: words written as hexadecimal numbers, with or without a `0x` prefix, separated
+//!   by whitespace, e.g. copied from a debugger console. Tokens that end with a colon, like the
+//!   addresses of a memory dump, and the rest of a line after a `#`, are skipped.
+
+use core::fmt;
+
+/// Format of a capture file
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// 4 little endian bytes per word
+    Binary,
+    /// Hexadecimal text
+    Hex,
+}
+
+/// Error returned by `words`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// A binary capture whose length is not a multiple of 4; the last word is incomplete
+    Truncated,
+    /// A token of a hexadecimal capture, found in the given line (starting at 1), that's not a
+    /// 32-bit hexadecimal number
+    Hex {
+        /// Line of the capture file
+        line: usize,
+        /// The offending token
+        token: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => f.write_str("the capture ends in the middle of a word"),
+            Error::Hex { line, token } => {
+                write!(f, "line {}: `{}` is not a hexadecimal word", line, token)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Extracts the words from the contents of a capture file
+///
+/// ```
+/// use arm_dcc_host::capture::{self, Format};
+///
+/// let words = capture::words(b"0xdcc10002 0x00006948 # \"Hi\"\n", Format::Hex).unwrap();
+/// assert_eq!(words, [0xdcc1_0002, 0x6948]);
+/// ```
+pub fn words(bytes: &[u8], format: Format) -> Result<Vec<u32>, Error> {
+    match format {
+        Format::Binary => {
+            let chunks = bytes.chunks_exact(4);
+
+            if !chunks.remainder().is_empty() {
+                return Err(Error::Truncated);
+            }
+
+            Ok(chunks
+                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect())
+        }
+        Format::Hex => {
+            let text = String::from_utf8_lossy(bytes);
+            let mut words = Vec::new();
+            for (i, line) in text.lines().enumerate() {
+                let line = line.split('#').next().unwrap_or("");
+                for token in line
+                    .split_whitespace()
+                    .filter(|token| !token.ends_with(':'))
+                {
+                    let digits = token
+                        .strip_prefix("0x")
+                        .or_else(|| token.strip_prefix("0X"))
+                        .unwrap_or(token);
+                    let word = u32::from_str_radix(digits, 16).map_err(|_| Error::Hex {
+                        line: i + 1,
+                        token: token.to_string(),
+                    })?;
+                    words.push(word);
+                }
+            }
+            Ok(words)
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Host side decoder of the Debug Communication Channel (DCC) output of `arm-dcc` devices
+//!
+//! `arm_dcc::decode` splits the DCC word stream into its encodings; this crate builds on it to turn
+//! a capture of the raw words into what the device meant to print:
+//!
+//! - `capture` reads the words out of a capture file, binary or hexadecimal text
+//! - `session` records the words of a live session along with when they were received, to decode
+//!   them again later
+//! - `Stream` decodes them into `Event`s: text (packed, word-extended or compressed, see the
+//!   `compress` feature), binary log records (see `render`), channel data, the handshake frame,
+//!   the exit status and the corrupted and lost frames reported by the `crc` and `sequence`
+//!   features
+//! - `openocd` decodes the output of devices that have the `openocd` feature enabled, which uses
+//!   the protocol of OpenOCD's `libdcc` instead
+//! - `symbolize` looks the code addresses sent with `arm_dcc::address` up in the ELF file of the
+//!   program; `Stream` prints them as function names and source locations with
+//!   `Stream::set_symbolizer`, and in hexadecimal otherwise
+//! - `poll` reads the DCC through the debug registers of the core, e.g. with `tcl`, a client of a
+//!   running OpenOCD instance
+//! - `pty` creates pseudo-terminals that carry the text output of a device, for tools that expect
+//!   a UART, and `server` serves it over TCP to any number of clients
+//!
+//! The `arm-dcc-host` tool does all of the above for a capture file, or live through OpenOCD; see
+//! its `--help`.
+//!
+//! ```
+//! use arm_dcc_host::{Event, Stream};
+//!
+//! let mut stream = Stream::new();
+//! let mut text = Vec::new();
+//!
+//! // "Hi" word-extended, then "there!" packed
+//! let words = [0x48, 0x69, arm_dcc::PACKED_TAG | 6, 0x7265_6874, 0x0000_2165];
+//! for word in words.iter() {
+//!     if let Some(Event::Text(bytes)) = stream.push(*word) {
+//!         text.extend_from_slice(&bytes);
+//!     }
+//! }
+//!
+//! assert_eq!(text, b"Hithere!");
+//! ```
+//!
+//! Captures of devices that use `defmt-dcc` are decoded with that crate's `Demux`, which needs the
+//! ELF file of the program.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use arm_dcc::{
+    compress::Decompressor,
+    decode::{Decoder, Item},
+    handshake::Handshake,
+};
+
+use crate::{
+    render::{Output, Record, Renderer},
+    symbolize::Symbolizer,
+};
+
+pub mod capture;
+pub mod openocd;
+pub mod poll;
+#[cfg(unix)]
+pub mod pty;
+pub mod render;
+pub mod server;
+pub mod session;
+pub mod symbolize;
+pub mod tcl;
+
+/// What a device sent, as decoded by `Stream`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// Text to print: text sent by the device, decompressed if needed
+    Text(Vec<u8>),
+    /// A binary log record; see `render`
+    Record(Record),
+    /// Data sent to the channel with the given ID; see `arm_dcc::channel`
+    Channel(u8, Vec<u8>),
+    /// The handshake frame sent by `arm_dcc::init`
+    Handshake(Handshake),
+    /// The exit status of the program; see `arm_dcc::exit`
+    Exit(i32),
+    /// A frame was corrupted; see `arm_dcc::decode::Item::BadCrc`
+    BadCrc,
+    /// The given number of frames were lost; see `arm_dcc::decode::Item::Lost`
+    Lost(u32),
+    /// A word that's not part of any known encoding
+    Unknown(u32),
+}
+
+/// Stateful decoder of the DCC word stream of a device
+#[derive(Default)]
+pub struct Stream {
+    decoder: Decoder,
+    decompressor: Decompressor,
+    renderer: Renderer,
+    symbolizer: Option<Symbolizer>,
+}
+
+impl Stream {
+    /// Creates a decoder for a device that sends a handshake frame (see `arm_dcc::init`), or that
+    /// has neither the `crc` nor the `sequence` feature enabled
+    pub fn new() -> Self {
+        Stream::with_features(0)
+    }
+
+    /// Creates a decoder for a device that has the given `arm_dcc::handshake::FEATURE_*` bits set
+    /// but doesn't send a handshake frame
+    pub fn with_features(features: u32) -> Self {
+        Stream {
+            decoder: Decoder::with_features(features),
+            decompressor: Decompressor::new(),
+            renderer: Renderer::new(),
+            symbolizer: None,
+        }
+    }
+
+    /// Prints the addresses sent with `arm_dcc::address::write_addresses` as function names and
+    /// source locations, looked up by `symbolizer`, instead of in hexadecimal
+    pub fn set_symbolizer(&mut self, symbolizer: Symbolizer) {
+        self.symbolizer = Some(symbolizer);
+    }
+
+    /// Feeds the next word of the capture into the decoder
+    ///
+    /// Returns `None` if the word doesn't complete anything, e.g. it's a header word or part of a
+    /// log record
+    pub fn push(&mut self, word: u32) -> Option<Event> {
+        let item = self.decoder.push(word)?;
+
+        match item {
+            Item::Compressed(chunk) => {
+                let mut text = Vec::new();
+                self.decompressor
+                    .push(chunk.as_bytes(), |byte| text.push(byte));
+
+                if text.is_empty() {
+                    None
+                } else {
+                    Some(Event::Text(text))
+                }
+            }
+            Item::Channel(id, chunk) => Some(Event::Channel(id, chunk.as_bytes().to_vec())),
+            Item::Handshake(handshake) => {
+                self.decompressor.reset();
+                Some(Event::Handshake(handshake))
+            }
+            Item::Exit(status) => Some(Event::Exit(status)),
+            Item::Address(index, address) => Some(Event::Text(self.address(index, address))),
+            // NOTE the renderer drops the log record that's being received, if any, and the
+            // compressed text resumes with the next frame
+            Item::BadCrc => {
+                self.renderer.push(item);
+                self.decompressor.reset();
+                Some(Event::BadCrc)
+            }
+            Item::Lost(frames) => {
+                self.renderer.push(item);
+                self.decompressor.reset();
+                Some(Event::Lost(frames))
+            }
+            Item::Unknown(word) => {
+                self.renderer.push(item);
+                Some(Event::Unknown(word))
+            }
+            Item::Text(_)
+            | Item::Word(_)
+            | Item::Bytes(_)
+            | Item::Value(_)
+            | Item::RecordBytes(_)
+            | Item::Record(_) => self.renderer.push_record(item).map(|output| match output {
+                Output::Text(text) => Event::Text(text),
+                Output::Record(record) => Event::Record(record),
+            }),
+        }
+    }
+
+    // one line per frame that contains the address, e.g. `   1: main (src/main.rs:12)`
+    fn address(&self, index: u16, address: u32) -> Vec<u8> {
+        let frames = self
+            .symbolizer
+            .as_ref()
+            .map(|symbolizer| {
+                // NOTE bit 0 of Thumb code addresses is set; return addresses are looked up one
+                // byte before so they land on the call instruction
+                let mut lookup = u64::from(address & !1);
+                if index != 0 && lookup != 0 {
+                    lookup -= 1;
+                }
+                symbolizer.lookup(lookup)
+            })
+            .unwrap_or_default();
+
+        if frames.is_empty() {
+            return format!("{:>4}: {:#010x}\n", index, address).into_bytes();
+        }
+
+        let mut text = String::new();
+        for (i, frame) in frames.iter().enumerate() {
+            if i == 0 {
+                text.push_str(&format!("{:>4}: {}\n", index, frame));
+            } else {
+                // NOTE the function was inlined into this one
+                text.push_str(&format!("      {}\n", frame));
+            }
+        }
+        text.into_bytes()
+    }
+}
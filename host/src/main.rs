@@ -0,0 +1,898 @@
+//! Decodes the Debug Communication Channel (DCC) output of an `arm-dcc` device
+//!
+//! Usage:
+//!
+//! - `arm-dcc-host [--hex] [--openocd] [--features <bits>] [--channels <dir>] [--elf <file>]..
+//!   [--labels <names>] [--format <format>] <capture>..`
+//! - `arm-dcc-host --connect [<address>] [--dap <name>] [--ap <index>] [--ready] [--capture
+//!   <file>] [--openocd] [--features <bits>] [--channels <dir>] [--elf <file>].. [--labels
+//!   <names>] [--format <format>] [--timestamps] [--pty] [--listen <address>] [--record <file>]
+//!   <base>..`
+//! - `arm-dcc-host --replay [--realtime] [--openocd] [--features <bits>] [--channels <dir>] [--elf
+//!   <file>].. [--labels <names>] [--format <format>] [--timestamps] [--pty] [--listen <address>]
+//!   <session>`
+//!
+//! `<capture>` holds the raw words received from the DCC, each one as 4 little endian bytes, or,
+//! with `--hex`, as hexadecimal text (see the `arm_dcc_host::capture` module). Text, including the
+//! compressed text and the binary log records, is printed to stdout; the handshake frame and
+//! corrupted, lost and unknown words are reported on stderr.
+//!
+//! With `--connect` the words are instead read live from a running OpenOCD instance, through its
+//! Tcl RPC server (`localhost:6666` by default; see the `arm_dcc_host::tcl` module), and decoded as
+//! they arrive, until the tool is interrupted. `<base>` is the address of the debug register block
+//! of the core as seen from memory AP `--ap` (1 by default) of DAP `--dap` (the first DAP by
+//! default), e.g. `0x80030000` for the first Cortex-R5 of a Zynq UltraScale+. `--ready` sends
+//! `arm_dcc::handshake::HOST_READY` to the device first, for devices that start with
+//! `arm_dcc::init_and_wait`, and with `--capture` the raw words are also appended to `<file>`. The
+//! tool exits with the exit status of the program, if it sends one (see `arm_dcc::exit`).
+//!
+//! ``` text
+//! $ openocd -f interface/ftdi/olimex-arm-usb-ocd-h.cfg -f target/xilinx_zynqmp.cfg &
+//! $ arm-dcc-host --connect --ready 0x80030000
+//! Hello, world!
+//! ```
+//!
+//! With `--openocd` the words are decoded as OpenOCD `libdcc` requests, as sent by devices that
+//! have the `openocd` feature enabled: the words of hex messages are printed in hexadecimal and
+//! trace points as `trace point <number>`.
+//!
+//! `--features` takes the `arm_dcc::handshake::FEATURE_*` bits, in decimal or `0x` hexadecimal, of
+//! a device that doesn't send a handshake frame, e.g. `--features 0xc` if it has the `crc` and
+//! `sequence` features enabled.
+//!
+//! With `--channels` the data of each channel (see the `arm_dcc::channel` module) is written to
+//! `<dir>/channel-<id>`, one file per channel; otherwise it's discarded.
+//!
+//! Code addresses (see the `arm_dcc::address` module), e.g. the ones sent by the `addresses`
+//! feature of `arm-dcc-exception`, are printed in hexadecimal, or, with `--elf`, as the function
+//! and source location they belong to, looked up in the debug information of the program.
+//!
+//! ``` text
+//! $ arm-dcc-host --elf target/armv7r-none-eabi/debug/app dcc.bin
+//! Undefined instruction at 0x00100a4c
+//!    0: app::fault (src/main.rs:31)
+//!    1: app::main (src/main.rs:18)
+//! ```
+//!
+//! # Recording and replay
+//!
+//! With `--record` the words received in live mode, from all of the cores, are written to a
+//! session file along with when they were received; see the `arm_dcc_host::session` module.
+//! `--replay` decodes a session file again, e.g. after fixing a decoder bug, or with a newer
+//! `--elf`, to triage an intermittent failure, as if it was live: timestamps (`--timestamps`,
+//! `--format jsonl`) are those of the recording, and with `--realtime` the words are replayed with
+//! their original timing, e.g. for the clients of `--pty` or `--listen`. A replay has as many cores
+//! as the recording had `<base>` addresses, even if some of them never sent anything. Unlike a
+//! live session, a replay doesn't end when the program exits, but at the end of the recording.
+//!
+//! ``` text
+//! $ arm-dcc-host --connect --record field.dcc 0x80030000 0x80032000
+//! $ arm-dcc-host --replay --timestamps --elf target/armv7r-none-eabi/release/app field.dcc
+//! ```
+//!
+//! # JSON Lines
+//!
+//! `--format jsonl` prints one JSON object per line instead of text, for log collectors (ELK,
+//! Loki) and `jq`; `--format text`, the default, prints text. Each object has a `type`:
+//!
+//! - `record`: a binary log record (see the `binary-log` feature), with its `level`, `module`,
+//!   `message` and, if known, its source `line` and the `uptime` of the device in seconds
+//! - `text`: a line of text, as `message`
+//! - `channel`: the hexadecimal `data` of a `channel`, unless `--channels` writes it to files
+//!
+//! In live mode, and in replays, objects also have a `timestamp`, in seconds since the Unix epoch,
+//! of when they were received, and with several cores, a `core` label. Messages on stderr stay
+//! text.
+//!
+//! ``` text
+//! $ arm-dcc-host --format jsonl dcc.bin | jq -r 'select(.level == "ERROR") | .message'
+//! sensor timeout
+//! ```
+//!
+//! # Pseudo-terminals
+//!
+//! With `--pty` (Unix only) the text output of the device doesn't go to stdout but to a
+//! pseudo-terminal, whose path is printed on stderr, so serial terminals, test frameworks and
+//! scripts that expect a UART can use the DCC instead; see the `arm_dcc_host::pty` module. What
+//! they write to the terminal is sent to the device the way `arm_dcc::read`, `arm_dcc::shell` and
+//! the `embedded-hal-nb` and `embedded-io` implementations expect it: one byte per word or, if the
+//! device reports the `packed` feature in its handshake or `--features` does, 4 bytes per word,
+//! packed like `arm_dcc::pack` does. In the latter case bytes are held back until they fill a word
+//! or a newline ends them, as the device drops what follows a newline in a word (see
+//! `arm_dcc::read_line`). Like on a UART, the output is lost if no one reads it. With several
+//! cores each core gets its own terminal, and its output isn't prefixed.
+//!
+//! ``` text
+//! $ arm-dcc-host --connect --pty 0x80030000 &
+//! text output on /dev/pts/3
+//! $ picocom /dev/pts/3
+//! ```
+//!
+//! # TCP server
+//!
+//! With `--listen` the text printed on stdout is also served over TCP, on `<address>`, to any
+//! number of clients, e.g. a CI log collector, a dashboard and a developer's terminal, that attach
+//! to the same session; see the `arm_dcc_host::server` module. A bare port, e.g. `--listen 4444`,
+//! only accepts connections from the same machine; use `--listen 0.0.0.0:4444` to accept them
+//! from anywhere. Clients get the output from the moment they connect.
+//!
+//! ``` text
+//! $ arm-dcc-host --connect --listen 4444 0x80030000 &
+//! serving the output on 127.0.0.1:4444
+//! $ nc localhost 4444
+//! Hello, world!
+//! ```
+//!
+//! # Multiple cores
+//!
+//! Several `<base>` addresses read the DCCs of several cores at once, e.g. both Cortex-R5 cores of
+//! a Zynq UltraScale+, or a Cortex-R5 and a Cortex-A53 one, each through its own connection to
+//! OpenOCD. Their output is merged into a single view as it arrives: each line of text is prefixed
+//! with the label of the core it comes from, `core0`, `core1`, etc. in the order of the `<base>`
+//! addresses, or the comma separated names of `--labels`. So are the messages on stderr, the
+//! names of the `--channels` files (`<dir>/<label>-channel-<id>`) and of the `--capture` files
+//! (`<file>-<label>`, one per core). A single `--elf` applies to all of the cores; otherwise there
+//! must be one per core, in the same order. The tool exits once every core has exited, with the
+//! first non-zero exit status, if any.
+//!
+//! With `--timestamps` each line is also prefixed with the time, in seconds since the session
+//! started (the tool, or the recording of a replay), at which its last word was received,
+//! measured with the same clock for all cores so lines from different cores can be put in order.
+//!
+//! ``` text
+//! $ arm-dcc-host --connect --labels r5-0,r5-1 --timestamps 0x80030000 0x80032000
+//! [  0.001204 r5-0] Hello from core 0
+//! [  0.001519 r5-1] Hello from core 1
+//! ```
+//!
+//! Several `<capture>` files, or `--labels`, also prefix the output with labels; the captures are
+//! decoded one after the other since they don't record when each word was received.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    mem,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use arm_dcc::handshake::{FEATURE_PACKED, HOST_READY};
+use arm_dcc_host::{
+    capture::{self, Format},
+    openocd::{self, Request},
+    poll::Poller,
+    render::Record,
+    server::Server,
+    session::{self, Recorder},
+    symbolize::Symbolizer,
+    tcl::{self, ApRegisters, Client},
+    Event, Stream,
+};
+use serde_json::{json, Value};
+
+#[cfg(unix)]
+use arm_dcc_host::pty::Pty;
+
+const USAGE: &str = "usage: arm-dcc-host [--hex] [--openocd] [--features <bits>] \
+                     [--channels <dir>] [--elf <file>].. [--labels <names>] [--format <format>] \
+                     <capture>..
+       arm-dcc-host --connect [<address>] [--dap <name>] [--ap <index>] [--ready] \
+                     [--capture <file>] [--openocd] [--features <bits>] [--channels <dir>] \
+                     [--elf <file>].. [--labels <names>] [--format <format>] [--timestamps] \
+                     [--pty] [--listen <address>] [--record <file>] <base>..
+       arm-dcc-host --replay [--realtime] [--openocd] [--features <bits>] [--channels <dir>] \
+                     [--elf <file>].. [--labels <names>] [--format <format>] [--timestamps] \
+                     [--pty] [--listen <address>] <session>";
+
+// how long to wait before polling again when no device has anything to send
+const IDLE: Duration = Duration::from_millis(1);
+
+struct Options {
+    format: Format,
+    jsonl: bool,
+    openocd: bool,
+    features: u32,
+    channels_dir: Option<PathBuf>,
+    elfs: Vec<String>,
+    labels: Option<Vec<String>>,
+    timestamps: bool,
+    pty: bool,
+    listen: Option<String>,
+    // `Some` in live mode: the address of the Tcl RPC server
+    connect: Option<String>,
+    dap: Option<String>,
+    ap: u8,
+    ready: bool,
+    capture: Option<String>,
+    record: Option<String>,
+    replay: bool,
+    realtime: bool,
+    // the capture files, the base addresses of the debug registers in live mode, one per core, or
+    // the session file with `--replay`
+    inputs: Vec<String>,
+}
+
+// the output of one core
+struct Core {
+    // `None` if there's a single core and no `--labels`
+    label: Option<String>,
+    stream: Stream,
+    // `Some` if the device sends `libdcc` requests instead
+    openocd: Option<openocd::Decoder>,
+    // text received since the last newline; only used if lines are prefixed
+    line: Vec<u8>,
+    // `Some` with `--pty`: where the text goes instead of stdout
+    pty: Option<Pty>,
+    // whether the device takes 4 bytes of input per word; see `input_words`
+    packed: bool,
+    // input from the terminal that doesn't fill a word yet; only used if `packed`
+    input: Vec<u8>,
+    channels: HashMap<u8, File>,
+    exit: Option<i32>,
+}
+
+// turns words into output
+struct Output {
+    cores: Vec<Core>,
+    channels_dir: Option<PathBuf>,
+    live: bool,
+    // `--format jsonl`
+    jsonl: bool,
+    timestamps: bool,
+    // when the session started, and when the current word was received since then; `None` for
+    // captures, which don't record it
+    start: Option<SystemTime>,
+    elapsed: Duration,
+    // `Some` with `--listen`: also gets what's printed on stdout
+    server: Option<Server>,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let options = options()?;
+
+    let mut output = Output {
+        cores: Vec::new(),
+        channels_dir: options.channels_dir.clone(),
+        live: options.connect.is_some(),
+        jsonl: options.jsonl,
+        timestamps: options.timestamps,
+        start: None,
+        elapsed: Duration::from_secs(0),
+        server: None,
+    };
+    if let Some(address) = &options.listen {
+        // NOTE a bare port only accepts local connections
+        let address = if address.contains(':') {
+            address.clone()
+        } else {
+            format!("127.0.0.1:{}", address)
+        };
+        let server = Server::bind(&*address)
+            .map_err(|e| format!("couldn't listen on {}: {}", address, e))?;
+        eprintln!(
+            "serving the output on {}",
+            server.local_addr().map_err(|e| e.to_string())?
+        );
+        output.server = Some(server);
+    }
+
+    let session = if options.replay {
+        let path = &options.inputs[0];
+        let bytes = fs::read(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+        let session =
+            session::read(&bytes).map_err(|e| format!("couldn't parse {}: {}", path, e))?;
+        if session.truncated {
+            eprintln!(
+                "warning: {} ends in the middle of an entry, which was dropped",
+                path
+            );
+        }
+        Some(session)
+    } else {
+        None
+    };
+
+    let cores = match &session {
+        Some(session) => session.cores as usize,
+        None => options.inputs.len(),
+    };
+    if options.elfs.len() > 1 && options.elfs.len() != cores {
+        return Err(format!("expected 1 or {} ELF files", cores));
+    }
+    if let Some(labels) = &options.labels {
+        if labels.len() != cores {
+            return Err(format!("expected {} labels", cores));
+        }
+    }
+
+    for i in 0..cores {
+        let mut stream = Stream::with_features(options.features);
+        // NOTE a single ELF file applies to all of the cores
+        if let Some(path) = options.elfs.get(i).or_else(|| options.elfs.first()) {
+            let elf = fs::read(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+            stream.set_symbolizer(
+                Symbolizer::new(&elf).map_err(|e| format!("couldn't load {}: {}", path, e))?,
+            );
+        }
+
+        output.cores.push(Core {
+            label: match &options.labels {
+                Some(labels) => Some(labels[i].clone()),
+                None if cores > 1 => Some(format!("core{}", i)),
+                None => None,
+            },
+            stream,
+            openocd: if options.openocd {
+                Some(openocd::Decoder::new())
+            } else {
+                None
+            },
+            line: Vec::new(),
+            pty: if options.pty { Some(open_pty()?) } else { None },
+            packed: options.features & FEATURE_PACKED != 0,
+            input: Vec::new(),
+            channels: HashMap::new(),
+            exit: None,
+        });
+
+        if let Some(pty) = &output.cores[i].pty {
+            output.message(i, format!("text output on {}", pty.path().display()));
+        }
+    }
+
+    if let Some(session) = session {
+        output.start = Some(session.start);
+
+        let replay = Instant::now();
+        for entry in session.entries {
+            if options.realtime {
+                if let Some(wait) = entry.time.checked_sub(replay.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+
+            output.elapsed = entry.time;
+            output.push(entry.core as usize, entry.word)?;
+
+            if let Some(server) = &mut output.server {
+                server
+                    .poll()
+                    .map_err(|e| format!("couldn't accept a client: {}", e))?;
+            }
+        }
+
+        return output.finish();
+    }
+
+    let address = match &options.connect {
+        Some(address) => address,
+        None => {
+            for (i, input) in options.inputs.iter().enumerate() {
+                let bytes =
+                    fs::read(input).map_err(|e| format!("couldn't read {}: {}", input, e))?;
+                let words = capture::words(&bytes, options.format)
+                    .map_err(|e| format!("couldn't parse {}: {}", input, e))?;
+
+                words
+                    .into_iter()
+                    .try_for_each(|word| output.push(i, word))?;
+            }
+
+            return output.finish();
+        }
+    };
+
+    let mut pollers = Vec::new();
+    for (input, core) in options.inputs.iter().zip(&output.cores) {
+        let base = number(input).ok_or_else(|| format!("invalid address: {}", input))?;
+        let capture = match &options.capture {
+            Some(path) => {
+                let path = match &core.label {
+                    Some(label) => format!("{}-{}", path, label),
+                    None => path.clone(),
+                };
+                Some(
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&path)
+                        .map_err(|e| format!("couldn't open {}: {}", path, e))?,
+                )
+            }
+            None => None,
+        };
+
+        // NOTE each core gets its own connection to OpenOCD
+        let client = Client::connect(&**address)
+            .map_err(|e| format!("couldn't connect to OpenOCD at {}: {}", address, e))?;
+        let mut poller = Poller::new(ApRegisters::new(
+            client,
+            options.dap.as_deref(),
+            options.ap,
+            base,
+        ));
+        if options.ready {
+            poller.push_input(&[HOST_READY]);
+        }
+
+        pollers.push((base, poller, capture));
+    }
+
+    let mut recorder = match &options.record {
+        Some(path) => Some(
+            File::create(path)
+                .and_then(|file| Recorder::new(file, pollers.len() as u32))
+                .map_err(|e| format!("couldn't create {}: {}", path, e))?,
+        ),
+        None => None,
+    };
+
+    output.start = Some(SystemTime::now());
+    let start = Instant::now();
+    loop {
+        let mut idle = true;
+        for (i, (base, poller, capture)) in pollers.iter_mut().enumerate() {
+            let core = &mut output.cores[i];
+            if let Some(pty) = &mut core.pty {
+                let mut buffer = [0; 64];
+                match pty.read(&mut buffer) {
+                    Ok(n) => {
+                        let words = input_words(&mut core.input, &buffer[..n], core.packed);
+                        poller.push_input(&words);
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(e) => {
+                        return Err(format!("couldn't read {}: {}", pty.path().display(), e));
+                    }
+                }
+            }
+
+            let word = match poller
+                .poll()
+                .map_err(|e| format!("couldn't poll the DCC at {:#010x}: {}", base, e))?
+            {
+                Some(word) => word,
+                None => continue,
+            };
+            idle = false;
+
+            if let Some(file) = capture {
+                file.write_all(&word.to_le_bytes())
+                    .map_err(|e| format!("couldn't write the capture: {}", e))?;
+            }
+            if let Some(recorder) = &mut recorder {
+                recorder
+                    .record(i as u32, word)
+                    .map_err(|e| format!("couldn't write the recording: {}", e))?;
+            }
+
+            output.elapsed = start.elapsed();
+            output.push(i, word)?;
+        }
+
+        if let Some(server) = &mut output.server {
+            server
+                .poll()
+                .map_err(|e| format!("couldn't accept a client: {}", e))?;
+        }
+
+        if idle {
+            thread::sleep(IDLE);
+        }
+    }
+}
+
+impl Output {
+    fn push(&mut self, i: usize, word: u32) -> Result<(), String> {
+        if let Some(decoder) = &mut self.cores[i].openocd {
+            return match decoder.push(word) {
+                Some(Request::Text(bytes)) => self.text(i, &bytes),
+                Some(Request::Word(word)) => self.text(i, format!("{:#010x}\n", word).as_bytes()),
+                Some(Request::TracePoint(number)) => {
+                    self.text(i, format!("trace point {}\n", number).as_bytes())
+                }
+                Some(Request::Unknown(word)) => {
+                    self.message(i, format!("warning: unknown request {:#010x}", word));
+                    Ok(())
+                }
+                None => Ok(()),
+            };
+        }
+
+        match self.cores[i].stream.push(word) {
+            Some(Event::Text(text)) => self.text(i, &text)?,
+            Some(Event::Record(record)) => self.record(i, record)?,
+            Some(Event::Channel(id, bytes)) => {
+                if let Some(dir) = &self.channels_dir {
+                    write_channel(&mut self.cores[i], dir, id, &bytes)?;
+                } else if self.jsonl {
+                    let data = bytes.iter().map(|byte| format!("{:02x}", byte));
+                    let object = json!({ "channel": id, "data": data.collect::<String>() });
+                    let line = self.json(i, "channel", object);
+                    self.stdout(&line)?;
+                }
+            }
+            Some(Event::Handshake(handshake)) => {
+                self.cores[i].packed = handshake.has(FEATURE_PACKED);
+                self.message(
+                    i,
+                    format!(
+                        "device: protocol version {}, arm-dcc {}.{}.{}, features {:#x}",
+                        handshake.version,
+                        handshake.crate_version >> 16,
+                        handshake.crate_version >> 8 & 0xff,
+                        handshake.crate_version & 0xff,
+                        handshake.features,
+                    ),
+                )
+            }
+            Some(Event::Exit(status)) => {
+                self.message(i, format!("device exited with status {}", status));
+                self.cores[i].exit = Some(status);
+
+                // NOTE in live mode the devices have nothing else to send
+                if self.live && self.cores.iter().all(|core| core.exit.is_some()) {
+                    self.finish()?;
+                    process::exit(
+                        self.cores
+                            .iter()
+                            .filter_map(|core| core.exit)
+                            .find(|status| *status != 0)
+                            .unwrap_or(0),
+                    );
+                }
+            }
+            Some(Event::BadCrc) => self.message(
+                i,
+                "warning: a frame was corrupted (CRC mismatch)".to_string(),
+            ),
+            Some(Event::Lost(frames)) => {
+                self.message(i, format!("warning: {} frames were lost", frames))
+            }
+            Some(Event::Unknown(word)) => {
+                self.message(i, format!("warning: unknown word {:#010x}", word))
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    // whether lines of text get a prefix
+    fn prefixed(&self) -> bool {
+        self.timestamps || self.cores.iter().any(|core| core.label.is_some())
+    }
+
+    fn record(&mut self, i: usize, record: Record) -> Result<(), String> {
+        if self.jsonl && self.cores[i].pty.is_none() {
+            let mut object = json!({
+                "level": record.level.to_string(),
+                "module": record.module,
+                "message": record.message,
+            });
+            if let Some(line) = record.line {
+                object["line"] = line.into();
+            }
+            if let Some(uptime) = record.uptime {
+                object["uptime"] = (f64::from(uptime) / 1_000.).into();
+            }
+
+            let line = self.json(i, "record", object);
+            self.stdout(&line)
+        } else {
+            self.text(i, format!("{}\n", record).as_bytes())
+        }
+    }
+
+    fn text(&mut self, i: usize, bytes: &[u8]) -> Result<(), String> {
+        if let Some(pty) = &mut self.cores[i].pty {
+            return match pty.write_all(bytes) {
+                // NOTE no one is reading the terminal
+                Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(()),
+                result => {
+                    result.map_err(|e| format!("couldn't write to {}: {}", pty.path().display(), e))
+                }
+            };
+        }
+
+        if !self.jsonl && !self.prefixed() {
+            return self.stdout(bytes);
+        }
+
+        // NOTE only whole lines are printed so the lines of different cores don't get mixed up
+        let mut lines = Vec::new();
+        for byte in bytes {
+            self.cores[i].line.push(*byte);
+
+            if *byte == b'\n' {
+                let line = mem::take(&mut self.cores[i].line);
+
+                if self.jsonl {
+                    let text = String::from_utf8_lossy(&line);
+                    let message = text.trim_end_matches(&['\r', '\n'][..]);
+                    lines.extend(self.json(i, "text", json!({ "message": message })));
+                } else {
+                    lines.extend_from_slice(self.prefix(i).as_bytes());
+                    lines.extend(line);
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            Ok(())
+        } else {
+            self.stdout(&lines)
+        }
+    }
+
+    // prints the text on stdout and serves it to the clients of the server
+    fn stdout(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if let Some(server) = &mut self.server {
+            server.send(bytes);
+        }
+
+        write_stdout(bytes)
+    }
+
+    // a JSON object on a line of its own, e.g. `{"message":"Hello","type":"text"}`; `object` is a
+    // JSON object
+    fn json(&self, i: usize, kind: &str, mut object: Value) -> Vec<u8> {
+        object["type"] = kind.into();
+        if let Some(label) = &self.cores[i].label {
+            object["core"] = label.clone().into();
+        }
+        if let Some(start) = self.start {
+            let now = (start + self.elapsed)
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default();
+            object["timestamp"] = now.as_secs_f64().into();
+        }
+
+        let mut line = object.to_string().into_bytes();
+        line.push(b'\n');
+        line
+    }
+
+    // e.g. `[  1.204178 core0] `
+    fn prefix(&self, i: usize) -> String {
+        let mut fields = Vec::new();
+        if self.timestamps {
+            fields.push(format!("{:>10.6}", self.elapsed.as_secs_f64()));
+        }
+        if let Some(label) = &self.cores[i].label {
+            fields.push(label.clone());
+        }
+
+        format!("[{}] ", fields.join(" "))
+    }
+
+    // prints a message about the device on stderr
+    fn message(&self, i: usize, message: String) {
+        match &self.cores[i].label {
+            Some(label) => eprintln!("{}: {}", label, message),
+            None => eprintln!("{}", message),
+        }
+    }
+
+    // prints the lines that have not been terminated yet
+    fn finish(&mut self) -> Result<(), String> {
+        for i in 0..self.cores.len() {
+            if !self.cores[i].line.is_empty() {
+                self.text(i, b"\n")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn options() -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Binary,
+        jsonl: false,
+        openocd: false,
+        features: 0,
+        channels_dir: None,
+        elfs: Vec::new(),
+        labels: None,
+        timestamps: false,
+        pty: false,
+        listen: None,
+        connect: None,
+        dap: None,
+        ap: 1,
+        ready: false,
+        capture: None,
+        record: None,
+        replay: false,
+        realtime: false,
+        inputs: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--hex" => options.format = Format::Hex,
+            "--format" => {
+                options.jsonl = match &*args.next().ok_or(USAGE)? {
+                    "text" => false,
+                    "jsonl" => true,
+                    format => return Err(format!("invalid format: {}", format)),
+                }
+            }
+            "--openocd" => options.openocd = true,
+            "--features" => {
+                let bits = args.next().ok_or(USAGE)?;
+                options.features =
+                    number(&bits).ok_or_else(|| format!("invalid features: {}", bits))?;
+            }
+            "--channels" => options.channels_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            "--elf" => options.elfs.push(args.next().ok_or(USAGE)?),
+            "--labels" => {
+                let labels = args.next().ok_or(USAGE)?;
+                options.labels = Some(labels.split(',').map(str::to_string).collect());
+            }
+            "--timestamps" => options.timestamps = true,
+            "--pty" => options.pty = true,
+            "--listen" => options.listen = Some(args.next().ok_or(USAGE)?),
+            "--connect" => {
+                // NOTE the address is optional; addresses contain a colon, base addresses don't
+                let address = match args.peek() {
+                    Some(next) if next.contains(':') => args.next(),
+                    _ => None,
+                };
+                options.connect = Some(address.unwrap_or_else(|| tcl::DEFAULT_ADDRESS.to_string()));
+            }
+            "--dap" => options.dap = Some(args.next().ok_or(USAGE)?),
+            "--ap" => {
+                let index = args.next().ok_or(USAGE)?;
+                options.ap = index
+                    .parse()
+                    .map_err(|_| format!("invalid AP index: {}", index))?;
+            }
+            "--ready" => options.ready = true,
+            "--capture" => options.capture = Some(args.next().ok_or(USAGE)?),
+            "--record" => options.record = Some(args.next().ok_or(USAGE)?),
+            "--replay" => options.replay = true,
+            "--realtime" => options.realtime = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if !arg.starts_with("--") => options.inputs.push(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    if options.inputs.is_empty()
+        || options.replay && (options.inputs.len() != 1 || options.connect.is_some())
+    {
+        return Err(USAGE.to_string());
+    }
+
+    // NOTE replays have the timing of the live session
+    let timed = options.connect.is_some() || options.replay;
+    if options.timestamps && !timed {
+        return Err("--timestamps needs --connect or --replay".to_string());
+    }
+    if options.pty && !timed {
+        return Err("--pty needs --connect or --replay".to_string());
+    }
+    if options.listen.is_some() && !timed {
+        return Err("--listen needs --connect or --replay".to_string());
+    }
+    if options.record.is_some() && options.connect.is_none() {
+        return Err("--record needs --connect".to_string());
+    }
+    if options.realtime && !options.replay {
+        return Err("--realtime needs --replay".to_string());
+    }
+
+    Ok(options)
+}
+
+// parses a decimal or `0x` hexadecimal number
+fn number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x") {
+        Some(digits) => u32::from_str_radix(digits, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+#[cfg(unix)]
+fn open_pty() -> Result<Pty, String> {
+    Pty::open().map_err(|e| format!("couldn't create a pseudo-terminal: {}", e))
+}
+
+#[cfg(not(unix))]
+fn open_pty() -> Result<Pty, String> {
+    Err("--pty is only supported on Unix".to_string())
+}
+
+// turns what was written to a pseudo-terminal into input words: one byte per word or, if the
+// device is `packed`, 4 bytes per word; in that case the bytes that neither fill a word nor end in
+// a newline stay in `pending` until more arrive
+fn input_words(pending: &mut Vec<u8>, bytes: &[u8], packed: bool) -> Vec<u32> {
+    if !packed {
+        return bytes.iter().map(|byte| u32::from(*byte)).collect();
+    }
+
+    let mut words = Vec::new();
+    for byte in bytes {
+        pending.push(*byte);
+        if pending.len() == 4 || *byte == b'\n' {
+            words.push(arm_dcc::pack(pending));
+            pending.clear();
+        }
+    }
+    words
+}
+
+// NOTE pseudo-terminals only exist on Unix
+#[cfg(not(unix))]
+enum Pty {}
+
+#[cfg(not(unix))]
+impl Pty {
+    fn path(&self) -> &Path {
+        match *self {}
+    }
+}
+
+#[cfg(not(unix))]
+impl Read for Pty {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        match *self {}
+    }
+}
+
+#[cfg(not(unix))]
+impl Write for Pty {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        match *self {}
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {}
+    }
+}
+
+// NOTE stdout is flushed after every write so live output shows up right away
+fn write_stdout(bytes: &[u8]) -> Result<(), String> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout
+        .write_all(bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("couldn't write to stdout: {}", e))
+}
+
+fn write_channel(core: &mut Core, dir: &Path, id: u8, bytes: &[u8]) -> Result<(), String> {
+    let path = match &core.label {
+        Some(label) => dir.join(format!("{}-channel-{}", label, id)),
+        None => dir.join(format!("channel-{}", id)),
+    };
+    let file = match core.channels.entry(id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(
+            File::create(&path)
+                .map_err(|e| format!("couldn't create {}: {}", path.display(), e))?,
+        ),
+    };
+
+    file.write_all(bytes)
+        .map_err(|e| format!("couldn't write to {}: {}", path.display(), e))
+}
@@ -0,0 +1,85 @@
+//! Decoding of the requests of OpenOCD's `libdcc` protocol
+//!
+//! Devices that have the `openocd` feature enabled, or that use `arm_dcc::openocd` directly, send
+//! these requests instead of the encodings of `arm_dcc::decode`; see `arm_dcc::openocd` for the
+//! layout of each request.
+//!
+//! ```
+//! use arm_dcc_host::openocd::{Decoder, Request};
+//!
+//! let mut decoder = Decoder::new();
+//! assert_eq!(decoder.push(2 << 16 | 0x01), None);
+//! assert_eq!(decoder.push(0x6948), Some(Request::Text(b"Hi".to_vec())));
+//! assert_eq!(decoder.push(7 << 8), Some(Request::TracePoint(7)));
+//! ```
+
+/// Request type of a trace point
+const TRACE_POINT: u32 = 0x00;
+
+/// Request type of ASCII and hex messages
+const DEBUG_MSG: u32 = 0x01;
+
+/// Request type of a single character
+const DEBUG_CHAR: u32 = 0x02;
+
+/// What the device requested OpenOCD to print, or count
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Request {
+    /// Bytes of an ASCII message or a single character
+    Text(Vec<u8>),
+    /// A word of a hex message
+    Word(u32),
+    /// The trace point with the given number was hit
+    TracePoint(u32),
+    /// A request word of an unknown type
+    Unknown(u32),
+}
+
+/// Stateful decoder of the `libdcc` word stream
+#[derive(Clone, Debug, Default)]
+pub struct Decoder {
+    // bytes left in the current ASCII message
+    bytes: u32,
+    // words left in the current hex message
+    words: u32,
+}
+
+impl Decoder {
+    /// Creates a new decoder
+    pub const fn new() -> Self {
+        Decoder { bytes: 0, words: 0 }
+    }
+
+    /// Feeds the next word received from the DCC into the decoder
+    ///
+    /// Returns `None` if the word was the first word of a message
+    pub fn push(&mut self, word: u32) -> Option<Request> {
+        if self.bytes != 0 {
+            let len = self.bytes.min(4);
+            self.bytes -= len;
+            let bytes = word.to_le_bytes()[..len as usize].to_vec();
+            return Some(Request::Text(bytes));
+        }
+
+        if self.words != 0 {
+            self.words -= 1;
+            return Some(Request::Word(word));
+        }
+
+        match word & 0xff {
+            TRACE_POINT => Some(Request::TracePoint(word >> 8)),
+            DEBUG_MSG => {
+                // NOTE the second byte is the size of the elements of the message: 1 for ASCII
+                // messages, 4 for hex messages
+                match word >> 8 & 0xff {
+                    0 => self.bytes = word >> 16,
+                    4 => self.words = word >> 16,
+                    _ => return Some(Request::Unknown(word)),
+                }
+                None
+            }
+            DEBUG_CHAR => Some(Request::Text(vec![(word >> 16) as u8])),
+            _ => Some(Request::Unknown(word)),
+        }
+    }
+}
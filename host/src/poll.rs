@@ -0,0 +1,108 @@
+//! Polling of the DCC through the memory mapped debug registers of the core
+//!
+//! A debugger reaches the DCC through the external debug interface: the device's writes end up in
+//! DBGDTRTX and set the TXfull bit of DBGDSCR, and the debugger's writes to DBGDTRRX set its RXfull
+//! bit. ARMv7-A, ARMv7-R and ARMv8 (EDSCR, AArch64) cores map these registers at the same offsets
+//! of their debug register block, which is usually reached through the APB-AP of the debug port.
+//!
+//! `Poller` implements the polling on top of any way of reading and writing those registers,
+//! e.g. the `dcc-probe` tool of this repository uses a probe-rs session.
+//!
+//! ```
+//! use arm_dcc_host::poll::{DebugRegisters, Poller, DBGDSCR, DBGDTRTX, TXFULL};
+//!
+//! // a core that has sent one word
+//! struct Core;
+//!
+//! impl DebugRegisters for Core {
+//!     type Error = ();
+//!
+//!     fn read(&mut self, offset: u32) -> Result<u32, ()> {
+//!         match offset {
+//!             DBGDSCR => Ok(TXFULL),
+//!             DBGDTRTX => Ok(0x2a),
+//!             _ => Err(()),
+//!         }
+//!     }
+//!
+//!     fn write(&mut self, _: u32, _: u32) -> Result<(), ()> {
+//!         Err(())
+//!     }
+//! }
+//!
+//! let mut poller = Poller::new(Core);
+//! assert_eq!(poller.poll(), Ok(Some(0x2a)));
+//! ```
+
+use std::collections::VecDeque;
+
+/// Offset of DBGDTRRX(ext), the host to device data register
+pub const DBGDTRRX: u32 = 0x080;
+
+/// Offset of DBGDSCR(ext), the debug status and control register
+pub const DBGDSCR: u32 = 0x088;
+
+/// Offset of DBGDTRTX(ext), the device to host data register
+pub const DBGDTRTX: u32 = 0x08c;
+
+/// DBGDSCR bit that's set while DBGDTRTX holds a word the host has not read yet
+pub const TXFULL: u32 = 1 << 29;
+
+/// DBGDSCR bit that's set while DBGDTRRX holds a word the device has not read yet
+pub const RXFULL: u32 = 1 << 30;
+
+/// Access to the debug registers of a core
+pub trait DebugRegisters {
+    /// Error reported by the debug probe
+    type Error;
+
+    /// Reads the register at the given offset of the debug register block
+    fn read(&mut self, offset: u32) -> Result<u32, Self::Error>;
+
+    /// Writes the register at the given offset of the debug register block
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), Self::Error>;
+}
+
+/// Host side of the DCC, on top of the debug registers of a core
+pub struct Poller<R> {
+    registers: R,
+    // words waiting to be sent to the device
+    input: VecDeque<u32>,
+}
+
+impl<R> Poller<R>
+where
+    R: DebugRegisters,
+{
+    /// Creates a poller that has no words to send
+    pub fn new(registers: R) -> Self {
+        Poller {
+            registers,
+            input: VecDeque::new(),
+        }
+    }
+
+    /// Queues words to send to the device, e.g. `arm_dcc::handshake::HOST_READY`
+    pub fn push_input(&mut self, words: &[u32]) {
+        self.input.extend(words.iter().cloned())
+    }
+
+    /// Checks the DCC once: sends the next queued word if the device has read the previous one and
+    /// returns the word sent by the device, if any
+    pub fn poll(&mut self) -> Result<Option<u32>, R::Error> {
+        let status = self.registers.read(DBGDSCR)?;
+
+        if status & RXFULL == 0 {
+            if let Some(word) = self.input.pop_front() {
+                self.registers.write(DBGDTRRX, word)?;
+            }
+        }
+
+        if status & TXFULL == 0 {
+            return Ok(None);
+        }
+
+        // NOTE in the default, non-blocking, mode this read also clears TXfull
+        self.registers.read(DBGDTRTX).map(Some)
+    }
+}
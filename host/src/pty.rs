@@ -0,0 +1,111 @@
+//! Pseudo-terminals that carry the decoded output of a device
+//!
+//! A `Pty` makes the text output of a device look like a UART: serial terminals (`picocom`,
+//! `minicom`, `screen`), test frameworks and scripts open the terminal at `Pty::path`, e.g.
+//! `/dev/pts/3`, and read what's written to the `Pty`, while what they write to it can be read
+//! from the `Pty` and sent to the device, e.g. as input for `arm_dcc::read` or `arm_dcc::shell`.
+//!
+//! The terminal is in raw mode, so bytes go through as they are, and stays open while the `Pty`
+//! is alive, so its users can come and go. Reads and writes don't block: they fail with
+//! `io::ErrorKind::WouldBlock` when there's nothing to read, or when the buffer of the terminal
+//! (a few KiB) is full because no one is reading from it.
+//!
+//! Only Unix systems have pseudo-terminals; on Windows a pair of virtual COM ports, e.g. from
+//! com0com, plays the same role but needs a driver.
+
+use std::{
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    mem::MaybeUninit,
+    os::unix::{
+        fs::OpenOptionsExt,
+        io::{AsRawFd, FromRawFd},
+    },
+    path::{Path, PathBuf},
+};
+
+/// A pseudo-terminal
+pub struct Pty {
+    // the side the tool reads and writes
+    master: File,
+    // the terminal, kept open so the master doesn't see a hang up when its last user closes it
+    _slave: File,
+    path: PathBuf,
+}
+
+impl Pty {
+    /// Creates a new pseudo-terminal
+    pub fn open() -> io::Result<Self> {
+        // NOTE(unsafe) the file descriptor is owned by `master` from here on
+        let master = unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_NONBLOCK);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            File::from_raw_fd(fd)
+        };
+
+        // NOTE(unsafe) `ptsname` returns a pointer to a static buffer, which is copied right away;
+        // the tool doesn't call it from other threads
+        let path = unsafe {
+            if libc::grantpt(master.as_raw_fd()) != 0 || libc::unlockpt(master.as_raw_fd()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let name = libc::ptsname(master.as_raw_fd());
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            PathBuf::from(CStr::from_ptr(name).to_string_lossy().into_owned())
+        };
+
+        let slave = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open(&path)?;
+
+        // NOTE(unsafe) `termios` is initialized by `tcgetattr`
+        unsafe {
+            let mut termios = MaybeUninit::uninit();
+            if libc::tcgetattr(slave.as_raw_fd(), termios.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut termios = termios.assume_init();
+            libc::cfmakeraw(&mut termios);
+            if libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Pty {
+            master,
+            _slave: slave,
+            path,
+        })
+    }
+
+    /// Path of the terminal, e.g. `/dev/pts/3`
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Reads what the users of the terminal wrote to it
+impl Read for Pty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.master.read(buf)
+    }
+}
+
+/// Writes to the users of the terminal
+impl Write for Pty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.master.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.master.flush()
+    }
+}
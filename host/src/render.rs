@@ -0,0 +1,265 @@
+//! Rendering of the binary log records sent with the `binary-log` feature
+//!
+//! See the `arm_dcc::record` module for their encoding. Records are printed like the text log
+//! lines: `[uptime] LEVEL module:line: message`, the uptime in seconds; `Renderer::push_record`
+//! returns them as `Record`s instead, for tools that want their fields.
+
+use std::{collections::HashMap, fmt, mem};
+
+use arm_dcc::{decode::Item, record::MODULE_KIND, Level};
+
+/// A binary log record
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Record {
+    /// Level of the record
+    pub level: Level,
+    /// Module that sent the record, or `<module 0x..>` if the device didn't send its name
+    pub module: String,
+    /// Source line of the log statement, if known
+    pub line: Option<u32>,
+    /// Uptime of the device when it sent the record, in milliseconds, if known
+    pub uptime: Option<u32>,
+    /// Formatted message
+    pub message: String,
+}
+
+/// Formats the record as a text log line, without the newline
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(uptime) = self.uptime {
+            write!(f, "[{:4}.{:03}] ", uptime / 1_000, uptime % 1_000)?;
+        }
+        write!(f, "{} {}", self.level, self.module)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// What `Renderer::push_record` returns
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Output {
+    /// Text that's not part of a record
+    Text(Vec<u8>),
+    /// A complete record
+    Record(Record),
+}
+
+/// Turns the items of the DCC word stream into output text
+pub struct Renderer {
+    modules: HashMap<u32, String>,
+    // the message of the record that's being received
+    message: Vec<u8>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    /// Creates a renderer that knows no modules yet
+    pub fn new() -> Self {
+        Renderer {
+            modules: HashMap::new(),
+            message: Vec::new(),
+        }
+    }
+
+    /// Returns the text to print, if any
+    pub fn push(&mut self, item: Item) -> Option<Vec<u8>> {
+        self.push_record(item).map(|output| match output {
+            Output::Text(text) => text,
+            Output::Record(record) => format!("{}\n", record).into_bytes(),
+        })
+    }
+
+    /// Like `push` but returns records as they are, rather than rendered
+    pub fn push_record(&mut self, item: Item) -> Option<Output> {
+        // NOTE the message of a record is followed by its words; anything else in between means
+        // that part of the record was lost, so it's dropped
+        if let Item::RecordBytes(chunk) = item {
+            self.message.extend_from_slice(chunk.as_bytes());
+            return None;
+        }
+        let message = mem::take(&mut self.message);
+
+        match item {
+            Item::Text(chunk) => Some(Output::Text(chunk.as_bytes().to_vec())),
+            Item::Record([MODULE_KIND, hash, ..]) => {
+                let name = String::from_utf8_lossy(&message).into_owned();
+                self.modules.insert(hash, name);
+                None
+            }
+            Item::Record(words) => Some(Output::Record(record(&self.modules, words, &message))),
+            _ => None,
+        }
+    }
+}
+
+fn record(modules: &HashMap<u32, String>, words: [u32; 4], message: &[u8]) -> Record {
+    let [level, hash, line, uptime] = words;
+
+    let level = match level {
+        1 => Level::Error,
+        2 => Level::Warn,
+        3 => Level::Info,
+        4 => Level::Debug,
+        _ => Level::Trace,
+    };
+    let module = match modules.get(&hash) {
+        Some(module) => module.clone(),
+        None => format!("<module {:#010x}>", hash),
+    };
+
+    // NOTE `0` means unknown
+    Record {
+        level,
+        module,
+        line: if line != 0 { Some(line) } else { None },
+        uptime: if uptime != 0 { Some(uptime) } else { None },
+        message: String::from_utf8_lossy(message).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arm_dcc::{
+        crc::Crc32,
+        decode::{Chunk, Decoder, Item},
+        handshake::FEATURE_CRC,
+        record::{module_hash, MODULE_KIND, RECORD_TAG},
+        Level,
+    };
+
+    use super::{Output, Record, Renderer};
+
+    // the words of a record frame, without the CRC word
+    fn frame(message: &[u8], fields: [u32; 4]) -> Vec<u32> {
+        let mut words = vec![RECORD_TAG | message.len() as u32];
+        words.extend(message.chunks(4).map(|chunk| {
+            let mut le = [0; 4];
+            le[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(le)
+        }));
+        words.extend_from_slice(&fields);
+        words
+    }
+
+    fn render(decoder: &mut Decoder, renderer: &mut Renderer, words: &[u32]) -> Vec<Output> {
+        words
+            .iter()
+            .filter_map(|word| decoder.push(*word))
+            .filter_map(|item| renderer.push_record(item))
+            .collect()
+    }
+
+    #[test]
+    fn reassembly() {
+        let hash = module_hash("app::net");
+        let mut words = frame(b"app::net", [MODULE_KIND, hash, 0, 0]);
+        words.push(0x48);
+        words.extend(frame(b"link up", [Level::Info as u32, hash, 42, 1_234]));
+        words.extend(frame(b"", [Level::Warn as u32, hash, 0, 0]));
+
+        let outputs = render(&mut Decoder::new(), &mut Renderer::new(), &words);
+        assert_eq!(
+            outputs,
+            [
+                Output::Text(b"H".to_vec()),
+                Output::Record(Record {
+                    level: Level::Info,
+                    module: "app::net".to_string(),
+                    line: Some(42),
+                    uptime: Some(1_234),
+                    message: "link up".to_string(),
+                }),
+                Output::Record(Record {
+                    level: Level::Warn,
+                    module: "app::net".to_string(),
+                    line: None,
+                    uptime: None,
+                    message: String::new(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn line() {
+        let hash = module_hash("app::net");
+        let mut words = frame(b"app::net", [MODULE_KIND, hash, 0, 0]);
+        words.extend(frame(b"link up", [Level::Info as u32, hash, 42, 1_234]));
+
+        let mut decoder = Decoder::new();
+        let mut renderer = Renderer::new();
+        let text = words
+            .iter()
+            .filter_map(|word| decoder.push(*word))
+            .filter_map(|item| renderer.push(item))
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(text, b"[   1.234] INFO app::net:42: link up\n");
+    }
+
+    #[test]
+    fn unknown_module() {
+        let words = frame(b"hi", [Level::Error as u32, 0xdead_beef, 7, 0]);
+        let outputs = render(&mut Decoder::new(), &mut Renderer::new(), &words);
+        match &outputs[..] {
+            [Output::Record(record)] => assert_eq!(record.module, "<module 0xdeadbeef>"),
+            _ => panic!("{:?}", outputs),
+        }
+    }
+
+    #[test]
+    fn corrupted_record() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let mut renderer = Renderer::new();
+
+        // NOTE the CRC doesn't match so the record is dropped, message included
+        let mut words = frame(b"lost", [Level::Info as u32, 1, 1, 1]);
+        words.push(0);
+        assert!(render(&mut decoder, &mut renderer, &words).is_empty());
+
+        let mut crc = Crc32::new();
+        let mut words = frame(b"kept", [Level::Info as u32, 1, 1, 1]);
+        words.iter().for_each(|word| crc.update_word(*word));
+        words.push(crc.finish());
+        match &render(&mut decoder, &mut renderer, &words)[..] {
+            [Output::Record(record)] => assert_eq!(record.message, "kept"),
+            outputs => panic!("{:?}", outputs),
+        }
+    }
+
+    #[test]
+    fn interrupted_record() {
+        let mut renderer = Renderer::new();
+        assert_eq!(renderer.push_record(Item::RecordBytes(chunk(b"lo"))), None);
+        // NOTE the rest of the record was lost so its message is dropped
+        assert_eq!(renderer.push_record(Item::Word(1)), None);
+
+        let outputs = render(
+            &mut Decoder::new(),
+            &mut renderer,
+            &frame(b"ok", [Level::Debug as u32, 1, 0, 0]),
+        );
+        match &outputs[..] {
+            [Output::Record(record)] => assert_eq!(record.message, "ok"),
+            _ => panic!("{:?}", outputs),
+        }
+    }
+
+    // `bytes` as a `Chunk`, which only the decoder can create
+    fn chunk(bytes: &[u8]) -> Chunk {
+        let mut decoder = Decoder::new();
+        let mut words = frame(bytes, [0; 4]);
+        words.truncate(2);
+        match words.iter().filter_map(|word| decoder.push(*word)).next() {
+            Some(Item::RecordBytes(chunk)) => chunk,
+            item => panic!("{:?}", item),
+        }
+    }
+}
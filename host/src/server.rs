@@ -0,0 +1,145 @@
+//! TCP server that serves the decoded output of a device to any number of clients
+//!
+//! `Server` sends what's passed to `Server::send` to every client connected at the time, e.g. a CI
+//! log collector, a live dashboard and a developer's `nc localhost 4444`, so they can all follow
+//! the same session. Clients receive the output from the moment they connect; what they send to
+//! the server is ignored.
+//!
+//! The server never blocks: `Server::poll` accepts new clients and sends them what's queued for
+//! them, as much as their connection takes. A client that falls more than `MAX_BACKLOG` bytes
+//! behind, or whose connection fails, is disconnected.
+
+use std::{
+    io::{self, ErrorKind, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+};
+
+/// Maximum number of bytes queued for a client
+pub const MAX_BACKLOG: usize = 1 << 20;
+
+/// A TCP server
+pub struct Server {
+    listener: TcpListener,
+    clients: Vec<Client>,
+}
+
+struct Client {
+    stream: TcpStream,
+    // bytes that have not been sent yet
+    backlog: Vec<u8>,
+}
+
+impl Server {
+    /// Listens for clients on `address`
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Server {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Number of connected clients
+    pub fn clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Accepts the clients that are waiting to connect and sends the queued bytes to all of them
+    ///
+    /// Errors that only affect the client being accepted, e.g. it hung up while waiting, or that
+    /// go away on their own, e.g. the process running out of file descriptors, are not reported;
+    /// an error means that the listener itself failed.
+    pub fn poll(&mut self) -> io::Result<()> {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    // NOTE the output is mostly made of short lines
+                    let setup = stream
+                        .set_nonblocking(true)
+                        .and_then(|_| stream.set_nodelay(true));
+
+                    // NOTE a client whose connection can't be set up is dropped, as one whose
+                    // connection fails later
+                    if setup.is_ok() {
+                        self.clients.push(Client {
+                            stream,
+                            backlog: Vec::new(),
+                        });
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                // NOTE the client hung up before it was accepted, or the call was interrupted;
+                // that doesn't affect the other clients
+                Err(e) if is_transient(&e) => continue,
+                // NOTE the client stays in the queue of the listener and is accepted by a later
+                // call, e.g. once other clients have disconnected
+                Err(e) if is_out_of_resources(&e) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.clients.retain_mut(Client::flush);
+        Ok(())
+    }
+
+    /// Sends `bytes` to every connected client
+    ///
+    /// What a client doesn't take right away is queued and sent by later calls to `send` or
+    /// `poll`.
+    pub fn send(&mut self, bytes: &[u8]) {
+        self.clients.retain_mut(|client| {
+            client.backlog.extend_from_slice(bytes);
+            client.flush() && client.backlog.len() <= MAX_BACKLOG
+        });
+    }
+}
+
+impl Client {
+    // sends as much of the backlog as the connection takes; returns `false` if the connection
+    // failed
+    fn flush(&mut self) -> bool {
+        while !self.backlog.is_empty() {
+            match self.stream.write(&self.backlog) {
+                Ok(0) => return false,
+                Ok(n) => {
+                    self.backlog.drain(..n);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+
+        true
+    }
+}
+
+// errors of `accept` that only affect the client being accepted
+fn is_transient(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ConnectionAborted | ErrorKind::ConnectionReset | ErrorKind::Interrupted
+    )
+}
+
+// errors of `accept` caused by the process, or the system, running out of file descriptors or
+// memory
+#[cfg(unix)]
+fn is_out_of_resources(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::EMFILE) | Some(libc::ENFILE) | Some(libc::ENOBUFS) | Some(libc::ENOMEM)
+    )
+}
+
+#[cfg(not(unix))]
+fn is_out_of_resources(e: &io::Error) -> bool {
+    e.kind() == ErrorKind::OutOfMemory
+}
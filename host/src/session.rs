@@ -0,0 +1,292 @@
+//! Recordings of DCC sessions
+//!
+//! A session file holds the raw words received from the DCCs of one or more cores along with
+//! when each one was received, so a session can be decoded again later, e.g. after fixing a
+//! decoder bug or with a newer ELF file, with the timing of the original: `arm-dcc-host --record`
+//! writes one and `arm-dcc-host --replay` decodes it.
+//!
+//! The file starts with `MAGIC`, the time at which the recording started, in microseconds since
+//! the Unix epoch, and the number of cores; then comes one entry per word: the time at which it
+//! was received, in microseconds since the start of the recording, the index of the core it came
+//! from and the word. The times are 64-bit and the other fields 32-bit, all little endian.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use arm_dcc_host::session::{self, Recorder};
+//!
+//! let mut file = Vec::new();
+//! let mut recorder = Recorder::new(&mut file, 2).unwrap();
+//! recorder.record(0, 0x48).unwrap();
+//! recorder.record(1, 0x69).unwrap();
+//!
+//! let session = session::read(&file).unwrap();
+//! assert_eq!(session.cores, 2);
+//! assert_eq!(session.entries.len(), 2);
+//! assert_eq!((session.entries[1].core, session.entries[1].word), (1, 0x69));
+//! assert!(session.entries[1].time < Duration::from_secs(1));
+//! ```
+
+use std::{
+    fmt,
+    io::{self, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// First bytes of a session file
+pub const MAGIC: [u8; 8] = *b"DCCSESS\x02";
+
+// size of the time at which the recording started and the number of cores, and of an entry
+const HEADER_LEN: usize = 12;
+const ENTRY_LEN: usize = 16;
+
+/// Error returned by `read`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The file doesn't start with `MAGIC`
+    Magic,
+    /// The file ends before the number of cores
+    Truncated,
+    /// Entry number `entry` comes from core `core`, which is not one of the cores of the session
+    Core {
+        /// Index of the entry
+        entry: usize,
+        /// Index of the core
+        core: u32,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Magic => f.write_str("not a session file"),
+            Error::Truncated => f.write_str("the session ends before its number of cores"),
+            Error::Core { entry, core } => {
+                write!(
+                    f,
+                    "entry {} comes from core {}, which is not in the session",
+                    entry, core
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A word received from a core
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Entry {
+    /// When the word was received, since the start of the recording
+    pub time: Duration,
+    /// Index of the core, e.g. in the order of the `<base>` addresses of `arm-dcc-host`
+    pub core: u32,
+    /// The word
+    pub word: u32,
+}
+
+/// A recorded session
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Session {
+    /// When the recording started
+    pub start: SystemTime,
+    /// Number of cores; the `core` of every entry is less than this
+    pub cores: u32,
+    /// The words, in the order they were received
+    pub entries: Vec<Entry>,
+    /// Whether the file ends in the middle of an entry, e.g. because the recording was cut short;
+    /// that entry is not part of `entries`
+    pub truncated: bool,
+}
+
+/// Writes a session file
+pub struct Recorder<W>
+where
+    W: Write,
+{
+    writer: W,
+    start: Instant,
+}
+
+impl<W> Recorder<W>
+where
+    W: Write,
+{
+    /// Starts recording the words of `cores` cores into `writer`; the recording starts now
+    pub fn new(mut writer: W, cores: u32) -> io::Result<Self> {
+        let start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&(start.as_micros() as u64).to_le_bytes())?;
+        writer.write_all(&cores.to_le_bytes())?;
+
+        Ok(Recorder {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    /// Records a word that core `core` has just sent
+    ///
+    /// **NOTE:** the entry is written as a whole, with no buffering, so a recording that's cut
+    /// short, e.g. by Ctrl-C, contains all the words received until then; wrap buffered writers
+    /// in `io::BufWriter` yourself
+    pub fn record(&mut self, core: u32, word: u32) -> io::Result<()> {
+        let mut entry = [0; ENTRY_LEN];
+        entry[..8].copy_from_slice(&(self.start.elapsed().as_micros() as u64).to_le_bytes());
+        entry[8..12].copy_from_slice(&core.to_le_bytes());
+        entry[12..].copy_from_slice(&word.to_le_bytes());
+
+        self.writer.write_all(&entry)
+    }
+}
+
+/// Parses the contents of a session file
+///
+/// A file that ends in the middle of an entry is not an error: the complete entries are returned
+/// and `Session::truncated` is set. An entry from a core beyond `Session::cores` is an error.
+pub fn read(bytes: &[u8]) -> Result<Session, Error> {
+    let rest = bytes.strip_prefix(&MAGIC[..]).ok_or(Error::Magic)?;
+    if rest.len() < HEADER_LEN {
+        return Err(Error::Truncated);
+    }
+    let (header, rest) = rest.split_at(HEADER_LEN);
+    let cores = u32_at(header, 8);
+
+    let chunks = rest.chunks_exact(ENTRY_LEN);
+    let truncated = !chunks.remainder().is_empty();
+
+    let mut entries = Vec::with_capacity(chunks.len());
+    for (i, chunk) in chunks.enumerate() {
+        let core = u32_at(chunk, 8);
+        if core >= cores {
+            return Err(Error::Core { entry: i, core });
+        }
+
+        entries.push(Entry {
+            time: Duration::from_micros(u64_at(chunk, 0)),
+            core,
+            word: u32_at(chunk, 12),
+        });
+    }
+
+    Ok(Session {
+        start: UNIX_EPOCH + Duration::from_micros(u64_at(header, 0)),
+        cores,
+        entries,
+        truncated,
+    })
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    let mut le = [0; 8];
+    le.copy_from_slice(&bytes[at..at + 8]);
+    u64::from_le_bytes(le)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    let mut le = [0; 4];
+    le.copy_from_slice(&bytes[at..at + 4]);
+    u32::from_le_bytes(le)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{read, Entry, Error, Recorder, MAGIC};
+
+    // a session of 3 cores that started 1 second after the epoch with the given entries
+    fn file(entries: &[(u64, u32, u32)]) -> Vec<u8> {
+        let mut file = MAGIC.to_vec();
+        file.extend_from_slice(&1_000_000_u64.to_le_bytes());
+        file.extend_from_slice(&3_u32.to_le_bytes());
+        for (time, core, word) in entries {
+            file.extend_from_slice(&time.to_le_bytes());
+            file.extend_from_slice(&core.to_le_bytes());
+            file.extend_from_slice(&word.to_le_bytes());
+        }
+        file
+    }
+
+    #[test]
+    fn entries() {
+        let session = read(&file(&[(5, 0, 0x48), (7, 2, 0xDCC1_0002)])).unwrap();
+        assert_eq!(session.start, UNIX_EPOCH + Duration::from_secs(1));
+        assert_eq!(
+            session.entries,
+            [
+                Entry {
+                    time: Duration::from_micros(5),
+                    core: 0,
+                    word: 0x48,
+                },
+                Entry {
+                    time: Duration::from_micros(7),
+                    core: 2,
+                    word: 0xDCC1_0002,
+                },
+            ]
+        );
+        assert!(!session.truncated);
+        assert_eq!(session.cores, 3);
+    }
+
+    #[test]
+    fn empty() {
+        let session = read(&file(&[])).unwrap();
+        assert!(session.entries.is_empty());
+        assert!(!session.truncated);
+        assert_eq!(session.cores, 3);
+    }
+
+    #[test]
+    fn core() {
+        assert_eq!(
+            read(&file(&[(5, 0, 0x48), (7, 3, 0x69)])),
+            Err(Error::Core { entry: 1, core: 3 })
+        );
+        assert_eq!(
+            read(&file(&[(5, 0xffff_ffff, 0x48)])),
+            Err(Error::Core {
+                entry: 0,
+                core: 0xffff_ffff
+            })
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let file = file(&[(5, 0, 0x48), (7, 0, 0x69)]);
+        let session = read(&file[..file.len() - 1]).unwrap();
+        assert_eq!(session.entries.len(), 1);
+        assert_eq!(session.entries[0].word, 0x48);
+        assert!(session.truncated);
+
+        // NOTE without its number of cores there's no session
+        assert_eq!(read(&file[..MAGIC.len() + 11]), Err(Error::Truncated));
+    }
+
+    #[test]
+    fn magic() {
+        assert_eq!(read(b"DCCSESS"), Err(Error::Magic));
+        assert_eq!(read(&file(&[])[1..]), Err(Error::Magic));
+    }
+
+    #[test]
+    fn recorder() {
+        let mut file = Vec::new();
+        let mut recorder = Recorder::new(&mut file, 2).unwrap();
+        recorder.record(1, 0x48).unwrap();
+
+        let session = read(&file).unwrap();
+        assert_eq!(session.cores, 2);
+        assert_eq!(
+            (session.entries[0].core, session.entries[0].word),
+            (1, 0x48)
+        );
+        assert!(session.start > UNIX_EPOCH);
+    }
+}
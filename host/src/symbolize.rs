@@ -0,0 +1,204 @@
+//! Symbolization of code addresses with the ELF file of the program
+//!
+//! `Symbolizer` looks code addresses up in the debug information (DWARF) of the program, or in its
+//! symbol table if it was built without debug information, e.g. the addresses sent with
+//! `arm_dcc::address::write_addresses`. `Stream::set_symbolizer` makes the decoder print them
+//! as function names and source locations.
+
+use std::{borrow::Cow, fmt, rc::Rc};
+
+use addr2line::Context;
+use gimli::{EndianRcSlice, RunTimeEndian};
+use object::{Object, ObjectSection, ObjectSymbol, ObjectSymbolTable};
+
+type Reader = EndianRcSlice<RunTimeEndian>;
+
+/// Error returned by `Symbolizer::new`
+#[derive(Debug)]
+pub enum Error {
+    /// The file is not a valid ELF file
+    Elf(object::Error),
+    /// The debug information of the file is not valid
+    Dwarf(gimli::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Elf(e) => write!(f, "invalid ELF file: {}", e),
+            Error::Dwarf(e) => write!(f, "invalid debug information: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<gimli::Error> for Error {
+    fn from(e: gimli::Error) -> Self {
+        Error::Dwarf(e)
+    }
+}
+
+/// A function that contains an address, and the source location of the address
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    /// Demangled name of the function, if known
+    pub function: Option<String>,
+    /// Source file, if known
+    pub file: Option<String>,
+    /// Line of the source file, if known
+    pub line: Option<u32>,
+}
+
+/// Formats the frame as `function (file:line)`
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.function.as_deref().unwrap_or("??"))?;
+
+        if let Some(file) = &self.file {
+            write!(f, " ({}", file)?;
+            if let Some(line) = self.line {
+                write!(f, ":{}", line)?;
+            }
+            f.write_str(")")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Looks up code addresses in the ELF file of the program
+pub struct Symbolizer {
+    context: Context<Reader>,
+    // start and end address of each executable section
+    code: Vec<(u64, u64)>,
+    // start address, size and demangled name of each function of the symbol table, sorted by
+    // address
+    symbols: Vec<(u64, u64, String)>,
+}
+
+impl Symbolizer {
+    /// Parses the contents of an ELF file
+    pub fn new(elf: &[u8]) -> Result<Self, Error> {
+        let file = object::File::parse(elf).map_err(Error::Elf)?;
+        let endian = if file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+
+        let dwarf = gimli::Dwarf::load(|id| -> Result<Reader, Error> {
+            let data = match file.section_by_name(id.name()) {
+                Some(section) => section.uncompressed_data().map_err(Error::Elf)?,
+                None => Cow::Borrowed(&[][..]),
+            };
+            Ok(EndianRcSlice::new(Rc::from(&*data), endian))
+        })?;
+        let context = Context::from_dwarf(dwarf).map_err(Error::Dwarf)?;
+
+        let code = file
+            .sections()
+            .filter(|section| section.kind() == object::SectionKind::Text)
+            .map(|section| (section.address(), section.address() + section.size()))
+            .collect();
+
+        let mut symbols = file
+            .symbol_table()
+            .map(|table| {
+                table
+                    .symbols()
+                    .filter(|symbol| symbol.kind() == object::SymbolKind::Text)
+                    .filter_map(|symbol| {
+                        let name = symbol.name().ok()?;
+                        Some((
+                            // NOTE the symbols of Thumb functions have bit 0 set
+                            symbol.address() & !1,
+                            symbol.size(),
+                            demangle(name),
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        symbols.sort();
+
+        Ok(Symbolizer {
+            context,
+            code,
+            symbols,
+        })
+    }
+
+    /// Returns the frames that contain `address`, the innermost one first; there's more than one
+    /// if functions were inlined into the function that contains `address`
+    ///
+    /// Returns an empty vector if nothing is known about `address`, or if it's not in an executable
+    /// section of the ELF file.
+    pub fn lookup(&self, address: u64) -> Vec<Frame> {
+        let mut frames = Vec::new();
+
+        // NOTE the debug information of the functions removed by the linker starts at address 0
+        if !self
+            .code
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&address))
+        {
+            return frames;
+        }
+
+        if let Ok(mut iter) = self.context.find_frames(address).skip_all_loads() {
+            while let Ok(Some(frame)) = iter.next() {
+                frames.push(Frame {
+                    function: frame
+                        .function
+                        .and_then(|function| function.raw_name().ok().map(|name| demangle(&name))),
+                    file: frame
+                        .location
+                        .as_ref()
+                        .and_then(|location| location.file)
+                        .map(str::to_string),
+                    line: frame.location.and_then(|location| location.line),
+                });
+            }
+        }
+
+        if frames.iter().all(|frame| frame.function.is_none()) {
+            if let Some(function) = self.symbol(address) {
+                match frames.first_mut() {
+                    Some(frame) => frame.function = Some(function),
+                    None => frames.push(Frame {
+                        function: Some(function),
+                        file: None,
+                        line: None,
+                    }),
+                }
+            }
+        }
+
+        frames
+    }
+
+    // function of the symbol table that contains `address`
+    fn symbol(&self, address: u64) -> Option<String> {
+        let i = match self
+            .symbols
+            .binary_search_by(|(start, ..)| start.cmp(&address))
+        {
+            Ok(i) => i,
+            Err(0) => return None,
+            Err(i) => i - 1,
+        };
+
+        let (start, size, name) = &self.symbols[i];
+        if address < start + size.max(&1) {
+            Some(name.clone())
+        } else {
+            None
+        }
+    }
+}
+
+// NOTE the alternate format leaves out the hash of Rust symbols
+fn demangle(name: &str) -> String {
+    format!("{:#}", rustc_demangle::demangle(name))
+}
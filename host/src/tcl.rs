@@ -0,0 +1,169 @@
+//! Client of the Tcl RPC server of OpenOCD
+//!
+//! OpenOCD listens for Tcl commands on port 6666 by default; each command, and each response, ends
+//! with a `0x1a` byte. `ApRegisters` uses the `apreg` command of the DAP to access the debug
+//! registers of the core through a memory AP while the core runs, which lets `poll::Poller` drain
+//! the DCC with any adapter OpenOCD supports (ST-Link, FTDI based adapters, J-Link, etc.).
+//!
+//! The MEM-AP is expected to be configured for 32-bit accesses, as OpenOCD leaves it after
+//! examining the target. OpenOCD must not be polling the DCC itself at the same time, e.g. with
+//! `target_request debugmsgs enable`.
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::poll::DebugRegisters;
+
+/// Default address of the Tcl RPC server
+pub const DEFAULT_ADDRESS: &str = "localhost:6666";
+
+// ends commands and responses
+const TERMINATOR: u8 = 0x1a;
+
+// MEM-AP registers: transfer address and data read / write
+const TAR: u32 = 0x04;
+const DRW: u32 = 0x0c;
+
+/// Error returned by `Client`
+#[derive(Debug)]
+pub enum Error {
+    /// The connection to OpenOCD failed
+    Io(io::Error),
+    /// OpenOCD reported an error while running the command
+    Command(String),
+    /// The response of OpenOCD is not a number
+    Response(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Command(message) => write!(f, "OpenOCD error: {}", message),
+            Error::Response(response) => write!(f, "unexpected response: `{}`", response),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Connection to the Tcl RPC server of OpenOCD
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    /// Connects to the OpenOCD instance listening at `address`, e.g. `DEFAULT_ADDRESS`
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, Error> {
+        let writer = TcpStream::connect(address)?;
+        // NOTE each poll is a small request followed by a small response
+        writer.set_nodelay(true)?;
+
+        Ok(Client {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Runs a Tcl command and returns its result
+    pub fn command(&mut self, command: &str) -> Result<String, Error> {
+        // NOTE `catch` tells errors apart from results
+        write!(self.writer, "list [catch {{{}}} result] $result", command)?;
+        self.writer.write_all(&[TERMINATOR])?;
+
+        let mut response = Vec::new();
+        self.reader.read_until(TERMINATOR, &mut response)?;
+        if response.pop() != Some(TERMINATOR) {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        match response.split_at(response.find(' ').unwrap_or(response.len())) {
+            ("0", result) => Ok(unbrace(result.trim_start()).to_string()),
+            (_, message) => Err(Error::Command(unbrace(message.trim_start()).to_string())),
+        }
+    }
+}
+
+/// The debug registers of a core, reached through a memory AP of the DAP
+pub struct ApRegisters {
+    client: Client,
+    // Tcl expression that evaluates to the name of the DAP
+    dap: String,
+    ap: u8,
+    base: u32,
+}
+
+impl ApRegisters {
+    /// Accesses the debug registers at address `base` of memory AP `ap` of the DAP named `dap`
+    ///
+    /// `None` selects the first DAP of the OpenOCD configuration.
+    pub fn new(client: Client, dap: Option<&str>, ap: u8, base: u32) -> Self {
+        ApRegisters {
+            client,
+            dap: dap.unwrap_or("[lindex [dap names] 0]").to_string(),
+            ap,
+            base,
+        }
+    }
+
+    fn select(&self, offset: u32) -> String {
+        format!(
+            "{} apreg {} {:#x} {:#x}",
+            self.dap,
+            self.ap,
+            TAR,
+            self.base.wrapping_add(offset)
+        )
+    }
+}
+
+impl DebugRegisters for ApRegisters {
+    type Error = Error;
+
+    fn read(&mut self, offset: u32) -> Result<u32, Error> {
+        let command = format!(
+            "{}; {} apreg {} {:#x}",
+            self.select(offset),
+            self.dap,
+            self.ap,
+            DRW
+        );
+        let response = self.client.command(&command)?;
+
+        let value = match response.strip_prefix("0x") {
+            Some(digits) => u32::from_str_radix(digits, 16).ok(),
+            None => response.parse().ok(),
+        };
+        value.ok_or(Error::Response(response))
+    }
+
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), Error> {
+        let command = format!(
+            "{}; {} apreg {} {:#x} {:#x}",
+            self.select(offset),
+            self.dap,
+            self.ap,
+            DRW,
+            value
+        );
+        self.client.command(&command).map(drop)
+    }
+}
+
+// removes the braces Tcl puts around list elements that contain spaces
+fn unbrace(s: &str) -> &str {
+    s.strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .unwrap_or(s)
+}
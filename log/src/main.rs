@@ -0,0 +1,119 @@
+//! Renders binary log records received over the Debug Communication Channel (DCC)
+//!
+//! Usage: `dcc-log [--channels <dir>] <words>`
+//!
+//! `<words>` holds the raw words received from the DCC, each one as 4 little endian bytes. The log
+//! records found among them (see the `arm_dcc::record` module) are printed as lines of text; text
+//! sent by the device is printed as is.
+//!
+//! Text that the device sent compressed (see the `arm_dcc::compress` module) is decompressed.
+//!
+//! With `--channels` the data of each channel (see the `arm_dcc::channel` module) is written to
+//! `<dir>/channel-<id>`, one file per channel; otherwise it's discarded.
+//!
+//! If the device has the `crc` or `sequence` features enabled, and announced them with a handshake
+//! frame, corrupted and lost frames are reported on stderr; a corrupted log record is dropped.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use arm_dcc::{
+    compress::Decompressor,
+    decode::{Decoder, Item},
+};
+use arm_dcc_host::render::Renderer;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let (channels_dir, input) = match &args[..] {
+        [input] => (None, input),
+        [flag, dir, input] if flag == "--channels" => (Some(PathBuf::from(dir)), input),
+        _ => return Err("usage: dcc-log [--channels <dir>] <words>".to_string()),
+    };
+
+    let bytes = fs::read(input).map_err(|e| format!("couldn't read {}: {}", input, e))?;
+    let mut channels = HashMap::new();
+
+    let mut decoder = Decoder::new();
+    let mut decompressor = Decompressor::new();
+    let mut renderer = Renderer::new();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for chunk in bytes.chunks_exact(4) {
+        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+
+        if let Some(item) = decoder.push(word) {
+            if let Item::Channel(id, chunk) = item {
+                if let Some(dir) = &channels_dir {
+                    write_channel(&mut channels, dir, id, chunk.as_bytes())?;
+                }
+                continue;
+            }
+
+            if let Item::Compressed(chunk) = item {
+                let mut text = Vec::new();
+                decompressor.push(chunk.as_bytes(), |byte| text.push(byte));
+                stdout
+                    .write_all(&text)
+                    .map_err(|e| format!("couldn't write to stdout: {}", e))?;
+                continue;
+            }
+
+            // NOTE the compressed text resumes with the next frame
+            match item {
+                Item::BadCrc => {
+                    eprintln!("warning: a frame was corrupted (CRC mismatch)");
+                    decompressor.reset();
+                }
+                Item::Lost(frames) => {
+                    eprintln!("warning: {} frames were lost", frames);
+                    decompressor.reset();
+                }
+                _ => {}
+            }
+
+            if let Some(output) = renderer.push(item) {
+                stdout
+                    .write_all(&output)
+                    .map_err(|e| format!("couldn't write to stdout: {}", e))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_channel(
+    channels: &mut HashMap<u8, File>,
+    dir: &Path,
+    id: u8,
+    bytes: &[u8],
+) -> Result<(), String> {
+    let path = dir.join(format!("channel-{}", id));
+    let file = match channels.entry(id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(
+            File::create(&path)
+                .map_err(|e| format!("couldn't create {}: {}", path.display(), e))?,
+        ),
+    };
+
+    file.write_all(bytes)
+        .map_err(|e| format!("couldn't write to {}: {}", path.display(), e))
+}
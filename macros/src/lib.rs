@@ -0,0 +1,86 @@
+//! Procedural macros for the `arm-dcc` crate
+//!
+//! These macros are re-exported by `arm-dcc` when its `macros` feature is enabled; use them
+//! through that crate.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, AttributeArgs, FnArg, ItemFn, Meta, NestedMeta, Pat,
+};
+
+/// Traces calls to the function over the DCC
+///
+/// See the documentation of `arm_dcc::dcc_instrument`.
+#[proc_macro_attribute]
+pub fn dcc_instrument(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let f = parse_macro_input!(input as ItemFn);
+
+    let mut cycles = false;
+    for arg in args {
+        match arg {
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("cycles") => cycles = true,
+            _ => {
+                return parse_error(arg.span(), "expected `cycles` or no arguments");
+            }
+        }
+    }
+
+    if let Some(constness) = f.sig.constness {
+        return parse_error(constness.span(), "`const` functions can't be instrumented");
+    }
+
+    let name = f.sig.ident.to_string();
+    let mut show_args = vec![];
+    for (i, input) in f.sig.inputs.iter().enumerate() {
+        if i != 0 {
+            show_args.push(quote!(::arm_dcc::write_str(", ");));
+        }
+
+        match input {
+            // NOTE receivers are not printed; `self` can be large
+            FnArg::Receiver(_) => show_args.push(quote!(::arm_dcc::write_str("self");)),
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(pat) => {
+                    let ident = &pat.ident;
+                    let name = ident.to_string();
+                    show_args.push(quote!((&::arm_dcc::TraceArg(&#ident)).trace(#name);));
+                }
+                _ => show_args.push(quote!(::arm_dcc::write_str("_");)),
+            },
+        }
+    }
+
+    let attrs = &f.attrs;
+    let vis = &f.vis;
+    let sig = &f.sig;
+    let stmts = &f.block.stmts;
+    quote!(
+        #(#attrs)*
+        #vis #sig {
+            let __dcc_instrument = {
+                #[allow(unused_imports)]
+                use ::arm_dcc::{TraceDebug as _, TraceOpaque as _};
+
+                ::arm_dcc::write_str(concat!("-> ", #name, "("));
+                #(#show_args)*
+                ::arm_dcc::write_str(")\n");
+                ::arm_dcc::TraceExit::new(#name, #cycles)
+            };
+
+            #(#stmts)*
+        }
+    )
+    .into()
+}
+
+fn parse_error(span: Span, message: &str) -> TokenStream {
+    syn::Error::new(span, message).to_compile_error().into()
+}
@@ -0,0 +1,68 @@
+use std::{env, fs, path::PathBuf};
+
+/// Prefixes of the M-profile targets, which have no CPSR
+const M_PROFILE: &[&str] = &["thumbv6m-", "thumbv7em-", "thumbv7m-", "thumbv8m."];
+
+/// Thumb targets whose instruction set (Thumb-1) can't store the high registers
+const THUMB1: &[&str] = &[
+    "thumbv4t-none-eabi",
+    "thumbv5te-none-eabi",
+    "thumbv6-none-eabi",
+];
+
+/// Prefixes of the ARMv7-A, ARMv7-R and ARMv8-R targets, whose cores have MPIDR; these also match
+/// the ARMv7-M targets, which are filtered out separately
+const ARMV7: &[&str] = &["armebv7r-", "armv7", "armv8r-", "thumbv7", "thumbv8r-"];
+
+fn main() {
+    let target = env::var("TARGET").unwrap();
+    let arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap();
+
+    // the register dump is only implemented for AArch64 and for A-profile and R-profile cores in
+    // ARM or Thumb-2 state
+    println!("cargo:rustc-check-cfg=cfg(panic_dcc_registers)");
+    if arch == "aarch64"
+        || (arch == "arm"
+            && !M_PROFILE.iter().any(|prefix| target.starts_with(prefix))
+            && !THUMB1.contains(&&*target))
+    {
+        println!("cargo:rustc-cfg=panic_dcc_registers");
+    }
+
+    // the core ID tag needs MPIDR
+    println!("cargo:rustc-check-cfg=cfg(panic_dcc_mpidr)");
+    if arch == "aarch64"
+        || (ARMV7.iter().any(|prefix| target.starts_with(prefix))
+            && !M_PROFILE.iter().any(|prefix| target.starts_with(prefix)))
+    {
+        println!("cargo:rustc-cfg=panic_dcc_mpidr");
+    }
+
+    // targets without an atomic swap, like `armv4t-none-eabi` and `thumbv6m-none-eabi`
+    let atomics = env::var("CARGO_CFG_TARGET_HAS_ATOMIC").unwrap_or_default();
+    println!("cargo:rustc-check-cfg=cfg(panic_dcc_no_atomics)");
+    if !atomics.split(',').any(|width| width == "8") {
+        println!("cargo:rustc-cfg=panic_dcc_no_atomics");
+    }
+
+    // number of bytes dumped by the `stack-dump` feature; rounded up to a whole number of words
+    let bytes = match env::var("PANIC_DCC_STACK_DUMP_BYTES") {
+        Ok(bytes) => bytes
+            .parse::<usize>()
+            .expect("PANIC_DCC_STACK_DUMP_BYTES must be a number of bytes"),
+        Err(_) => 256,
+    };
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(
+        out_dir.join("stack.rs"),
+        format!(
+            "/// Number of bytes dumped by the `stack-dump` feature\n\
+             const STACK_DUMP_BYTES: usize = {};\n",
+            (bytes + 3) & !3
+        ),
+    )
+    .unwrap();
+    println!("cargo:rerun-if-env-changed=PANIC_DCC_STACK_DUMP_BYTES");
+
+    println!("cargo:rerun-if-changed=build.rs");
+}
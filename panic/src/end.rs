@@ -0,0 +1,71 @@
+//! What the panic handler does after reporting the panic; see the optional features
+
+#[cfg(any(
+    feature = "halt-debugger",
+    all(any(feature = "bkpt", feature = "udf"), not(feature = "custom-action"))
+))]
+use core::arch::asm;
+#[cfg(all(
+    not(debug_assertions),
+    not(any(feature = "bkpt", feature = "udf", feature = "custom-action"))
+))]
+use core::sync::atomic::{self, Ordering};
+
+#[cfg(feature = "custom-action")]
+extern "Rust" {
+    fn panic_dcc_action() -> !;
+}
+
+#[cfg(feature = "custom-action")]
+pub fn end() -> ! {
+    unsafe { panic_dcc_action() }
+}
+
+#[cfg(all(feature = "udf", not(feature = "custom-action")))]
+pub fn end() -> ! {
+    unsafe { asm!("udf #0", options(noreturn, nomem, nostack)) }
+}
+
+#[cfg(all(feature = "bkpt", not(any(feature = "udf", feature = "custom-action"))))]
+pub fn end() -> ! {
+    // NOTE resuming from the breakpoint halts the core again
+    loop {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            asm!("bkpt #0", options(nomem, nostack))
+        }
+
+        #[cfg(target_arch = "aarch64")]
+        unsafe {
+            asm!("brk #0", options(nomem, nostack))
+        }
+    }
+}
+
+#[cfg(not(any(feature = "bkpt", feature = "udf", feature = "custom-action")))]
+pub fn end() -> ! {
+    loop {
+        // NOTE the compiler_fence prevents this loop from turning into an abort instruction when
+        // this crate is compiled with optimizations
+        #[cfg(not(debug_assertions))]
+        atomic::compiler_fence(Ordering::SeqCst)
+    }
+}
+
+/// Stops an attached debugger
+///
+/// The breakpoint instruction doesn't advance the PC: the debugger must step over it (e.g. with
+/// `set $pc = $pc + 2` in GDB, `+ 4` for ARM and A64 code) for the core to continue with the next
+/// instruction; otherwise resuming executes the breakpoint, and halts, again.
+#[cfg(feature = "halt-debugger")]
+pub fn halt() {
+    #[cfg(target_arch = "arm")]
+    unsafe {
+        asm!("bkpt #0", options(nomem, nostack))
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        asm!("brk #0", options(nomem, nostack))
+    }
+}
@@ -0,0 +1,32 @@
+//! Detection of panics that happen while the panic handler is reporting a panic
+
+#[cfg(not(panic_dcc_no_atomics))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(panic_dcc_no_atomics))]
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Marks the panic handler as entered; returns `true` if it had already been entered
+#[cfg(not(panic_dcc_no_atomics))]
+pub fn enter() -> bool {
+    PANICKING.swap(true, Ordering::Relaxed)
+}
+
+// NOTE some targets (e.g. `armv4t-none-eabi` and `thumbv6m-none-eabi`) have no atomic swap; their
+// cores are single core so only an interrupt that panics between the load and the store below is
+// missed
+#[cfg(panic_dcc_no_atomics)]
+static mut PANICKING: bool = false;
+
+/// Marks the panic handler as entered; returns `true` if it had already been entered
+#[cfg(panic_dcc_no_atomics)]
+pub fn enter() -> bool {
+    use core::ptr;
+
+    unsafe {
+        let panicking = ptr::addr_of_mut!(PANICKING);
+        let entered = ptr::read_volatile(panicking);
+        ptr::write_volatile(panicking, true);
+        entered
+    }
+}
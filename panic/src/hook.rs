@@ -0,0 +1,29 @@
+//! Application hook that runs after the panic has been reported
+
+use core::panic::PanicInfo;
+
+use arm_dcc::FnCell;
+
+static HOOK: FnCell<fn(&PanicInfo)> = FnCell::new();
+
+/// Registers a function that the panic handler will call after reporting the panic and before
+/// the post-panic action (see the optional features)
+///
+/// Use it to put the system in a safe state, e.g. to de-energize outputs or to stop motors, or to
+/// log the panic to non-volatile memory. If the hook panics the handler reports "panicked while
+/// panicking" and goes straight to the post-panic action.
+pub fn set_hook(hook: fn(&PanicInfo)) {
+    HOOK.set(hook)
+}
+
+/// Unregisters the function set with `set_hook`
+pub fn clear_hook() {
+    HOOK.clear()
+}
+
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
+pub(crate) fn call(info: &PanicInfo) {
+    if let Some(hook) = HOOK.get() {
+        hook(info)
+    }
+}
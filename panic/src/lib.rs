@@ -1,5 +1,23 @@
 //! Report panic messages to the host using the Debug Communication Channel (DCC)
 //!
+//! The whole panic message is reported, formatting arguments included, along with the location of
+//! the panic. On A-profile and R-profile cores, except on the Thumb-1 targets, the message is
+//! followed by a dump of r0 - r12, SP, LR, PC and CPSR as seen by the panic handler. On AArch64
+//! the dump has x0 - x30, SP, and the ELR and SPSR of the current exception level (which must be
+//! EL1 or higher).
+//!
+//! On ARMv7, ARMv8-R and AArch64 cores the message is tagged with the affinity fields of MPIDR,
+//! e.g. `[core 0.0.1]`, so the panics of the cores of a multi-core system (e.g. a Cortex-R5 in
+//! split mode) can be told apart when their logs are merged.
+//!
+//! If a timestamp source was registered with `arm_dcc::set_timestamp_source` the message is
+//! prefixed with the time of the panic, e.g. `[   1.234567]`, in the same format as the log lines
+//! of `arm-dcc`.
+//!
+//! If a panic happens while the panic handler is reporting a panic, e.g. in a `Display`
+//! implementation used by the panic message, only the fixed string "panicked while panicking" is
+//! reported for the second panic.
+//!
 //! # Example
 //!
 //! ## Device side
@@ -8,7 +26,8 @@
 //! use panic_dcc;
 //!
 //! fn main() {
-//!     panic!("Oops");
+//!     let x = 42;
+//!     panic!("value was {}", x);
 //! }
 //! ```
 //!
@@ -43,51 +62,219 @@
 //! ``` text
 //! $ # on another terminal
 //! $ tail -f dcc.log
-//! panicked at 'Oops', src/hello.rs:4:4
+//! [core 0.0.0] panicked at src/hello.rs:5:5:
+//! value was 42
+//!   r0 = 0x0000002a    r1 = 0x00000000    r2 = 0x00000012    r3 = 0x00000000
+//!   r4 = 0x0010c6d4    r5 = 0x00000000    r6 = 0x00000000    r7 = 0x0010ffd8
+//!   r8 = 0x00000000    r9 = 0x00000000   r10 = 0x00000000   r11 = 0x00000000
+//!  r12 = 0x00000000    sp = 0x0010ffb8    lr = 0x00100a4c    pc = 0x001003f0
+//! cpsr = 0x600001df
 //! ```
 //!
 //! # Supported Rust version
 //!
-//! - Rust >=1.31 when the target is one of the 4 ARMv7 Cortex-R targets.
+//! This crate is guaranteed to compile on stable Rust 1.59 and up (the release that stabilized
+//! `asm!`) on the following targets:
+//!
+//! - the 4 ARMv7 Cortex-R targets and the ARMv8-R target (`armv8r-none-eabihf`, e.g. Cortex-R52)
+//!
+//! - the ARMv7-A targets (`armv7a-none-eabi`, `armv7a-none-eabihf`, `armv7-unknown-linux-gnueabi`
+//!   and `armv7-unknown-linux-gnueabihf`)
+//!
+//! - the ARMv6 targets (`armv6-none-eabi`, `armv6-none-eabihf`, `arm-unknown-linux-gnueabi` and
+//!   `arm-unknown-linux-gnueabihf`, e.g. ARM11 cores like the one in the BCM2835)
 //!
-//! - All the other ARM targets require enabling the `inline-asm`, which requires a nightly
-//! compiler.
+//!   On Linux the kernel must allow user space access to the DCC (the UDCCdis bit of DBGDSCR
+//!   must be cleared), otherwise the DCC instructions are undefined.
+//!
+//! - the ARMv4T / ARMv5TE targets (`armv4t-none-eabi`, `armv5te-none-eabi`,
+//!   `armv4t-unknown-linux-gnueabi` and `armv5te-unknown-linux-gnueabi`, e.g. ARM7TDMI and
+//!   ARM926EJ-S). These cores use the EmbeddedICE comms channel.
+//!
+//! - the Thumb variants of the above targets (`thumbv4t-none-eabi`, `thumbv5te-none-eabi`,
+//!   `thumbv6-none-eabi`, `thumbv7a-none-eabi`, `thumbv7a-none-eabihf`,
+//!   `thumbv7neon-unknown-linux-gnueabihf`, `thumbv7r-none-eabi`, `thumbv7r-none-eabihf` and
+//!   `thumbv8r-none-eabihf`). Thumb-1 has no coprocessor instructions so on the ARMv4T, ARMv5TE
+//!   and ARMv6 ones the DCC is accessed through ARM state routines.
+//!
+//! - the AArch64 targets, e.g. `aarch64-unknown-none`. On AArch64 the DCC is accessed through the
+//!   MDCCSR_EL0, DBGDTRTX_EL0 and DBGDTRRX_EL0 registers.
+//!
+//! - the Cortex-M targets (`thumbv6m-none-eabi`, `thumbv7m-none-eabi`, `thumbv7em-none-eabi`,
+//!   `thumbv7em-none-eabihf` and the `thumbv8m.*` targets). M-profile cores have no DCC so the
+//!   output goes to stimulus port 0 of the ITM instead, one 4-byte packet per DCC word. The
+//!   debugger must enable the ITM and stimulus port 0.
 //!
 //! # Optional features
 //!
-//! ## `inline-asm`
+//! ## `extern-asm`
 //!
-//! When this feature is enabled `dcc::write` is implemented using inline assembly (`asm!`) and
-//! compiling this crate requires nightly. Note that this feature requires that the compilation
-//! target is one of the 4 ARMv7 Cortex-R targets.
+//! Enables the `extern-asm` feature of `arm-dcc`, which makes it call assembly routines that are
+//! shipped as prebuilt static libraries instead of using `asm!` and `global_asm!`. Note that the
+//! register dump of this crate uses `asm!` regardless.
 //!
-//! When this feature is disabled `dcc::write` is implemented using FFI calls into an external
-//! assembly file and compiling this crate works on stable and beta.
-
+//! ## `bkpt`
+//!
+//! After reporting the panic, executes a breakpoint instruction (`BKPT` / `BRK`), which halts the
+//! core if a debugger is attached, instead of spinning forever. `BKPT` requires ARMv5 or newer.
+//!
+//! ## `halt-debugger`
+//!
+//! Executes a breakpoint instruction (`BKPT` / `BRK`) as soon as the panic handler is entered,
+//! before reporting the panic, so an attached debugger stops with the frames of the code that
+//! panicked on the stack. The breakpoint instruction doesn't advance the PC so resuming the core
+//! executes it, and halts, again; step over it (e.g. `set $pc = $pc + 4` in GDB, `+ 2` for Thumb
+//! code) and resume, and the panic is reported as usual.
+//! Without a debugger the breakpoint raises a prefetch abort (a HardFault on Cortex-M), or is
+//! ignored, depending on the core. `BKPT` requires ARMv5 or newer.
+//!
+//! ## `udf`
+//!
+//! After reporting the panic, executes a permanently undefined instruction (`UDF`), which raises
+//! an undefined instruction exception (a HardFault on Cortex-M). This feature has precedence
+//! over the `bkpt` feature.
+//!
+//! ## `custom-action`
+//!
+//! After reporting the panic, calls a function provided by the application, e.g. to reset the
+//! device. This feature has precedence over the `bkpt` and `udf` features.
+//!
+//! ``` ignore
+//! #[no_mangle]
+//! fn panic_dcc_action() -> ! {
+//!     // e.g. trigger a watchdog reset
+//!     loop {}
+//! }
+//! ```
+//!
+//! ## `exit`
+//!
+//! After reporting the panic, sends exit status 101, the status of a Rust program that panics on
+//! the host, with `arm_dcc::exit::report`, so a test runner like `dcc-run` stops the core and
+//! fails. What the panic handler does afterwards is not affected.
+//!
+//! ## `no-location`
+//!
+//! Reports only the panic message, without the file, line and column of the panic. This feature
+//! requires Rust 1.81 or newer (`PanicInfo::message`).
+//!
+//! **NOTE:** The location strings are part of the code that calls `panic!` so this feature alone
+//! doesn't remove them from the binary. To also drop them build with the (nightly) rustc flag
+//! `-Z location-detail=none`, which stops the compiler from emitting them in the first place.
+//!
+//! ## `stack-dump`
+//!
+//! After the panic message, dumps the top of the active stack, starting at the stack pointer of
+//! the panic handler, as 32-bit words. The host can use it to recover local variables and return
+//! addresses when the program was compiled without frame pointers.
+//!
+//! 256 bytes are dumped by default; set the `PANIC_DCC_STACK_DUMP_BYTES` environment variable at
+//! build time to change that. The whole range must be readable memory, so make sure it doesn't
+//! extend past the end of the memory that holds the stack.
+//!
+//! ## `coredump`
+//!
+//! After the panic message, streams a core dump with the register dump and the memory regions
+//! registered with `arm_dcc::coredump::set_regions`; the `dcc-coredump` tool converts it into an
+//! ELF core file that GDB can load. Only available on the 32-bit targets that have the register
+//! dump.
+//!
+//! ## `persist`
+//!
+//! Also saves the panic message to a RAM region that survives resets, so it can be retrieved when
+//! the panic happened without a debugger attached; see the `persist` module.
+//!
+//! ## `inline-asm`
+//!
+//! Deprecated and ignored. Inline assembly is used by default.
 
 #![deny(missing_docs)]
 #![deny(warnings)]
 #![no_std]
 
-#[cfg(not(debug_assertions))]
-use core::sync::atomic::{self, Ordering};
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
 use core::{fmt::Write, panic::PanicInfo};
 
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
 use arm_dcc::Writer;
 
+pub use crate::hook::{clear_hook, set_hook};
+
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
+mod end;
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
+mod guard;
+mod hook;
+
+#[cfg(all(
+    not(test),
+    any(target_arch = "arm", target_arch = "aarch64"),
+    feature = "stack-dump"
+))]
+mod stack;
+
+#[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), feature = "persist"))]
+pub mod persist;
+
+// NOTE `panic_dcc_mpidr` and `panic_dcc_registers` are set by the build script
+#[cfg(all(not(test), panic_dcc_mpidr))]
+mod mpidr;
+#[cfg(all(not(test), panic_dcc_registers))]
+mod registers;
+
+// NOTE on the host `std`, which is linked by the test harness and by the `std` feature of
+// `arm-dcc`, already provides a panic handler
+#[cfg(all(not(test), any(target_arch = "arm", target_arch = "aarch64")))]
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
+    // capture the registers before the code below modifies them
+    #[cfg(panic_dcc_registers)]
+    let registers = registers::Registers::capture();
+    #[cfg(feature = "stack-dump")]
+    let stack = stack::Stack::here();
+
+    // stop the debugger while the frames of the code that panicked are still on the stack
+    #[cfg(feature = "halt-debugger")]
+    end::halt();
+
     // TODO uncomment
     // cortex_r::disable_fiq();
     // cortex_r::disable_irq();
 
-    // NOTE this operation never returns `Err`
-    writeln!(Writer, "{}", info).ok();
+    // formatting the message, the hook or an interrupt handler panicked while reporting the first
+    // panic; don't format anything this time
+    if guard::enter() {
+        arm_dcc::write_str("\npanicked while panicking\n");
+        end::end()
+    }
 
-    loop {
-        // NOTE the compiler_fence prevents this loop from turning into an abort instruction when
-        // this crate is compiled with optimizations
-        #[cfg(not(debug_assertions))]
-        atomic::compiler_fence(Ordering::SeqCst)
+    if let Some(timestamp) = arm_dcc::timestamp() {
+        write!(Writer, "{} ", timestamp).ok();
     }
+    #[cfg(panic_dcc_mpidr)]
+    write!(Writer, "{}", mpidr::CoreId::read()).ok();
+    // NOTE `PanicInfo`'s `Display` implementation renders the formatted message, not just `&str`
+    // payloads. This operation never returns `Err`
+    #[cfg(not(feature = "no-location"))]
+    writeln!(Writer, "{}", info).ok();
+    #[cfg(feature = "no-location")]
+    writeln!(Writer, "panicked: {}", info.message()).ok();
+    // NOTE formatted a second time; the message is not buffered anywhere
+    #[cfg(all(not(feature = "no-location"), feature = "persist"))]
+    writeln!(persist::Persist::new(), "{}", info).ok();
+    #[cfg(all(feature = "no-location", feature = "persist"))]
+    writeln!(persist::Persist::new(), "panicked: {}", info.message()).ok();
+    #[cfg(panic_dcc_registers)]
+    write!(Writer, "{}", registers).ok();
+    #[cfg(feature = "stack-dump")]
+    write!(Writer, "{}", stack).ok();
+    #[cfg(all(feature = "coredump", target_arch = "arm", panic_dcc_registers))]
+    arm_dcc::coredump::write(arm_dcc::coredump::Reason::Panic, registers.words());
+
+    hook::call(info);
+
+    #[cfg(feature = "exit")]
+    arm_dcc::exit::report(101);
+
+    end::end()
 }
@@ -0,0 +1,60 @@
+//! Identification of the core that panicked
+
+use core::{arch::asm, fmt};
+
+/// The affinity fields of the Multiprocessor Affinity Register (MPIDR)
+#[cfg(target_arch = "arm")]
+pub struct CoreId(u32);
+
+/// The affinity fields of the Multiprocessor Affinity Register (MPIDR_EL1)
+#[cfg(target_arch = "aarch64")]
+pub struct CoreId(u64);
+
+impl CoreId {
+    /// Reads MPIDR
+    #[cfg(target_arch = "arm")]
+    pub fn read() -> Self {
+        let mpidr: u32;
+        unsafe {
+            asm!(
+                "mrc p15, 0, {}, c0, c0, 5",
+                out(reg) mpidr,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+
+        CoreId(mpidr)
+    }
+
+    /// Reads MPIDR_EL1; the current exception level must be EL1 or higher
+    #[cfg(target_arch = "aarch64")]
+    pub fn read() -> Self {
+        let mpidr: u64;
+        unsafe {
+            asm!(
+                "mrs {}, MPIDR_EL1",
+                out(reg) mpidr,
+                options(nomem, nostack, preserves_flags),
+            );
+        }
+
+        CoreId(mpidr)
+    }
+}
+
+/// Formats the tag that prefixes the panic message, `[core Aff2.Aff1.Aff0] ` (AArch64:
+/// `[core Aff3.Aff2.Aff1.Aff0] `)
+impl fmt::Display for CoreId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[core ")?;
+        #[cfg(target_arch = "aarch64")]
+        write!(f, "{}.", (self.0 >> 32) & 0xff)?;
+        write!(
+            f,
+            "{}.{}.{}] ",
+            (self.0 >> 16) & 0xff,
+            (self.0 >> 8) & 0xff,
+            self.0 & 0xff
+        )
+    }
+}
@@ -0,0 +1,111 @@
+//! Copy of the last panic message that survives a reset
+//!
+//! With the `persist` feature the panic handler also writes the panic message to a RAM region
+//! that the startup code doesn't initialize. The application provides the region by defining the
+//! `_panic_dcc_persist_start` and `_panic_dcc_persist_end` symbols in its linker script, e.g.
+//!
+//! ``` text
+//! MEMORY
+//! {
+//!   /* .. */
+//!   PANIC : ORIGIN = 0x0013fc00, LENGTH = 1K
+//! }
+//!
+//! _panic_dcc_persist_start = ORIGIN(PANIC);
+//! _panic_dcc_persist_end = ORIGIN(PANIC) + LENGTH(PANIC);
+//! ```
+//!
+//! After the reset the application can retrieve the message with `take` and, for example, report
+//! it once a debugger is attached. Messages that don't fit in the region are truncated.
+
+use core::{fmt, mem, ptr, slice};
+
+/// Marks a region that holds a message
+const MAGIC: u32 = 0x0dcc_dead;
+
+/// Size of the header (magic word and message length)
+const HEADER: usize = 2 * mem::size_of::<u32>();
+
+extern "C" {
+    static mut _panic_dcc_persist_start: u32;
+    static mut _panic_dcc_persist_end: u32;
+}
+
+fn region() -> (*mut u32, usize) {
+    // NOTE taking the address of an extern static is only safe in recent compilers
+    #[allow(unused_unsafe)]
+    unsafe {
+        let start = ptr::addr_of_mut!(_panic_dcc_persist_start);
+        let end = ptr::addr_of_mut!(_panic_dcc_persist_end);
+        (start, end as usize - start as usize)
+    }
+}
+
+/// Returns the message saved by the panic handler before the last reset, if any, and clears it
+///
+/// The returned bytes stay valid until the next panic.
+pub fn take() -> Option<&'static [u8]> {
+    let (start, size) = region();
+
+    if size < HEADER {
+        return None;
+    }
+
+    unsafe {
+        if ptr::read_volatile(start) != MAGIC {
+            return None;
+        }
+        ptr::write_volatile(start, 0);
+
+        let len = ptr::read_volatile(start.add(1)) as usize;
+        if len > size - HEADER {
+            // garbage left by a power cycle
+            return None;
+        }
+
+        Some(slice::from_raw_parts(start.add(2) as *const u8, len))
+    }
+}
+
+/// Writes to the persistent region
+pub(crate) struct Persist {
+    len: usize,
+}
+
+impl Persist {
+    /// Erases the region; what's written next becomes the saved message
+    pub(crate) fn new() -> Self {
+        let (start, size) = region();
+
+        if size >= HEADER {
+            unsafe {
+                ptr::write_volatile(start, MAGIC);
+                ptr::write_volatile(start.add(1), 0);
+            }
+        }
+
+        Persist { len: 0 }
+    }
+}
+
+impl fmt::Write for Persist {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let (start, size) = region();
+        let capacity = size.saturating_sub(HEADER);
+
+        let n = s.len().min(capacity - self.len.min(capacity));
+        unsafe {
+            let data = (start.add(2) as *mut u8).add(self.len);
+            for (i, byte) in s.as_bytes()[..n].iter().enumerate() {
+                ptr::write_volatile(data.add(i), *byte);
+            }
+        }
+
+        self.len += n;
+        if size >= HEADER {
+            unsafe { ptr::write_volatile(start.add(1), self.len as u32) }
+        }
+
+        Ok(())
+    }
+}
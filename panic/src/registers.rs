@@ -0,0 +1,153 @@
+//! Register dump
+
+use core::{
+    arch::asm,
+    fmt,
+    mem::{self, MaybeUninit},
+};
+
+/// Names of the captured registers, in capture order
+#[cfg(target_arch = "arm")]
+const NAMES: [&str; 17] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "sp", "lr",
+    "pc", "cpsr",
+];
+
+/// Names of the captured registers, in capture order
+#[cfg(target_arch = "aarch64")]
+const NAMES: [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14",
+    "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27",
+    "x28", "x29", "x30", "sp", "elr", "spsr",
+];
+
+/// r0 - r12, SP, LR, PC and CPSR
+#[cfg(target_arch = "arm")]
+pub struct Registers([u32; 17]);
+
+/// x0 - x30, SP, and the ELR and SPSR of the current exception level
+#[cfg(target_arch = "aarch64")]
+pub struct Registers([u64; 34]);
+
+#[cfg(target_arch = "arm")]
+impl Registers {
+    /// Captures the registers as seen by the caller; PC is the address of the capture sequence
+    #[inline(always)]
+    pub fn capture() -> Self {
+        // NOTE left uninitialized so no call (e.g. to `memset`) clobbers the registers before the
+        // capture
+        let mut regs = MaybeUninit::<[u32; 17]>::uninit();
+
+        // NOTE `stm` stores the original value of the base register when it's part of the list
+        unsafe {
+            asm!(
+                "stm {buf}, {{r0-r12}}",
+                "str sp, [{buf}, #52]",
+                "str lr, [{buf}, #56]",
+                "1:",
+                "adr {tmp}, 1b",
+                "str {tmp}, [{buf}, #60]",
+                "mrs {tmp}, cpsr",
+                "str {tmp}, [{buf}, #64]",
+                buf = in(reg) regs.as_mut_ptr() as *mut u32,
+                tmp = out(reg) _,
+                options(nostack, preserves_flags),
+            );
+
+            // NOTE(unsafe) all 17 words were written above
+            Registers(regs.assume_init())
+        }
+    }
+
+    /// Returns the captured registers in core dump order
+    #[cfg(feature = "coredump")]
+    pub fn words(&self) -> &[u32; 17] {
+        &self.0
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Registers {
+    /// Captures the registers as seen by the caller
+    ///
+    /// ELR and SPSR hold the state of the last exception taken to the current exception level,
+    /// which must be EL1 or higher
+    #[inline(always)]
+    pub fn capture() -> Self {
+        // NOTE left uninitialized so no call (e.g. to `memset`) clobbers the registers before the
+        // capture
+        let mut regs = MaybeUninit::<[u64; 34]>::uninit();
+
+        unsafe {
+            asm!(
+                "stp x0, x1, [{buf}, #0]",
+                "stp x2, x3, [{buf}, #16]",
+                "stp x4, x5, [{buf}, #32]",
+                "stp x6, x7, [{buf}, #48]",
+                "stp x8, x9, [{buf}, #64]",
+                "stp x10, x11, [{buf}, #80]",
+                "stp x12, x13, [{buf}, #96]",
+                "stp x14, x15, [{buf}, #112]",
+                "stp x16, x17, [{buf}, #128]",
+                "stp x18, x19, [{buf}, #144]",
+                "stp x20, x21, [{buf}, #160]",
+                "stp x22, x23, [{buf}, #176]",
+                "stp x24, x25, [{buf}, #192]",
+                "stp x26, x27, [{buf}, #208]",
+                "stp x28, x29, [{buf}, #224]",
+                "str x30, [{buf}, #240]",
+                "mov {tmp}, sp",
+                "str {tmp}, [{buf}, #248]",
+                // CurrentEL holds the exception level in bits 3:2
+                "mrs {tmp}, CurrentEL",
+                "cmp {tmp}, #8",
+                "b.eq 2f",
+                "b.hi 3f",
+                "mrs {tmp}, ELR_EL1",
+                "str {tmp}, [{buf}, #256]",
+                "mrs {tmp}, SPSR_EL1",
+                "b 4f",
+                "2:",
+                "mrs {tmp}, ELR_EL2",
+                "str {tmp}, [{buf}, #256]",
+                "mrs {tmp}, SPSR_EL2",
+                "b 4f",
+                "3:",
+                "mrs {tmp}, ELR_EL3",
+                "str {tmp}, [{buf}, #256]",
+                "mrs {tmp}, SPSR_EL3",
+                "4:",
+                "str {tmp}, [{buf}, #264]",
+                buf = in(reg) regs.as_mut_ptr() as *mut u64,
+                tmp = out(reg) _,
+                options(nostack),
+            );
+
+            // NOTE(unsafe) all 34 words were written above
+            Registers(regs.assume_init())
+        }
+    }
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, value)) in NAMES.iter().zip(self.0.iter()).enumerate() {
+            let sep = if i % 4 == 3 || i == NAMES.len() - 1 {
+                "\n"
+            } else {
+                "  "
+            };
+            // NOTE the width includes the `0x` prefix
+            write!(
+                f,
+                "{:>4} = {:#0w$x}{}",
+                name,
+                value,
+                sep,
+                w = 2 + 2 * mem::size_of_val(value)
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+//! Stack hexdump
+
+use core::{arch::asm, fmt, ptr};
+
+include!(concat!(env!("OUT_DIR"), "/stack.rs"));
+
+/// The top `STACK_DUMP_BYTES` of the active stack
+pub struct Stack {
+    sp: usize,
+}
+
+impl Stack {
+    /// Reads the stack pointer of the caller
+    #[inline(always)]
+    pub fn here() -> Self {
+        let sp: usize;
+        unsafe { asm!("mov {}, sp", out(reg) sp, options(nomem, nostack, preserves_flags)) }
+
+        Stack { sp }
+    }
+}
+
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "stack (sp = {:#010x}):", self.sp)?;
+
+        let start = self.sp & !3;
+        for (i, addr) in (start..start + STACK_DUMP_BYTES).step_by(4).enumerate() {
+            if i % 4 == 0 {
+                write!(f, "{:#010x}:", addr)?;
+            }
+
+            // NOTE(unsafe) the documentation of the `stack-dump` feature requires that this range
+            // is readable
+            let word = unsafe { ptr::read_volatile(addr as *const u32) };
+            write!(f, " {:08x}", word)?;
+
+            if i % 4 == 3 {
+                writeln!(f)?;
+            }
+        }
+
+        if STACK_DUMP_BYTES % 16 != 0 {
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Reads the Debug Communication Channel (DCC) of a core through a probe-rs supported debug probe
+//!
+//! Usage: `dcc-probe [--jtag] [--ap <index>] [--ready] [--input <words>] [--capture <file>]
+//! [--features <bits>] <base>`
+//!
+//! `<base>` is the address of the debug register block of the core, as seen from the APB-AP of the
+//! debug port (AP `--ap`, 1 by default), e.g. `0x80030000` for the first Cortex-R5 of a Zynq
+//! UltraScale+. The first probe found is used, over SWD or, with `--jtag`, JTAG. Neither xsdb nor
+//! OpenOCD must be connected to the probe at the same time.
+//!
+//! The words sent by the device are decoded (see the `arm_dcc_host` crate) and text is printed to
+//! stdout as it arrives; with `--capture` the raw words are also appended to `<file>`, 4 little
+//! endian bytes each, for `arm-dcc-host` or `dcc-coredump`. `--features` is the same as in
+//! `arm-dcc-host`.
+//!
+//! The words of `<words>`, 4 little endian bytes each, are sent to the device, e.g. input for
+//! `arm_dcc::read` or `arm_dcc::shell`; `--ready` sends `arm_dcc::handshake::HOST_READY` before
+//! them, for devices that start with `arm_dcc::init_and_wait`.
+//!
+//! The tool polls the DCC until the program exits (see `arm_dcc::exit`), with the same exit status,
+//! or until it's interrupted, e.g. with Ctrl-C.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    process, thread,
+    time::Duration,
+};
+
+use arm_dcc::handshake::HOST_READY;
+use arm_dcc_host::{
+    capture::{self, Format},
+    poll::{DebugRegisters, Poller},
+    Event, Stream,
+};
+use probe_rs::{
+    architecture::arm::{ArmError, ArmMemoryInterface, DpAddress, FullyQualifiedApAddress},
+    probe::{list::Lister, WireProtocol},
+    MemoryInterface,
+};
+
+const USAGE: &str = "usage: dcc-probe [--jtag] [--ap <index>] [--ready] [--input <words>] \
+                     [--capture <file>] [--features <bits>] <base>";
+
+// how long to wait before polling again when the device has nothing to send
+const IDLE: Duration = Duration::from_millis(1);
+
+struct Options {
+    jtag: bool,
+    ap: u8,
+    ready: bool,
+    input: Option<String>,
+    capture: Option<String>,
+    features: u32,
+    base: u64,
+}
+
+// the debug register block of the core, through a memory AP
+struct Registers<'a> {
+    memory: Box<dyn ArmMemoryInterface + 'a>,
+    base: u64,
+}
+
+impl DebugRegisters for Registers<'_> {
+    type Error = ArmError;
+
+    fn read(&mut self, offset: u32) -> Result<u32, ArmError> {
+        self.memory.read_word_32(self.base + u64::from(offset))
+    }
+
+    fn write(&mut self, offset: u32, value: u32) -> Result<(), ArmError> {
+        self.memory
+            .write_word_32(self.base + u64::from(offset), value)
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let options = options()?;
+
+    let mut input = Vec::new();
+    if options.ready {
+        input.push(HOST_READY);
+    }
+    if let Some(path) = &options.input {
+        let bytes = fs::read(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+        let words = capture::words(&bytes, Format::Binary)
+            .map_err(|e| format!("couldn't parse {}: {}", path, e))?;
+        input.extend(words);
+    }
+
+    let mut capture = match &options.capture {
+        Some(path) => Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("couldn't open {}: {}", path, e))?,
+        ),
+        None => None,
+    };
+
+    let probes = Lister::new().list_all();
+    let info = probes.first().ok_or("no debug probe found")?;
+    let mut probe = info
+        .open()
+        .map_err(|e| format!("couldn't open {}: {}", info.identifier, e))?;
+    let protocol = if options.jtag {
+        WireProtocol::Jtag
+    } else {
+        WireProtocol::Swd
+    };
+    probe
+        .select_protocol(protocol)
+        .map_err(|e| format!("couldn't select {:?}: {}", protocol, e))?;
+
+    // NOTE the core is not halted, nor is its target description needed: only the debug port and
+    // the AP are used
+    let uninitialized = probe
+        .try_into_arm_interface()
+        .map_err(|(_, e)| format!("couldn't use the ARM debug interface: {}", e))?;
+    let mut interface = uninitialized
+        .initialize_unspecified(DpAddress::Default)
+        .map_err(|(_, e)| format!("couldn't initialize the debug port: {}", e))?;
+    let ap = FullyQualifiedApAddress::v1_with_default_dp(options.ap);
+    let memory = interface
+        .memory_interface(&ap)
+        .map_err(|e| format!("couldn't use AP {}: {}", options.ap, e))?;
+
+    let mut poller = Poller::new(Registers {
+        memory,
+        base: options.base,
+    });
+    poller.push_input(&input);
+
+    let mut stream = Stream::with_features(options.features);
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    loop {
+        let word = match poller
+            .poll()
+            .map_err(|e| format!("couldn't poll the DCC: {}", e))?
+        {
+            Some(word) => word,
+            None => {
+                thread::sleep(IDLE);
+                continue;
+            }
+        };
+
+        if let Some(file) = &mut capture {
+            write_capture(file, word)?;
+        }
+
+        match stream.push(word) {
+            Some(Event::Text(text)) => {
+                stdout
+                    .write_all(&text)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| format!("couldn't write to stdout: {}", e))?;
+            }
+            Some(Event::Record(record)) => {
+                writeln!(stdout, "{}", record)
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| format!("couldn't write to stdout: {}", e))?;
+            }
+            Some(Event::Exit(status)) => {
+                eprintln!("device exited with status {}", status);
+                process::exit(status)
+            }
+            Some(Event::BadCrc) => eprintln!("warning: a frame was corrupted (CRC mismatch)"),
+            Some(Event::Lost(frames)) => eprintln!("warning: {} frames were lost", frames),
+            Some(Event::Unknown(word)) => eprintln!("warning: unknown word {:#010x}", word),
+            Some(Event::Channel(..)) | Some(Event::Handshake(_)) | None => {}
+        }
+    }
+}
+
+fn options() -> Result<Options, String> {
+    let mut options = Options {
+        jtag: false,
+        ap: 1,
+        ready: false,
+        input: None,
+        capture: None,
+        features: 0,
+        base: 0,
+    };
+
+    let mut base = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--jtag" => options.jtag = true,
+            "--ready" => options.ready = true,
+            "--ap" => {
+                let index = args.next().ok_or(USAGE)?;
+                options.ap = index
+                    .parse()
+                    .map_err(|_| format!("invalid AP index: {}", index))?;
+            }
+            "--input" => options.input = Some(args.next().ok_or(USAGE)?),
+            "--capture" => options.capture = Some(args.next().ok_or(USAGE)?),
+            "--features" => {
+                let bits = args.next().ok_or(USAGE)?;
+                options.features =
+                    number(&bits).ok_or_else(|| format!("invalid features: {}", bits))? as u32;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if base.is_none() && !arg.starts_with("--") => {
+                base = Some(number(&arg).ok_or_else(|| format!("invalid address: {}", arg))?)
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    options.base = base.ok_or(USAGE)?;
+    Ok(options)
+}
+
+// parses a decimal or `0x` hexadecimal number
+fn number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x") {
+        Some(digits) => u64::from_str_radix(digits, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn write_capture(file: &mut File, word: u32) -> Result<(), String> {
+    file.write_all(&word.to_le_bytes())
+        .map_err(|e| format!("couldn't write the capture: {}", e))
+}
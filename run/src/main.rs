@@ -0,0 +1,228 @@
+//! Cargo runner that loads a program through OpenOCD and streams its Debug Communication Channel
+//! (DCC) output
+//!
+//! Usage: `dcc-run [--connect <address>] [--target <name>] [--dap <name>] [--ap <index>]
+//! [--ready] [--features <bits>] --base <address> <elf> [<args>..]`
+//!
+//! `dcc-run` connects to a running OpenOCD instance (`localhost:6666` by default; see the
+//! `arm_dcc_host::tcl` module), resets and halts the core (`--target` selects it, the current
+//! OpenOCD target by default), loads `<elf>` and resumes the core at its entry point. Then it polls
+//! the DCC through the debug registers at `--base` (see `arm-dcc-host --connect` for `--dap`,
+//! `--ap`, `--ready` and `--features`) and prints the decoded output until the program exits,
+//! with `arm_dcc::exit::exit` or with `panic-dcc`'s `exit` feature; `dcc-run` then exits with the
+//! same status. This makes `cargo run` and `cargo test` work for programs that run on the device:
+//!
+//! ``` text
+//! # .cargo/config.toml
+//! [target.armv7r-none-eabihf]
+//! runner = "dcc-run --target zynqmp.r5.0 --base 0x803f0000"
+//! ```
+//!
+//! Code addresses (see the `arm_dcc::address` module) are printed as function names and source
+//! locations, looked up in the debug information of `<elf>`.
+//!
+//! The arguments that follow `<elf>`, e.g. the test filters passed by `cargo test`, are ignored:
+//! the program can't receive them.
+
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+use std::{
+    convert::TryInto,
+    env, fs,
+    io::{self, Write},
+    process, thread,
+    time::Duration,
+};
+
+use arm_dcc::handshake::HOST_READY;
+use arm_dcc_host::{
+    poll::Poller,
+    symbolize::Symbolizer,
+    tcl::{self, ApRegisters, Client},
+    Event, Stream,
+};
+
+const USAGE: &str = "usage: dcc-run [--connect <address>] [--target <name>] [--dap <name>] \
+                     [--ap <index>] [--ready] [--features <bits>] --base <address> <elf> [<args>..]";
+
+// how long to wait before polling again when the device has nothing to send
+const IDLE: Duration = Duration::from_millis(1);
+
+struct Options {
+    connect: String,
+    target: Option<String>,
+    dap: Option<String>,
+    ap: u8,
+    ready: bool,
+    features: u32,
+    base: u32,
+    elf: String,
+}
+
+fn main() {
+    match run() {
+        Ok(status) => process::exit(status),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run() -> Result<i32, String> {
+    let options = options()?;
+
+    let elf =
+        fs::read(&options.elf).map_err(|e| format!("couldn't read {}: {}", options.elf, e))?;
+    let entry = entry(&elf).ok_or_else(|| format!("{} is not an ELF file", options.elf))?;
+
+    let mut client = Client::connect(&*options.connect)
+        .map_err(|e| format!("couldn't connect to OpenOCD at {}: {}", options.connect, e))?;
+    let mut command = |command: String| {
+        client
+            .command(&command)
+            .map_err(|e| format!("`{}` failed: {}", command, e))
+    };
+    if let Some(target) = &options.target {
+        command(format!("targets {}", target))?;
+    }
+    command("reset halt".to_string())?;
+    // NOTE braces keep paths with spaces in one piece
+    command(format!("load_image {{{}}} 0 elf", options.elf))?;
+    command(format!("resume {:#x}", entry))?;
+
+    let mut poller = Poller::new(ApRegisters::new(
+        client,
+        options.dap.as_deref(),
+        options.ap,
+        options.base,
+    ));
+    if options.ready {
+        poller.push_input(&[HOST_READY]);
+    }
+
+    let mut stream = Stream::with_features(options.features);
+    stream.set_symbolizer(
+        Symbolizer::new(&elf).map_err(|e| format!("couldn't load {}: {}", options.elf, e))?,
+    );
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    loop {
+        let word = match poller
+            .poll()
+            .map_err(|e| format!("couldn't poll the DCC: {}", e))?
+        {
+            Some(word) => word,
+            None => {
+                thread::sleep(IDLE);
+                continue;
+            }
+        };
+
+        match stream.push(word) {
+            Some(Event::Text(text)) => stdout
+                .write_all(&text)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("couldn't write to stdout: {}", e))?,
+            Some(Event::Record(record)) => writeln!(stdout, "{}", record)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("couldn't write to stdout: {}", e))?,
+            Some(Event::Exit(status)) => return Ok(status),
+            Some(Event::BadCrc) => eprintln!("warning: a frame was corrupted (CRC mismatch)"),
+            Some(Event::Lost(frames)) => eprintln!("warning: {} frames were lost", frames),
+            Some(Event::Unknown(word)) => eprintln!("warning: unknown word {:#010x}", word),
+            Some(Event::Channel(..)) | Some(Event::Handshake(_)) | None => {}
+        }
+    }
+}
+
+fn options() -> Result<Options, String> {
+    let mut options = Options {
+        connect: tcl::DEFAULT_ADDRESS.to_string(),
+        target: None,
+        dap: None,
+        ap: 1,
+        ready: false,
+        features: 0,
+        base: 0,
+        elf: String::new(),
+    };
+
+    let mut base = None;
+    let mut elf = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            // NOTE the arguments of the program come after the ELF file
+            _ if elf.is_some() => {}
+            "--connect" => options.connect = args.next().ok_or(USAGE)?,
+            "--target" => options.target = Some(args.next().ok_or(USAGE)?),
+            "--dap" => options.dap = Some(args.next().ok_or(USAGE)?),
+            "--ap" => {
+                let index = args.next().ok_or(USAGE)?;
+                options.ap = index
+                    .parse()
+                    .map_err(|_| format!("invalid AP index: {}", index))?;
+            }
+            "--ready" => options.ready = true,
+            "--features" => {
+                let bits = args.next().ok_or(USAGE)?;
+                options.features =
+                    number(&bits).ok_or_else(|| format!("invalid features: {}", bits))?;
+            }
+            "--base" => {
+                let address = args.next().ok_or(USAGE)?;
+                base =
+                    Some(number(&address).ok_or_else(|| format!("invalid address: {}", address))?);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if !arg.starts_with("--") => elf = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    options.base = base.ok_or(USAGE)?;
+    options.elf = elf.ok_or(USAGE)?;
+    Ok(options)
+}
+
+// parses a decimal or `0x` hexadecimal number
+fn number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x") {
+        Some(digits) => u32::from_str_radix(digits, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+// entry point of a 32-bit or 64-bit ELF file, of either endianness
+fn entry(elf: &[u8]) -> Option<u64> {
+    if elf.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+
+    let big_endian = *elf.get(5)? == 2;
+    let entry = match *elf.get(4)? {
+        1 => {
+            let bytes = elf.get(0x18..0x1c)?.try_into().ok()?;
+            u64::from(if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            })
+        }
+        2 => {
+            let bytes = elf.get(0x18..0x20)?.try_into().ok()?;
+            if big_endian {
+                u64::from_be_bytes(bytes)
+            } else {
+                u64::from_le_bytes(bytes)
+            }
+        }
+        _ => return None,
+    };
+    Some(entry)
+}
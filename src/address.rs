@@ -0,0 +1,42 @@
+//! Code addresses for the host to symbolize
+//!
+//! `write_addresses` sends code addresses, e.g. the address of a faulting instruction followed by
+//! the return addresses of a backtrace, in a frame of their own: an `ADDRESS_TAG | n` header word
+//! followed by the `n` addresses, with the sequence number and CRC of the framed encodings (see
+//! the `crc` and `sequence` features). `decode::Decoder` reports each address, along with its
+//! position in the frame, as `decode::Item::Address`.
+//!
+//! The first address of a frame is where the code was executing; the others are return
+//! addresses, which host tools look up one byte before so they land on the call instruction. The
+//! `arm-dcc-host` tool prints them as function names and source locations when given the ELF file
+//! of the program.
+//!
+//! ``` no_run
+//! let pc = 0x0010_0a4c;
+//! let lr = 0x0010_03f0;
+//!
+//! arm_dcc::address::write_addresses(&[pc, lr]);
+//! ```
+
+use crate::frame::FrameWriter;
+
+/// Upper half-word of the header word that precedes each frame of addresses
+pub const ADDRESS_TAG: u32 = 0xDCC7_0000;
+
+/// Maximum number of addresses in a frame
+///
+/// The number of addresses is encoded in the lower half-word of the header
+pub const ADDRESS_MAX_LEN: usize = 0xffff;
+
+/// Sends code addresses to the host, in a single frame
+///
+/// Only the first `ADDRESS_MAX_LEN` addresses are sent.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_addresses(addresses: &[u32]) {
+    let addresses = &addresses[..addresses.len().min(ADDRESS_MAX_LEN)];
+
+    let mut frame = FrameWriter::new(ADDRESS_TAG | addresses.len() as u32);
+    addresses.iter().for_each(|address| frame.write(*address));
+    frame.finish()
+}
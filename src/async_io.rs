@@ -0,0 +1,76 @@
+//! Non-blocking writes for async executors
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Dcc, TXFULL};
+
+impl Dcc {
+    /// Sends as many bytes as the DCC takes without blocking
+    ///
+    /// Returns `Pending` if the transmit register is full and not even one byte could be sent.
+    /// Each byte is sent word-extended, as `try_write_all` does, even in `packed` mode.
+    ///
+    /// Nothing signals the task when the host reads the transmit register so, before returning
+    /// `Pending`, the task is woken right away: the executor polls it again after the other ready
+    /// tasks instead of blocking its thread in a busy-wait loop.
+    pub fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<usize> {
+        let n = crate::try_write_all(buf);
+        if n == 0 && !buf.is_empty() {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        } else {
+            Poll::Ready(n)
+        }
+    }
+
+    /// Waits until the host has read the last word that was sent
+    ///
+    /// Returns `Pending`, and wakes the task right away (see `poll_write`), while the transmit
+    /// register is full.
+    pub fn poll_flush(&mut self, cx: &mut Context) -> Poll<()> {
+        if crate::dscr() & TXFULL == 0 {
+            Poll::Ready(())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// Sends all the bytes, yielding to the executor while the transmit register is full
+    ///
+    /// ``` ignore
+    /// async fn report(mut dcc: arm_dcc::Dcc) {
+    ///     dcc.write_all_async(b"sensor task started\n").await;
+    /// }
+    /// ```
+    pub fn write_all_async<'a>(&'a mut self, buf: &'a [u8]) -> WriteAll<'a> {
+        WriteAll { dcc: self, buf }
+    }
+}
+
+/// Future returned by `Dcc::write_all_async`
+pub struct WriteAll<'a> {
+    dcc: &'a mut Dcc,
+    buf: &'a [u8],
+}
+
+impl Future for WriteAll<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+
+        while !this.buf.is_empty() {
+            match this.dcc.poll_write(cx, this.buf) {
+                Poll::Ready(n) => this.buf = &this.buf[n..],
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(())
+    }
+}
@@ -0,0 +1,266 @@
+//! Atomic types used to store the global state of this crate
+//!
+//! On targets that have atomic instructions these are the `core::sync::atomic` types. The bare
+//! metal ARMv4T / ARMv5TE and ARMv6-M targets have none so this module provides single core
+//! replacements that use volatile loads and stores; their read-modify-write operations are *not*
+//! atomic with respect to interrupt handlers, except for `compare_exchange`, which masks
+//! interrupts.
+//!
+//! `FnCell` builds on them to store the functions that the application registers, e.g. handlers
+//! and hooks.
+
+// NOTE `dcc_no_atomics` is set by the build script
+#[cfg(not(dcc_no_atomics))]
+pub(crate) use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, AtomicUsize};
+
+#[cfg(dcc_no_atomics)]
+pub(crate) use self::single_core::{AtomicBool, AtomicPtr, AtomicU32, AtomicU8, AtomicUsize};
+
+pub(crate) use core::sync::atomic::Ordering;
+
+use core::{marker::PhantomData, mem};
+
+#[cfg(dcc_no_atomics)]
+mod single_core {
+    use core::{arch::asm, cell::UnsafeCell, ptr, sync::atomic};
+
+    use super::Ordering;
+
+    // masks IRQ and FIQ; returns the previous CPSR. NOTE Thumb-1 has no `mrs` / `msr` so these
+    // run in ARM state on the Thumb targets
+    #[cfg(not(dcc_itm))]
+    #[cfg_attr(dcc_thumb1, instruction_set(arm::a32))]
+    #[inline(never)]
+    fn disable_interrupts() -> u32 {
+        let cpsr: u32;
+        unsafe {
+            asm!(
+                "mrs {0}, cpsr",
+                "orr {1}, {0}, #0xc0",
+                "msr cpsr_c, {1}",
+                out(reg) cpsr,
+                out(reg) _,
+                options(nostack),
+            )
+        }
+        cpsr
+    }
+
+    // restores the IRQ and FIQ masks saved by `disable_interrupts`
+    #[cfg(not(dcc_itm))]
+    #[cfg_attr(dcc_thumb1, instruction_set(arm::a32))]
+    #[inline(never)]
+    fn restore_interrupts(cpsr: u32) {
+        unsafe { asm!("msr cpsr_c, {}", in(reg) cpsr, options(nostack)) }
+    }
+
+    // M-profile cores without atomics, e.g. ARMv6-M: masks the exceptions with configurable
+    // priority; returns the previous PRIMASK
+    #[cfg(dcc_itm)]
+    #[inline(always)]
+    fn disable_interrupts() -> u32 {
+        let primask: u32;
+        unsafe {
+            asm!(
+                "mrs {}, PRIMASK",
+                "cpsid i",
+                out(reg) primask,
+                options(nostack, preserves_flags),
+            )
+        }
+        primask
+    }
+
+    // restores the PRIMASK saved by `disable_interrupts`
+    #[cfg(dcc_itm)]
+    #[inline(always)]
+    fn restore_interrupts(primask: u32) {
+        unsafe { asm!("msr PRIMASK, {}", in(reg) primask, options(nostack, preserves_flags)) }
+    }
+
+    // runs `f` with interrupts masked
+    fn interrupt_free<R>(f: impl FnOnce() -> R) -> R {
+        let mask = disable_interrupts();
+        atomic::compiler_fence(Ordering::SeqCst);
+        let r = f();
+        atomic::compiler_fence(Ordering::SeqCst);
+        restore_interrupts(mask);
+        r
+    }
+
+    macro_rules! atomic {
+        ($name:ident, $ty:ty) => {
+            pub(crate) struct $name {
+                inner: UnsafeCell<$ty>,
+            }
+
+            // NOTE(unsafe) these targets are single core
+            unsafe impl Sync for $name {}
+
+            #[allow(dead_code)]
+            impl $name {
+                pub(crate) const fn new(value: $ty) -> Self {
+                    $name {
+                        inner: UnsafeCell::new(value),
+                    }
+                }
+
+                pub(crate) fn load(&self, _: Ordering) -> $ty {
+                    let value = unsafe { ptr::read_volatile(self.inner.get()) };
+                    atomic::compiler_fence(Ordering::SeqCst);
+                    value
+                }
+
+                pub(crate) fn store(&self, value: $ty, _: Ordering) {
+                    atomic::compiler_fence(Ordering::SeqCst);
+                    unsafe { ptr::write_volatile(self.inner.get(), value) }
+                }
+
+                pub(crate) fn swap(&self, value: $ty, _: Ordering) -> $ty {
+                    self.update(|_| value)
+                }
+
+                pub(crate) fn compare_exchange(
+                    &self,
+                    current: $ty,
+                    new: $ty,
+                    _: Ordering,
+                    _: Ordering,
+                ) -> Result<$ty, $ty> {
+                    interrupt_free(|| {
+                        let old = self.load(Ordering::Relaxed);
+                        if old == current {
+                            self.store(new, Ordering::Relaxed);
+                            Ok(old)
+                        } else {
+                            Err(old)
+                        }
+                    })
+                }
+
+                fn update(&self, f: impl FnOnce($ty) -> $ty) -> $ty {
+                    let old = self.load(Ordering::Relaxed);
+                    self.store(f(old), Ordering::Relaxed);
+                    old
+                }
+            }
+        };
+    }
+
+    macro_rules! atomic_int {
+        ($name:ident, $ty:ty) => {
+            atomic!($name, $ty);
+
+            #[allow(dead_code)]
+            impl $name {
+                pub(crate) fn fetch_add(&self, value: $ty, _: Ordering) -> $ty {
+                    self.update(|old| old.wrapping_add(value))
+                }
+
+                pub(crate) fn fetch_and(&self, value: $ty, _: Ordering) -> $ty {
+                    self.update(|old| old & value)
+                }
+
+                pub(crate) fn fetch_or(&self, value: $ty, _: Ordering) -> $ty {
+                    self.update(|old| old | value)
+                }
+
+                pub(crate) fn fetch_max(&self, value: $ty, _: Ordering) -> $ty {
+                    self.update(|old| if value > old { value } else { old })
+                }
+            }
+        };
+    }
+
+    atomic!(AtomicBool, bool);
+    atomic_int!(AtomicU8, u8);
+    atomic_int!(AtomicU32, u32);
+    atomic_int!(AtomicUsize, usize);
+
+    pub(crate) struct AtomicPtr<T> {
+        inner: UnsafeCell<*mut T>,
+    }
+
+    // NOTE(unsafe) these targets are single core
+    unsafe impl<T> Sync for AtomicPtr<T> {}
+
+    impl<T> AtomicPtr<T> {
+        pub(crate) const fn new(value: *mut T) -> Self {
+            AtomicPtr {
+                inner: UnsafeCell::new(value),
+            }
+        }
+
+        pub(crate) fn load(&self, _: Ordering) -> *mut T {
+            let value = unsafe { ptr::read_volatile(self.inner.get()) };
+            atomic::compiler_fence(Ordering::SeqCst);
+            value
+        }
+
+        pub(crate) fn store(&self, value: *mut T, _: Ordering) {
+            atomic::compiler_fence(Ordering::SeqCst);
+            unsafe { ptr::write_volatile(self.inner.get(), value) }
+        }
+    }
+}
+
+/// A function pointer, of type `F`, that can be registered, replaced and cleared at any time
+///
+/// This is shared with the `dcc-exception` and `panic-dcc` crates; it's not part of the public
+/// API.
+pub struct FnCell<F> {
+    // NOTE `0` means that no function is registered; function pointers are never null
+    value: AtomicUsize,
+    _f: PhantomData<F>,
+}
+
+impl<F> FnCell<F>
+where
+    F: Copy,
+{
+    /// Creates a cell with no function registered
+    pub const fn new() -> Self {
+        FnCell {
+            value: AtomicUsize::new(0),
+            _f: PhantomData,
+        }
+    }
+
+    /// Registers `f`, replacing the previous function, if any
+    ///
+    /// # Panics
+    ///
+    /// If `F` is not the size of a pointer, i.e. if it's not a function pointer
+    pub fn set(&self, f: F) {
+        assert_eq!(mem::size_of::<F>(), mem::size_of::<usize>());
+
+        // NOTE(transmute_copy) `F` is the size of `usize` (see above)
+        let value: usize = unsafe { mem::transmute_copy(&f) };
+        self.value.store(value, Ordering::Release)
+    }
+
+    /// Unregisters the function, if any
+    pub fn clear(&self) {
+        self.value.store(0, Ordering::Release)
+    }
+
+    /// Returns the registered function, if any
+    pub fn get(&self) -> Option<F> {
+        let value = self.value.load(Ordering::Acquire);
+        if value == 0 {
+            None
+        } else {
+            // NOTE(transmute_copy) non-zero values are always `F`s stored by `set`
+            Some(unsafe { mem::transmute_copy(&value) })
+        }
+    }
+}
+
+impl<F> Default for FnCell<F>
+where
+    F: Copy,
+{
+    fn default() -> Self {
+        FnCell::new()
+    }
+}
@@ -0,0 +1,48 @@
+//! AArch64 debug system registers
+//!
+//! MDCCSR_EL0 reports TXfull and RXfull in the same bit positions as the ARMv7 DBGDSCR register
+//! but none of the other DBGDSCR bits
+
+use core::arch::asm;
+
+use crate::TXFULL;
+
+/// Reads the Debug Comms Channel Status Register (MDCCSR_EL0)
+#[inline(always)]
+pub fn dscr() -> u32 {
+    let r: u64;
+    unsafe { asm!("MRS {}, MDCCSR_EL0", out(reg) r, options(nomem, nostack)) }
+    r as u32
+}
+
+/// Writes the Data Transfer Register (DBGDTRTX_EL0)
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { asm!("MSR DBGDTRTX_EL0, {}", in(reg) u64::from(word), options(nomem, nostack)) }
+}
+
+/// Reads the Data Transfer Register (DBGDTRRX_EL0)
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    let r: u64;
+    unsafe { asm!("MRS {}, DBGDTRRX_EL0", out(reg) r, options(nomem, nostack)) }
+    r as u32
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    // busy wait until we can send data
+    while dscr() & TXFULL != 0 {}
+
+    dtrtx(word)
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    bytes.iter().for_each(|byte| write(u32::from(*byte)))
+}
+
+#[inline(always)]
+pub fn wfe() {
+    unsafe { asm!("WFE", options(nomem, nostack)) }
+}
@@ -0,0 +1,58 @@
+//! ARMv6+ CP14 registers, accessed using inline assembly
+
+use core::arch::asm;
+
+use crate::TXFULL;
+
+/// Reads the Debug Status and Control Register (DBGDSCR)
+#[inline(always)]
+pub fn dscr() -> u32 {
+    unsafe {
+        let r: u32;
+        asm!("mrc p14, 0, {}, c0, c1, 0", out(reg) r, options(nomem, nostack, preserves_flags));
+        r
+    }
+}
+
+/// Writes the Data Transfer Register (DBGDTRTX)
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { asm!("mcr p14, 0, {}, c0, c5, 0", in(reg) word, options(nostack, preserves_flags)) }
+}
+
+/// Reads the Data Transfer Register (DBGDTRRX)
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    unsafe {
+        let r: u32;
+        asm!("mrc p14, 0, {}, c0, c5, 0", out(reg) r, options(nostack, preserves_flags));
+        r
+    }
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    // busy wait until we can send data
+    while dscr() & TXFULL != 0 {}
+
+    dtrtx(word)
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    bytes.iter().for_each(|byte| write(u32::from(*byte)))
+}
+
+#[cfg(not(dcc_armv6))]
+#[inline(always)]
+pub fn wfe() {
+    unsafe { asm!("wfe", options(nomem, nostack, preserves_flags)) }
+}
+
+// NOTE the assembler only accepts `wfe` on ARMv6K and newer; plain ARMv6 cores execute this
+// encoding as a NOP
+#[cfg(dcc_armv6)]
+#[inline(always)]
+pub fn wfe() {
+    unsafe { asm!(".inst 0xe320f002", options(nomem, nostack, preserves_flags)) }
+}
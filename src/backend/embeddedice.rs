@@ -0,0 +1,63 @@
+//! EmbeddedICE comms channel of ARMv4T / ARMv5 cores (e.g. ARM7TDMI, ARM926EJ-S), accessed
+//! through the assembly routines in `asm-embeddedice.s`
+//!
+//! The comms control register reports the state of the write (transmit) register in bit 1 (W)
+//! and the state of the read (receive) register in bit 0 (R); `dscr` moves these bits to the
+//! positions of TXfull and RXfull in the ARMv7 DBGDSCR register
+
+use crate::{RXFULL, TXFULL};
+
+// NOTE with the `extern-asm` feature these routines come from the prebuilt blob instead
+#[cfg(not(feature = "extern-asm"))]
+core::arch::global_asm!(include_str!("../../asm-embeddedice.s"));
+
+extern "C" {
+    fn __dcc_dscr() -> u32;
+    fn __dcc_dtrrx() -> u32;
+    fn __dcc_dtrtx(word: u32);
+    fn __dcc_wfe();
+    fn __dcc_write(word: u32);
+    fn __dcc_write_bytes(bytes: *const u8, len: usize);
+}
+
+/// W bit of the comms control register
+const W: u32 = 1 << 1;
+
+/// R bit of the comms control register
+const R: u32 = 1 << 0;
+
+/// Reads the comms control register
+#[inline(always)]
+pub fn dscr() -> u32 {
+    let control = unsafe { __dcc_dscr() };
+
+    (if control & W != 0 { TXFULL } else { 0 }) | (if control & R != 0 { RXFULL } else { 0 })
+}
+
+/// Writes the comms data write register
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { __dcc_dtrtx(word) }
+}
+
+/// Reads the comms data read register
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    unsafe { __dcc_dtrrx() }
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    unsafe { __dcc_write(word) }
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    unsafe { __dcc_write_bytes(bytes.as_ptr(), bytes.len()) }
+}
+
+/// No-op; these cores have no `WFE` instruction
+#[inline(always)]
+pub fn wfe() {
+    unsafe { __dcc_wfe() }
+}
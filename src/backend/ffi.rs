@@ -0,0 +1,52 @@
+//! ARMv6+ CP14 registers, accessed through the assembly routines in `asm.s`
+//!
+//! This backend is used on the Thumb-1 targets and when the `extern-asm` feature is enabled; in
+//! the latter case the routines come from the prebuilt blob in `bin`
+
+// NOTE with the `extern-asm` feature these routines come from the prebuilt blob instead. Without
+// it this backend is only used on the Thumb-1 ARMv6 target, hence `.arm`; ARMv6K is needed for
+// `wfe`, which plain ARMv6 cores execute as a NOP
+#[cfg(not(feature = "extern-asm"))]
+core::arch::global_asm!(".arm", ".arch armv6k", include_str!("../../asm.s"));
+
+extern "C" {
+    fn __dcc_dscr() -> u32;
+    fn __dcc_dtrrx() -> u32;
+    fn __dcc_dtrtx(word: u32);
+    fn __dcc_wfe();
+    fn __dcc_write(word: u32);
+    fn __dcc_write_bytes(bytes: *const u8, len: usize);
+}
+
+/// Reads the Debug Status and Control Register (DBGDSCR)
+#[inline(always)]
+pub fn dscr() -> u32 {
+    unsafe { __dcc_dscr() }
+}
+
+/// Writes the Data Transfer Register (DBGDTRTX)
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { __dcc_dtrtx(word) }
+}
+
+/// Reads the Data Transfer Register (DBGDTRRX)
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    unsafe { __dcc_dtrrx() }
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    unsafe { __dcc_write(word) }
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    unsafe { __dcc_write_bytes(bytes.as_ptr(), bytes.len()) }
+}
+
+#[inline(always)]
+pub fn wfe() {
+    unsafe { __dcc_wfe() }
+}
@@ -0,0 +1,37 @@
+//! Host stub, see the `host` module
+
+use crate::{host, RXFULL};
+
+#[inline(always)]
+pub fn dscr() -> u32 {
+    if host::has_input() {
+        RXFULL
+    } else {
+        0
+    }
+}
+
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    host::output(word)
+}
+
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    host::pop_input()
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    host::output(word)
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    bytes.iter().for_each(|byte| write(u32::from(*byte)))
+}
+
+#[inline(always)]
+pub fn wfe() {
+    host::wait()
+}
@@ -0,0 +1,58 @@
+//! ITM stimulus port 0 of Cortex-M cores
+//!
+//! The ITM has no receive register, so RXfull is never reported and `dtrrx` returns `0`. Each word
+//! is written as a 4-byte stimulus packet so the host receives the same word stream as from the
+//! DCC. The stimulus port reads as `1` when it can accept a word; `dscr` reports that as TXfull
+//! being cleared and C_DEBUGEN of DHCSR as HDBGen.
+
+use core::{arch::asm, ptr};
+
+use crate::{HDBGEN, TXFULL};
+
+/// ITM stimulus port 0
+const STIM0: *mut u32 = 0xe000_0000 as *mut u32;
+
+/// Debug Halting Control and Status Register
+const DHCSR: *const u32 = 0xe000_edf0 as *const u32;
+
+/// C_DEBUGEN bit of DHCSR
+const C_DEBUGEN: u32 = 1 << 0;
+
+/// Reads the state of stimulus port 0 and of DHCSR
+#[inline(always)]
+pub fn dscr() -> u32 {
+    let ready = unsafe { ptr::read_volatile(STIM0) } & 1 != 0;
+    let dhcsr = unsafe { ptr::read_volatile(DHCSR) };
+
+    (if ready { 0 } else { TXFULL }) | (if dhcsr & C_DEBUGEN != 0 { HDBGEN } else { 0 })
+}
+
+/// Writes stimulus port 0
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { ptr::write_volatile(STIM0, word) }
+}
+
+/// The ITM can't receive data; always returns `0`
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    0
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    // busy wait until the stimulus port can accept data
+    while unsafe { ptr::read_volatile(STIM0) } & 1 == 0 {}
+
+    dtrtx(word)
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    bytes.iter().for_each(|byte| write(u32::from(*byte)))
+}
+
+#[inline(always)]
+pub fn wfe() {
+    unsafe { asm!("wfe", options(nomem, nostack, preserves_flags)) }
+}
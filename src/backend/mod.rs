@@ -0,0 +1,96 @@
+//! Access to the DCC registers
+//!
+//! Every backend provides the same set of functions:
+//!
+//! - `dscr`, reads the status register. TXfull and RXfull are always reported in bits 29 and 30.
+//! - `dtrtx`, writes the transmit register without checking its status
+//! - `dtrrx`, reads the receive register without checking its status
+//! - `write`, busy waits until the transmit register is empty and then writes to it
+//! - `write_bytes`, `write`s each byte, word-extended, from a single loop
+//! - `wfe`, waits for an event
+
+#[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), feature = "nop"))]
+mod nop;
+#[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), feature = "nop"))]
+pub use self::nop::*;
+
+// NOTE the build script only accepts the `xscale` feature on ARMv5TE targets
+#[cfg(all(target_arch = "arm", not(feature = "nop"), feature = "xscale"))]
+mod xscale;
+#[cfg(all(target_arch = "arm", not(feature = "nop"), feature = "xscale"))]
+pub use self::xscale::*;
+
+// NOTE `dcc_embeddedice` is set by the build script
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(feature = "xscale"),
+    dcc_embeddedice
+))]
+mod embeddedice;
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(feature = "xscale"),
+    dcc_embeddedice
+))]
+pub use self::embeddedice::*;
+
+// NOTE `dcc_thumb1` is set by the build script. Thumb-1 has no coprocessor instructions so
+// those targets call ARM state routines
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(dcc_embeddedice),
+    not(dcc_itm),
+    any(feature = "extern-asm", dcc_thumb1)
+))]
+mod ffi;
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(dcc_embeddedice),
+    not(dcc_itm),
+    any(feature = "extern-asm", dcc_thumb1)
+))]
+pub use self::ffi::*;
+
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(dcc_embeddedice),
+    not(dcc_itm),
+    not(feature = "extern-asm"),
+    not(dcc_thumb1)
+))]
+mod asm;
+#[cfg(all(
+    target_arch = "arm",
+    not(feature = "nop"),
+    not(dcc_embeddedice),
+    not(dcc_itm),
+    not(feature = "extern-asm"),
+    not(dcc_thumb1)
+))]
+pub use self::asm::*;
+
+// NOTE `dcc_itm` is set by the build script on the M-profile targets, which have no DCC
+#[cfg(all(target_arch = "arm", not(feature = "nop"), dcc_itm))]
+mod itm;
+#[cfg(all(target_arch = "arm", not(feature = "nop"), dcc_itm))]
+pub use self::itm::*;
+
+#[cfg(all(target_arch = "aarch64", not(feature = "nop")))]
+mod aarch64;
+#[cfg(all(target_arch = "aarch64", not(feature = "nop")))]
+pub use self::aarch64::*;
+
+#[cfg(all(not(any(target_arch = "arm", target_arch = "aarch64")), feature = "std"))]
+mod host;
+#[cfg(all(not(any(target_arch = "arm", target_arch = "aarch64")), feature = "std"))]
+pub use self::host::*;
+
+#[cfg(all(not(any(target_arch = "arm", target_arch = "aarch64")), not(feature = "std")))]
+mod unsupported;
+#[cfg(all(not(any(target_arch = "arm", target_arch = "aarch64")), not(feature = "std")))]
+pub use self::unsupported::*;
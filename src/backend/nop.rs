@@ -0,0 +1,23 @@
+//! The DCC is not used; writes are discarded and nothing is ever received
+
+#[inline(always)]
+pub fn dscr() -> u32 {
+    0
+}
+
+#[inline(always)]
+pub fn dtrtx(_word: u32) {}
+
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    0
+}
+
+#[inline(always)]
+pub fn write(_word: u32) {}
+
+#[inline(always)]
+pub fn write_bytes(_bytes: &[u8]) {}
+
+#[inline(always)]
+pub fn wfe() {}
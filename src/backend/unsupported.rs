@@ -0,0 +1,31 @@
+//! Targets that have no DCC
+
+#[inline(always)]
+pub fn dscr() -> u32 {
+    unimplemented!()
+}
+
+#[inline(always)]
+pub fn dtrtx(_word: u32) {
+    unimplemented!()
+}
+
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    unimplemented!()
+}
+
+#[inline(always)]
+pub fn write(_word: u32) {
+    unimplemented!()
+}
+
+#[inline(always)]
+pub fn write_bytes(_bytes: &[u8]) {
+    unimplemented!()
+}
+
+#[inline(always)]
+pub fn wfe() {
+    unimplemented!()
+}
@@ -0,0 +1,60 @@
+//! CP14 debug channel of XScale cores (e.g. PXA255, PXA270, IXP4xx), accessed through the
+//! assembly routines in `asm-xscale.s`
+//!
+//! The TXRXCTRL register reports the state of the TX register in bit 28 (TR) and the state of the
+//! RX register in bit 31 (RR); `dscr` moves these bits to the positions of TXfull and RXfull in the
+//! ARMv7 DBGDSCR register
+
+use crate::{RXFULL, TXFULL};
+
+// NOTE with the `extern-asm` feature these routines come from the prebuilt blob instead
+#[cfg(not(feature = "extern-asm"))]
+core::arch::global_asm!(include_str!("../../asm-xscale.s"));
+
+extern "C" {
+    fn __dcc_xscale_dscr() -> u32;
+    fn __dcc_xscale_dtrrx() -> u32;
+    fn __dcc_xscale_dtrtx(word: u32);
+    fn __dcc_xscale_write(word: u32);
+    fn __dcc_xscale_write_bytes(bytes: *const u8, len: usize);
+}
+
+/// TR bit of the TXRXCTRL register
+const TR: u32 = 1 << 28;
+
+/// RR bit of the TXRXCTRL register
+const RR: u32 = 1 << 31;
+
+/// Reads the TXRXCTRL register
+#[inline(always)]
+pub fn dscr() -> u32 {
+    let control = unsafe { __dcc_xscale_dscr() };
+
+    (if control & TR != 0 { TXFULL } else { 0 }) | (if control & RR != 0 { RXFULL } else { 0 })
+}
+
+/// Writes the TX register
+#[inline(always)]
+pub fn dtrtx(word: u32) {
+    unsafe { __dcc_xscale_dtrtx(word) }
+}
+
+/// Reads the RX register
+#[inline(always)]
+pub fn dtrrx() -> u32 {
+    unsafe { __dcc_xscale_dtrrx() }
+}
+
+#[inline(always)]
+pub fn write(word: u32) {
+    unsafe { __dcc_xscale_write(word) }
+}
+
+#[inline(always)]
+pub fn write_bytes(bytes: &[u8]) {
+    unsafe { __dcc_xscale_write_bytes(bytes.as_ptr(), bytes.len()) }
+}
+
+/// No-op; these cores have no `WFE` instruction
+#[inline(always)]
+pub fn wfe() {}
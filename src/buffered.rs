@@ -0,0 +1,157 @@
+use core::fmt;
+
+use crate::{BYTES_MAX_LEN, BYTES_TAG};
+
+/// Proxy struct that implements `fmt::Write` and buffers up to `N` bytes before sending them
+///
+/// Formatting a value usually results in several small `write_str` calls; this writer collects
+/// them so they reach the DCC as a single `write_all` call. In `packed` mode that means a single
+/// frame instead of one frame (plus header) per formatted piece.
+///
+/// The buffered bytes are sent when `flush` is called, when the buffer runs out of space and when
+/// the writer is dropped.
+///
+/// ``` no_run
+/// use core::fmt::Write;
+///
+/// use arm_dcc::BufferedWriter;
+///
+/// let x = 42;
+/// let mut w = BufferedWriter::<64>::new();
+/// writeln!(w, "x = {}", x).ok();
+/// w.flush();
+/// ```
+pub struct BufferedWriter<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> BufferedWriter<N> {
+    /// Creates an empty buffered writer
+    pub const fn new() -> Self {
+        BufferedWriter {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Buffers the bytes, sending the buffer contents to the DCC first if the bytes don't fit
+    ///
+    /// Inputs that are as large as the buffer are sent straight to the DCC
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        if bytes.len() > N - self.len {
+            self.flush();
+        }
+
+        if bytes.len() >= N {
+            crate::sink::write_text(bytes);
+        } else {
+            self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+            self.len += bytes.len();
+        }
+    }
+
+    /// Sends the buffered bytes to the DCC
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn flush(&mut self) {
+        if self.len != 0 {
+            crate::sink::write_text(&self.buf[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+impl<const N: usize> Default for BufferedWriter<N> {
+    fn default() -> Self {
+        BufferedWriter::new()
+    }
+}
+
+impl<const N: usize> Drop for BufferedWriter<N> {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
+
+impl<const N: usize> fmt::Write for BufferedWriter<N> {
+    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
+        self.write_all(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// Buffers up to `N` bytes before sending them as a binary bytes frame (see `write_bytes_framed`)
+///
+/// Encoders and serializers usually hand out their output in small pieces, often a single byte;
+/// this writer collects them so each frame header is amortized over several words.
+///
+/// The buffered bytes are sent when `flush` is called, when the buffer runs out of space and when
+/// the writer is dropped.
+///
+/// ``` no_run
+/// use arm_dcc::BufferedBytesWriter;
+///
+/// let mut w = BufferedBytesWriter::<64>::new();
+/// w.write(&[0xde, 0xad]);
+/// w.write(&[0xbe, 0xef]);
+/// // sends a single frame
+/// w.flush();
+/// ```
+pub struct BufferedBytesWriter<const N: usize> {
+    // tag of the header of each frame
+    tag: u32,
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> BufferedBytesWriter<N> {
+    /// Creates an empty buffered writer
+    pub const fn new() -> Self {
+        BufferedBytesWriter::with_tag(BYTES_TAG)
+    }
+
+    // creates an empty buffered writer whose frames have the layout of binary bytes frames but
+    // the given `tag`
+    pub(crate) const fn with_tag(tag: u32) -> Self {
+        BufferedBytesWriter {
+            tag,
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Buffers the bytes, sending the buffer contents to the DCC whenever the buffer fills up
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.len == N {
+                self.flush();
+            }
+
+            self.buf[self.len] = *byte;
+            self.len += 1;
+        }
+    }
+
+    /// Sends the buffered bytes to the DCC
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn flush(&mut self) {
+        for frame in self.buf[..self.len].chunks(BYTES_MAX_LEN) {
+            crate::write_packed_frame(self.tag | frame.len() as u32, frame);
+        }
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for BufferedBytesWriter<N> {
+    fn default() -> Self {
+        BufferedBytesWriter::new()
+    }
+}
+
+impl<const N: usize> Drop for BufferedBytesWriter<N> {
+    fn drop(&mut self) {
+        self.flush()
+    }
+}
@@ -0,0 +1,45 @@
+//! C callable wrappers of the write functions, see `arm_dcc.h`
+
+/// Writes a single word to the DCC; see `write`
+#[no_mangle]
+pub extern "C" fn dcc_write(word: u32) {
+    crate::write(word)
+}
+
+/// Writes a NUL terminated string to the DCC; see `write_str`
+///
+/// # Safety
+///
+/// `string` must be null or point to a NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn dcc_write_str(string: *const u8) {
+    if string.is_null() {
+        return;
+    }
+
+    let mut len = 0;
+    while *string.add(len) != 0 {
+        len += 1;
+    }
+
+    crate::sink::write_text(core::slice::from_raw_parts(string, len))
+}
+
+/// Writes `len` bytes to the DCC; see `write_str`
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes; it may be null if `len` is `0`
+#[no_mangle]
+pub unsafe extern "C" fn dcc_write_bytes(bytes: *const u8, len: usize) {
+    if len != 0 {
+        crate::sink::write_text(core::slice::from_raw_parts(bytes, len))
+    }
+}
+
+/// Writes a single character to the DCC and returns it, like `putchar`
+#[no_mangle]
+pub extern "C" fn dcc_putc(c: i32) -> i32 {
+    crate::sink::write_text(&[c as u8]);
+    c
+}
@@ -0,0 +1,78 @@
+//! Logical channels multiplexed over the DCC
+//!
+//! Several subsystems, e.g. logging, telemetry and a file transfer, can share the DCC by writing
+//! to different channels; the host demultiplexes the stream into one stream per channel (see
+//! `decode::Item::Channel` and the `--channels` option of the `dcc-log` tool). Data written with
+//! the rest of the API (`write_all`, `dprint!`, etc.) is not part of any channel.
+//!
+//! Each `Channel::write_all` call sends one or more frames: a `CHANNEL_TAG | id << 11 | n` header
+//! word followed by the `n` bytes packed 4 per word, as in `write_all_packed`, and, with the `crc`
+//! feature, by the CRC of the frame.
+//!
+//! ``` no_run
+//! use core::fmt::Write;
+//!
+//! use arm_dcc::channel::Channel;
+//!
+//! let mut telemetry = Channel::new(1);
+//!
+//! telemetry.write_all(&[0x01, 0x02, 0x03]);
+//! // NOTE this operation never returns `Err`
+//! writeln!(telemetry, "battery: {} mV", 3700).ok();
+//! ```
+//!
+//! **NOTE:** Frames must not interleave: an interrupt handler that writes to the DCC while a
+//! frame is being sent corrupts it, as with the other framed encodings.
+
+use core::fmt;
+
+/// Upper half-word of the header word that precedes each channel frame
+pub const CHANNEL_TAG: u32 = 0xDCC4_0000;
+
+/// Number of channels; the channel ID goes in bits 11 to 15 of the header word
+pub const CHANNELS: u8 = 32;
+
+/// Maximum number of bytes in a single frame; the length goes in bits 0 to 10 of the header word
+pub const CHANNEL_MAX_LEN: usize = 0x7ff;
+
+/// A logical channel
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Channel {
+    id: u8,
+}
+
+impl Channel {
+    /// Returns the channel with the given ID
+    ///
+    /// # Panics
+    ///
+    /// If `id` is not below `CHANNELS`
+    pub const fn new(id: u8) -> Self {
+        assert!(id < CHANNELS, "channel ID out of range");
+        Channel { id }
+    }
+
+    /// Returns the ID of the channel
+    pub const fn id(self) -> u8 {
+        self.id
+    }
+
+    /// Writes the bytes to the channel
+    ///
+    /// Inputs longer than `CHANNEL_MAX_LEN` are split into several frames.
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn write_all(self, bytes: &[u8]) {
+        for frame in bytes.chunks(CHANNEL_MAX_LEN) {
+            let header = CHANNEL_TAG | u32::from(self.id) << 11 | frame.len() as u32;
+            crate::write_packed_frame(header, frame)
+        }
+    }
+}
+
+impl fmt::Write for Channel {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Channel::write_all(*self, s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,486 @@
+//! LZSS compression of the text output
+//!
+//! With the `compress` feature enabled the text API (`write_str`, `Writer`, the `dprint*` macros,
+//! the log macros, etc.) sends its output compressed: repeated phrases, e.g. the file name, the
+//! level and the format string of a log line, are replaced with references to the last `WINDOW`
+//! bytes of output, which usually shrinks verbose logs to a half or a third of their size, and the
+//! DCC is slow. `Compressor` can also be used directly.
+//!
+//! The compressed stream is a sequence of tokens, most significant bit first:
+//!
+//! - a literal: `1` followed by the 8 bits of the byte
+//! - a match: `0`, 8 bits of distance (`1` to `255` bytes back) and 4 bits of length minus
+//!   `MIN_MATCH`; the match may overlap the bytes it produces
+//! - the end of a flush: `0` followed by a distance of `0`; the bits left in the current byte are
+//!   padding. Both ends forget the history here so matches never refer to an earlier flush.
+//!
+//! The stream is sent as one or more `COMPRESSED_TAG | n` frames that have the layout of
+//! `write_all_packed` frames; `decode::Decoder` reports their contents as
+//! `decode::Item::Compressed` and `Decompressor` turns those back into the original bytes.
+//! `Compressor::write` ends each frame with the end of a flush so every frame can be decompressed
+//! on its own: a host that misses a frame, e.g. because words were dropped under the `Lossy` policy
+//! or it attached late, only loses the text of that frame. Call `Decompressor::reset` when that
+//! happens.
+//!
+//! ```
+//! use arm_dcc::compress::{Compressor, Decompressor};
+//!
+//! let mut compressed = Vec::new();
+//! let mut compressor = Compressor::new();
+//! compressor.compress(b"INFO sensor: 100 mV\nINFO sensor: 101 mV\n", |byte| {
+//!     compressed.push(byte)
+//! });
+//! compressor.flush_with(|byte| compressed.push(byte));
+//! assert!(compressed.len() < 30);
+//!
+//! let mut text = Vec::new();
+//! Decompressor::new().push(&compressed, |byte| text.push(byte));
+//! assert_eq!(text, b"INFO sensor: 100 mV\nINFO sensor: 101 mV\n");
+//! ```
+
+#[cfg(feature = "compress")]
+use core::cell::UnsafeCell;
+
+#[cfg(feature = "compress")]
+use crate::atomic::{AtomicBool, Ordering};
+
+/// Upper half-word of the header word that precedes each frame of compressed data
+pub const COMPRESSED_TAG: u32 = 0xDCC5_0000;
+
+/// Number of bytes of history that matches can refer to
+pub const WINDOW: usize = 255;
+
+/// Length of the shortest match
+pub const MIN_MATCH: usize = 2;
+
+/// Length of the longest match
+pub const MAX_MATCH: usize = MIN_MATCH + 15;
+
+// compressed bytes buffered before they are sent as a frame
+const OUT_LEN: usize = 128;
+
+// bound of the bytes that a token and the end of a flush can add to `out`: a 9-bit literal for
+// each byte of the lookahead, then 3 bytes for the end token, the bits left and the padding
+const MAX_FINISH: usize = (MAX_MATCH + 1) * 2 + 3;
+
+/// Streaming LZSS compressor
+///
+/// `write` sends the compressed stream over the DCC, as `COMPRESSED_TAG` frames; `compress` hands
+/// it to a closure instead.
+pub struct Compressor {
+    // the last `WINDOW` bytes, a ring buffer indexed by `pos`
+    history: [u8; WINDOW + 1],
+    // number of bytes that went through the compressor, modulo the size of `history`
+    pos: usize,
+    // bytes that have not been encoded yet
+    lookahead: [u8; MAX_MATCH],
+    lookahead_len: usize,
+    // bits that don't form a byte yet
+    bits: u32,
+    nbits: u32,
+    // compressed bytes that have not been sent yet; only used by `write`
+    out: [u8; OUT_LEN],
+    out_len: usize,
+}
+
+impl Compressor {
+    /// Creates a compressor with an empty history
+    pub const fn new() -> Self {
+        Compressor {
+            history: [0; WINDOW + 1],
+            pos: 0,
+            lookahead: [0; MAX_MATCH],
+            lookahead_len: 0,
+            bits: 0,
+            nbits: 0,
+            out: [0; OUT_LEN],
+            out_len: 0,
+        }
+    }
+
+    /// Compresses the bytes and sends the compressed stream over the DCC
+    ///
+    /// A frame is sent, ending with the end of a flush, whenever the next bytes may not fit in it.
+    /// Call `flush` to make sure the host receives all of the bytes.
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.out_len + MAX_FINISH > OUT_LEN {
+                self.flush();
+            }
+
+            self.feed(*byte, &mut Self::send_later);
+        }
+    }
+
+    /// Sends the bytes that are still in the compressor over the DCC
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn flush(&mut self) {
+        self.finish(&mut Self::send_later);
+
+        if self.out_len != 0 {
+            crate::write_packed_frame(
+                COMPRESSED_TAG | self.out_len as u32,
+                &self.out[..self.out_len],
+            );
+            self.out_len = 0;
+        }
+    }
+
+    /// Compresses the bytes and passes the compressed stream to `f`, byte by byte
+    pub fn compress(&mut self, bytes: &[u8], mut f: impl FnMut(u8)) {
+        for byte in bytes {
+            self.feed(*byte, &mut |_: &mut Self, byte| f(byte));
+        }
+    }
+
+    /// Passes the rest of the compressed stream, up to the end of a flush, to `f`
+    pub fn flush_with(&mut self, mut f: impl FnMut(u8)) {
+        self.finish(&mut |_: &mut Self, byte| f(byte));
+    }
+
+    fn send_later(&mut self, byte: u8) {
+        self.out[self.out_len] = byte;
+        self.out_len += 1;
+
+        if self.out_len == OUT_LEN {
+            crate::write_packed_frame(COMPRESSED_TAG | OUT_LEN as u32, &self.out);
+            self.out_len = 0;
+        }
+    }
+
+    fn feed(&mut self, byte: u8, emit: &mut dyn FnMut(&mut Self, u8)) {
+        self.lookahead[self.lookahead_len] = byte;
+        self.lookahead_len += 1;
+
+        if self.lookahead_len == MAX_MATCH {
+            self.token(emit);
+        }
+    }
+
+    fn finish(&mut self, emit: &mut dyn FnMut(&mut Self, u8)) {
+        while self.lookahead_len != 0 {
+            self.token(emit);
+        }
+
+        self.put(0, 9, emit);
+        if self.nbits != 0 {
+            self.put(0, 8 - self.nbits, emit);
+        }
+
+        // NOTE `Decompressor` forgets its history at the end token too
+        self.history = [0; WINDOW + 1];
+        self.pos = 0;
+    }
+
+    // encodes the longest match found at the start of the lookahead, or its first byte
+    fn token(&mut self, emit: &mut dyn FnMut(&mut Self, u8)) {
+        let (distance, len) = self.longest_match();
+
+        let consumed = if len >= MIN_MATCH {
+            let token = (distance as u32) << 4 | (len - MIN_MATCH) as u32;
+            self.put(token, 13, emit);
+            len
+        } else {
+            self.put(0x100 | u32::from(self.lookahead[0]), 9, emit);
+            1
+        };
+
+        for i in 0..consumed {
+            self.history[self.pos] = self.lookahead[i];
+            self.pos = (self.pos + 1) % self.history.len();
+        }
+        self.lookahead.copy_within(consumed..self.lookahead_len, 0);
+        self.lookahead_len -= consumed;
+    }
+
+    fn longest_match(&self) -> (usize, usize) {
+        let mut best = (0, 0);
+        for distance in 1..=WINDOW {
+            let mut len = 0;
+            while len < self.lookahead_len && self.at(distance, len) == self.lookahead[len] {
+                len += 1;
+            }
+
+            if len > best.1 {
+                best = (distance, len);
+
+                if len == self.lookahead_len {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // byte at offset `i` of a match that starts `distance` bytes before the lookahead
+    fn at(&self, distance: usize, i: usize) -> u8 {
+        if i < distance {
+            let n = self.history.len();
+            self.history[(self.pos + n - distance + i) % n]
+        } else {
+            // NOTE the match overlaps the bytes it produces
+            self.lookahead[i - distance]
+        }
+    }
+
+    fn put(&mut self, value: u32, width: u32, emit: &mut dyn FnMut(&mut Self, u8)) {
+        self.bits = self.bits << width | value;
+        self.nbits += width;
+
+        while self.nbits >= 8 {
+            self.nbits -= 8;
+            let byte = (self.bits >> self.nbits) as u8;
+            self.bits &= (1 << self.nbits) - 1;
+            emit(self, byte);
+        }
+    }
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Compressor::new()
+    }
+}
+
+/// Streaming LZSS decompressor
+#[derive(Clone, Debug)]
+pub struct Decompressor {
+    // the last `WINDOW` bytes of output, a ring buffer indexed by `pos`
+    history: [u8; WINDOW + 1],
+    pos: usize,
+    // bits that have not been decoded yet
+    bits: u32,
+    nbits: u32,
+}
+
+impl Decompressor {
+    /// Creates a decompressor with an empty history
+    pub const fn new() -> Self {
+        Decompressor {
+            history: [0; WINDOW + 1],
+            pos: 0,
+            bits: 0,
+            nbits: 0,
+        }
+    }
+
+    /// Forgets the history and any partially received token
+    ///
+    /// Call this when part of the compressed stream was lost; decompression resumes correctly at
+    /// the start of the next frame.
+    pub fn reset(&mut self) {
+        *self = Decompressor::new();
+    }
+
+    /// Feeds the next bytes of the compressed stream, e.g. the contents of a
+    /// `decode::Item::Compressed`, and passes the decompressed bytes to `f`
+    pub fn push(&mut self, bytes: &[u8], mut f: impl FnMut(u8)) {
+        for byte in bytes {
+            self.bits = self.bits << 8 | u32::from(*byte);
+            self.nbits += 8;
+
+            while let Some(token) = self.token() {
+                match token {
+                    Token::Literal(byte) => self.output(byte, &mut f),
+                    Token::Match { distance, len } => {
+                        let n = self.history.len();
+                        for _ in 0..len {
+                            let byte = self.history[(self.pos + n - distance) % n];
+                            self.output(byte, &mut f);
+                        }
+                    }
+                    // NOTE the end of a flush is padded to a whole byte
+                    Token::End => {
+                        self.nbits -= self.nbits % 8;
+                        self.history = [0; WINDOW + 1];
+                        self.pos = 0;
+                    }
+                }
+                self.bits &= (1 << self.nbits) - 1;
+            }
+        }
+    }
+
+    fn output(&mut self, byte: u8, f: &mut impl FnMut(u8)) {
+        self.history[self.pos] = byte;
+        self.pos = (self.pos + 1) % self.history.len();
+        f(byte)
+    }
+
+    // the next `width` bits, if they have been received
+    fn peek(&self, width: u32) -> Option<u32> {
+        if self.nbits >= width {
+            Some(self.bits >> (self.nbits - width))
+        } else {
+            None
+        }
+    }
+
+    // takes the next complete token out of `bits`
+    fn token(&mut self) -> Option<Token> {
+        let token = if self.peek(1)? == 1 {
+            let byte = self.peek(9)? as u8;
+            self.nbits -= 9;
+            Token::Literal(byte)
+        } else if self.peek(9)? == 0 {
+            self.nbits -= 9;
+            Token::End
+        } else {
+            let token = self.peek(13)?;
+            self.nbits -= 13;
+            Token::Match {
+                distance: (token >> 4) as usize & 0xff,
+                len: (token & 0xf) as usize + MIN_MATCH,
+            }
+        };
+        Some(token)
+    }
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Decompressor::new()
+    }
+}
+
+enum Token {
+    Literal(u8),
+    Match { distance: usize, len: usize },
+    End,
+}
+
+// compressor used by the text API when the `compress` feature is enabled
+#[cfg(feature = "compress")]
+struct Global {
+    busy: AtomicBool,
+    compressor: UnsafeCell<Compressor>,
+}
+
+// NOTE(unsafe) `compressor` is only accessed by whoever set `busy`
+#[cfg(feature = "compress")]
+unsafe impl Sync for Global {}
+
+#[cfg(feature = "compress")]
+static GLOBAL: Global = Global {
+    busy: AtomicBool::new(false),
+    compressor: UnsafeCell::new(Compressor::new()),
+};
+
+/// Sends text through the global compressor, flushing it afterwards
+///
+/// If the compressor is in use, e.g. this interrupted a `write_text` call, the text is sent
+/// uncompressed
+#[cfg(feature = "compress")]
+pub(crate) fn write_text(bytes: &[u8]) {
+    if GLOBAL.busy.swap(true, Ordering::Acquire) {
+        return crate::write_all(bytes);
+    }
+
+    // NOTE(unsafe) `busy` was `false` so no one else is accessing `compressor`
+    let compressor = unsafe { &mut *GLOBAL.compressor.get() };
+    compressor.write(bytes);
+    compressor.flush();
+
+    GLOBAL.busy.store(false, Ordering::Release);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::{Compressor, Decompressor, MAX_MATCH};
+
+    fn decompress(compressed: &[u8]) -> Vec<u8> {
+        let mut text = Vec::new();
+        Decompressor::new().push(compressed, |byte| text.push(byte));
+        text
+    }
+
+    #[test]
+    fn flushes() {
+        let lines: [&[u8]; 3] = [
+            b"INFO sensor: 100 mV\n",
+            b"INFO sensor: 101 mV\n",
+            b"WARN sensor: 102 mV\n",
+        ];
+
+        let mut compressor = Compressor::new();
+        let mut stream = Vec::new();
+        let mut flushes = Vec::new();
+        for line in lines.iter() {
+            let mut compressed = Vec::new();
+            compressor.compress(line, |byte| compressed.push(byte));
+            compressor.flush_with(|byte| compressed.push(byte));
+
+            // NOTE every flush can be decompressed on its own
+            assert_eq!(decompress(&compressed), *line);
+            stream.extend_from_slice(&compressed);
+            flushes.push(compressed);
+        }
+
+        // NOTE the history is forgotten at every flush so the later lines don't compress better
+        assert_eq!(flushes[0].len(), flushes[1].len());
+        assert_eq!(decompress(&stream), lines.concat());
+
+        // the stream may arrive in pieces of any size
+        let mut text = Vec::new();
+        let mut decompressor = Decompressor::new();
+        for piece in stream.chunks(3) {
+            decompressor.push(piece, |byte| text.push(byte));
+        }
+        assert_eq!(text, lines.concat());
+    }
+
+    #[test]
+    fn overlapping_matches() {
+        for text in [&[b'a'; 40][..], b"abcabcabcabcabcabcabcabc"].iter() {
+            let mut compressed = Vec::new();
+            let mut compressor = Compressor::new();
+            compressor.compress(text, |byte| compressed.push(byte));
+            compressor.flush_with(|byte| compressed.push(byte));
+
+            assert!(compressed.len() * 2 < text.len());
+            assert_eq!(decompress(&compressed), *text);
+        }
+    }
+
+    #[test]
+    fn longer_than_the_window() {
+        let text = (0..1024)
+            .map(|i| (i * 7 % 61) as u8 + b' ')
+            .collect::<Vec<_>>();
+
+        let mut compressed = Vec::new();
+        let mut compressor = Compressor::new();
+        for chunk in text.chunks(MAX_MATCH + 1) {
+            compressor.compress(chunk, |byte| compressed.push(byte));
+        }
+        compressor.flush_with(|byte| compressed.push(byte));
+
+        assert_eq!(decompress(&compressed), text);
+    }
+
+    #[test]
+    fn reset() {
+        let mut compressed = Vec::new();
+        let mut compressor = Compressor::new();
+        compressor.compress(b"lost", |byte| compressed.push(byte));
+        compressor.flush_with(|byte| compressed.push(byte));
+        let lost = compressed.len();
+        compressor.compress(b"received", |byte| compressed.push(byte));
+        compressor.flush_with(|byte| compressed.push(byte));
+
+        let mut text = Vec::new();
+        let mut decompressor = Decompressor::new();
+        decompressor.push(&compressed[..2], |byte| text.push(byte));
+        decompressor.reset();
+        text.clear();
+        decompressor.push(&compressed[lost..], |byte| text.push(byte));
+        assert_eq!(text, b"received");
+    }
+}
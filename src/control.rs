@@ -0,0 +1,119 @@
+//! Runtime control of the device from the host
+//!
+//! The host controls the device by sending command words over the DCC. The most significant byte
+//! of a command word is the opcode; the rest of the word is the argument.
+//!
+//! | Opcode | Command         | Argument                                     |
+//! |--------|-----------------|----------------------------------------------|
+//! | `0x01` | set verbosity   | new verbosity, `0` (nothing) to `255`        |
+//! | `0x02` | enable channel  | channel ID, `0` to `31`                      |
+//! | `0x03` | disable channel | channel ID, `0` to `31`                      |
+//! | `0x04` | dump statistics | none; the device replies with a line of text |
+//! | `0x05` | set log level   | `LevelFilter`, `0` (`Off`) to `5` (`Trace`)  |
+//!
+//! The device applies these commands when the application calls `poll` (or `handle`, from a
+//! COMMRX interrupt handler). The application decides what verbosity and channels mean by
+//! checking `verbosity` and `is_channel_enabled`. The log level is the runtime filter of `dlog!`
+//! and friends; see `set_max_level`. Words with unknown opcodes are passed to the
+//! function registered with `set_handler`, if any.
+
+use core::fmt::Write;
+
+use crate::atomic::{AtomicU32, AtomicU8, FnCell, Ordering};
+
+/// Opcode of the "set verbosity" command
+pub const SET_VERBOSITY: u8 = 0x01;
+/// Opcode of the "enable channel" command
+pub const ENABLE_CHANNEL: u8 = 0x02;
+/// Opcode of the "disable channel" command
+pub const DISABLE_CHANNEL: u8 = 0x03;
+/// Opcode of the "dump statistics" command
+pub const DUMP_STATS: u8 = 0x04;
+/// Opcode of the "set log level" command
+pub const SET_LOG_LEVEL: u8 = 0x05;
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(u8::MAX);
+static CHANNELS: AtomicU32 = AtomicU32::new(!0);
+static HANDLER: FnCell<fn(u32)> = FnCell::new();
+
+/// Encodes a command; host tooling can use this to prepare the words it sends to the device
+pub const fn command(opcode: u8, argument: u32) -> u32 {
+    (opcode as u32) << 24 | argument & 0x00ff_ffff
+}
+
+/// Processes all the commands the host has sent so far
+///
+/// Returns the number of commands that were processed
+pub fn poll() -> usize {
+    let mut n = 0;
+    while let Some(word) = crate::try_read() {
+        handle(word);
+        n += 1;
+    }
+    n
+}
+
+/// Processes a single command word
+///
+/// Returns `false` if the opcode is unknown (the word is then passed to the `set_handler`
+/// function)
+pub fn handle(word: u32) -> bool {
+    let argument = word & 0x00ff_ffff;
+
+    match (word >> 24) as u8 {
+        SET_VERBOSITY => VERBOSITY.store(argument as u8, Ordering::Relaxed),
+        ENABLE_CHANNEL if argument < 32 => {
+            CHANNELS.fetch_or(1 << argument, Ordering::Relaxed);
+        }
+        DISABLE_CHANNEL if argument < 32 => {
+            CHANNELS.fetch_and(!(1 << argument), Ordering::Relaxed);
+        }
+        DUMP_STATS => {
+            let stats = crate::stats();
+            // NOTE this operation never returns `Err`
+            writeln!(
+                crate::Writer,
+                "words_written={} words_dropped={} bytes_written={} bytes_dropped={} max_wait={}",
+                stats.words_written,
+                stats.words_dropped,
+                stats.bytes_written,
+                stats.bytes_dropped,
+                stats.max_wait
+            )
+            .ok();
+        }
+        SET_LOG_LEVEL => {
+            let level = if argument > 5 { 5 } else { argument as u8 };
+            crate::set_max_level(crate::LevelFilter::from_u8(level))
+        }
+        _ => {
+            if let Some(handler) = HANDLER.get() {
+                handler(word);
+            }
+
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Registers a function that will be called with the command words that have unknown opcodes
+///
+/// Applications can use this to implement their own commands
+pub fn set_handler(handler: fn(u32)) {
+    HANDLER.set(handler);
+}
+
+/// Returns the verbosity requested by the host; it's `255` until the host changes it
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// Returns `true` unless the host has disabled the channel
+///
+/// All channels are enabled until the host disables them. Channels IDs above `31` are always
+/// enabled.
+pub fn is_channel_enabled(channel: u8) -> bool {
+    channel >= 32 || CHANNELS.load(Ordering::Relaxed) & (1 << channel) != 0
+}
@@ -0,0 +1,305 @@
+//! Core dumps
+//!
+//! `write` streams the registers of the core and the memory regions registered with
+//! `set_regions` to the host, so a crash can be inspected with a debugger after the fact. The
+//! `dcc-coredump` tool turns the dump into an ELF core file that GDB can load.
+//!
+//! The dump is sent as one or more binary frames (see `write_words_framed`) so host decoders
+//! report it as `decode::Item::Word`s. The payload is:
+//!
+//! - `MAGIC`
+//! - `VERSION`
+//! - flags: `FLAG_BIG_ENDIAN` if the memory words are big endian
+//! - the `Reason` of the dump
+//! - the number of memory regions
+//! - `REGISTERS` words: r0 - r15 and CPSR
+//! - for each memory region its address, its length in words and then its contents
+//! - the CRC-32 of all the previous words (see `Checksum`)
+//!
+//! `write_text` prints the same payload, as little endian bytes encoded in Base64, through the
+//! text API instead, for setups that only capture a plain text DCC log.
+//!
+//! Core dumps are only produced on 32-bit ARM cores.
+//!
+//! ``` no_run
+//! use arm_dcc::coredump::{self, Reason, Region};
+//!
+//! static REGIONS: [Region; 1] = [Region {
+//!     address: 0x0010_0000,
+//!     words: 0x4000,
+//! }];
+//!
+//! // during initialization
+//! unsafe { coredump::set_regions(&REGIONS) }
+//!
+//! // e.g. in an exception handler
+//! # let registers = [0; coredump::REGISTERS];
+//! coredump::write(Reason::DataAbort, &registers);
+//! ```
+
+use core::{ptr, slice};
+
+use crate::{
+    atomic::{AtomicPtr, AtomicUsize, Ordering},
+    crc::Crc32,
+    frame::FrameWriter,
+    framing::base64::Encoder,
+    WORDS_MAX_LEN, WORDS_TAG,
+};
+
+/// First word of the payload of a core dump
+pub const MAGIC: u32 = 0xC0DE_D0CC;
+
+/// Version of the core dump format
+pub const VERSION: u32 = 1;
+
+/// Flag that's set when the memory words of the dump are big endian
+pub const FLAG_BIG_ENDIAN: u32 = 1 << 0;
+
+/// Number of registers in a core dump: r0 - r15 and CPSR
+pub const REGISTERS: usize = 17;
+
+/// Line that precedes a core dump printed by `write_text`
+pub const TEXT_BEGIN: &str = "-----BEGIN CORE DUMP-----";
+
+/// Line that follows a core dump printed by `write_text`
+pub const TEXT_END: &str = "-----END CORE DUMP-----";
+
+static REGIONS: AtomicPtr<Region> = AtomicPtr::new(ptr::null_mut());
+static REGIONS_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Why the core dump was taken
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reason {
+    /// The program panicked
+    Panic,
+    /// An undefined instruction exception was taken
+    UndefinedInstruction,
+    /// A prefetch abort exception was taken
+    PrefetchAbort,
+    /// A data abort exception was taken
+    DataAbort,
+}
+
+impl Reason {
+    /// Decodes the reason word of a core dump
+    pub fn from_u32(word: u32) -> Option<Self> {
+        Some(match word {
+            0 => Reason::Panic,
+            1 => Reason::UndefinedInstruction,
+            2 => Reason::PrefetchAbort,
+            3 => Reason::DataAbort,
+            _ => return None,
+        })
+    }
+
+    /// Encodes the reason as in a core dump
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Reason::Panic => 0,
+            Reason::UndefinedInstruction => 1,
+            Reason::PrefetchAbort => 2,
+            Reason::DataAbort => 3,
+        }
+    }
+}
+
+/// A memory region included in core dumps
+#[derive(Clone, Copy, Debug)]
+pub struct Region {
+    /// Address of the first word of the region; must be 4-byte aligned
+    pub address: usize,
+    /// Length of the region, in words
+    pub words: usize,
+}
+
+/// CRC-32 (the one used by Ethernet and zlib) of a sequence of words, each one taken as 4 bytes in
+/// little endian order; see `crc::Crc32`
+#[derive(Clone, Copy, Debug)]
+pub struct Checksum(Crc32);
+
+impl Checksum {
+    /// Starts a new checksum
+    pub const fn new() -> Self {
+        Checksum(Crc32::new())
+    }
+
+    /// Adds a word to the checksum
+    pub fn update(&mut self, word: u32) {
+        self.0.update_word(word)
+    }
+
+    /// Returns the checksum of the words added so far
+    pub fn finish(self) -> u32 {
+        self.0.finish()
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Checksum::new()
+    }
+}
+
+/// Registers the memory regions that `write` includes in core dumps
+///
+/// # Safety
+///
+/// The regions must be readable memory for as long as they are registered
+pub unsafe fn set_regions(regions: &'static [Region]) {
+    // NOTE the length is cleared first so `write` never pairs the new pointer with the old length
+    REGIONS_LEN.store(0, Ordering::Relaxed);
+    REGIONS.store(regions.as_ptr() as *mut Region, Ordering::Release);
+    REGIONS_LEN.store(regions.len(), Ordering::Release);
+}
+
+/// Unregisters the regions set with `set_regions`; core dumps will only contain the registers
+pub fn clear_regions() {
+    REGIONS_LEN.store(0, Ordering::Release);
+}
+
+fn regions() -> &'static [Region] {
+    let len = REGIONS_LEN.load(Ordering::Acquire);
+    let regions = REGIONS.load(Ordering::Acquire);
+
+    if len == 0 || regions.is_null() {
+        &[]
+    } else {
+        // NOTE(unsafe) `set_regions` stored these from a `&'static [Region]`
+        unsafe { slice::from_raw_parts(regions, len) }
+    }
+}
+
+/// Streams a core dump with the given registers (r0 - r15 and CPSR) and the registered memory
+/// regions to the host
+///
+/// **NOTE:** This operation is blocking
+pub fn write(reason: Reason, registers: &[u32; REGISTERS]) {
+    dump(reason, registers, &mut Frames { frame: None })
+}
+
+/// Like `write` but prints the core dump as Base64 text, between a `TEXT_BEGIN` and a `TEXT_END`
+/// line, through the text API; see `framing::base64`
+///
+/// Use this when only a plain text DCC log, e.g. one captured with `readjtaguart`, is available;
+/// `dcc-coredump --text` extracts the dump from the log.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_text(reason: Reason, registers: &[u32; REGISTERS]) {
+    crate::sink::write_text(TEXT_BEGIN.as_bytes());
+    crate::sink::write_text(b"\n");
+
+    let mut text = Text {
+        encoder: Encoder::new(),
+    };
+    dump(reason, registers, &mut text);
+    text.encoder.finish();
+
+    crate::sink::write_text(TEXT_END.as_bytes());
+    crate::sink::write_text(b"\n");
+}
+
+// destination of the words of a core dump
+trait Output {
+    // starts a group of `len` words
+    fn start(&mut self, len: usize);
+
+    fn word(&mut self, word: u32);
+
+    // ends the last group of words
+    fn end(&mut self);
+}
+
+// each group of words is sent as a binary frame
+struct Frames {
+    frame: Option<FrameWriter>,
+}
+
+impl Output for Frames {
+    fn start(&mut self, len: usize) {
+        if let Some(frame) = self.frame.take() {
+            frame.finish()
+        }
+
+        self.frame = Some(FrameWriter::new(WORDS_TAG | len as u32));
+    }
+
+    fn word(&mut self, word: u32) {
+        if let Some(frame) = &mut self.frame {
+            frame.write(word)
+        }
+    }
+
+    fn end(&mut self) {
+        if let Some(frame) = self.frame.take() {
+            frame.finish()
+        }
+    }
+}
+
+// the words are printed as Base64, as little endian bytes
+struct Text {
+    encoder: Encoder,
+}
+
+impl Output for Text {
+    fn start(&mut self, _: usize) {}
+
+    fn word(&mut self, word: u32) {
+        self.encoder.write(&word.to_le_bytes())
+    }
+
+    fn end(&mut self) {}
+}
+
+fn dump(reason: Reason, registers: &[u32; REGISTERS], output: &mut impl Output) {
+    let regions = regions();
+    let flags = if cfg!(target_endian = "big") {
+        FLAG_BIG_ENDIAN
+    } else {
+        0
+    };
+
+    let mut checksum = Checksum::new();
+    let mut send = |output: &mut dyn Output, word: u32| {
+        checksum.update(word);
+        output.word(word);
+    };
+
+    let header = [MAGIC, VERSION, flags, reason.to_u32(), regions.len() as u32];
+    output.start(header.len() + REGISTERS);
+    header
+        .iter()
+        .chain(registers.iter())
+        .for_each(|word| send(output, *word));
+
+    for region in regions {
+        output.start(2);
+        send(output, region.address as u32);
+        send(output, region.words as u32);
+
+        let mut address = region.address as *const u32;
+        let mut left = region.words;
+        while left != 0 {
+            let n = if left < WORDS_MAX_LEN {
+                left
+            } else {
+                WORDS_MAX_LEN
+            };
+            left -= n;
+
+            output.start(n);
+            for _ in 0..n {
+                // NOTE(unsafe) `set_regions` requires the regions to be readable
+                unsafe {
+                    send(output, ptr::read_volatile(address));
+                    address = address.add(1);
+                }
+            }
+        }
+    }
+
+    output.start(1);
+    output.word(checksum.finish());
+    output.end();
+}
@@ -0,0 +1,80 @@
+//! Frame check sequence
+//!
+//! With the `crc` feature enabled every frame sent by `write_all_packed`, `write_words_framed`,
+//! `write_bytes_framed` and `channel::Channel::write_all` is followed by one more word: the CRC-32
+//! of the words of the frame, header word included, each one taken as 4 little endian bytes. The
+//! handshake frame sent by `init` has no CRC; its `FEATURE_CRC` bit tells the host to expect them.
+//!
+//! `decode::Decoder` checks the CRC of each frame and reports a mismatch as `decode::Item::BadCrc`
+//! after the last item of the frame, so corrupted words (e.g. due to a long JTAG cable or a flaky
+//! adapter) don't go unnoticed.
+//!
+//! ```
+//! use arm_dcc::crc::Crc32;
+//!
+//! let mut crc = Crc32::new();
+//! crc.update(b"123456789");
+//! assert_eq!(crc.finish(), 0xCBF4_3926);
+//! ```
+
+/// Reversed polynomial of the CRC-32 used by Ethernet and zlib
+const POLY: u32 = 0xEDB8_8320;
+
+// NOTE a table of 16 entries, one per nibble, keeps the code size small while processing 4 bits
+// per step
+const TABLE: [u32; 16] = table();
+
+const fn table() -> [u32; 16] {
+    let mut table = [0; 16];
+    let mut i = 0;
+    while i < 16 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 4 {
+            crc = if crc & 1 == 0 { crc >> 1 } else { crc >> 1 ^ POLY };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// CRC-32 computed incrementally
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    /// Starts a new computation
+    pub const fn new() -> Self {
+        Crc32 { state: !0 }
+    }
+
+    /// Feeds bytes into the computation
+    pub fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            let mut crc = self.state ^ u32::from(*byte);
+            crc = crc >> 4 ^ TABLE[crc as usize & 0xf];
+            crc = crc >> 4 ^ TABLE[crc as usize & 0xf];
+            self.state = crc;
+        }
+    }
+
+    /// Feeds a word, as 4 little endian bytes, into the computation
+    pub fn update_word(&mut self, word: u32) {
+        self.update(&word.to_le_bytes())
+    }
+
+    /// Returns the CRC of the data fed so far
+    pub fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
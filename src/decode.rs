@@ -0,0 +1,745 @@
+//! Decoding of the DCC word stream
+//!
+//! This module is meant to be used by host tooling that receives the raw words written by the
+//! device. It understands every encoding produced by this crate:
+//!
+//! - word-extended text, as sent by `write_all`: one byte per word, the word is always `< 0x100`
+//! - packed text, as sent by `write_all_packed`: a `PACKED_TAG | n` header word followed by
+//!   `ceil(n / 4)` words. Each word carries 4 bytes, least significant byte first; the unused bytes
+//!   of the last word are zero.
+//! - binary words, as sent by `write_words_framed`: a `WORDS_TAG | n` header word followed by `n`
+//!   words
+//! - binary bytes, as sent by `write_bytes_framed`: same layout as packed text but with a
+//!   `BYTES_TAG | n` header word
+//! - values, as sent by `value::send_value`: same layout as packed text but with a `VALUE_TAG | n`
+//!   header word, between the binary words frames that start and end the value
+//! - channel frames, as sent by `channel::Channel::write_all`: same layout as packed text but with
+//!   a `CHANNEL_TAG | id << 11 | n` header word
+//! - compressed text, as sent with the `compress` feature: same layout as packed text but with a
+//!   `compress::COMPRESSED_TAG | n` header word; feed the bytes to a `compress::Decompressor`
+//! - the handshake frame, as sent by `init`; see the `handshake` module
+//! - code addresses, as sent by `address::write_addresses`: same layout as binary words but with
+//!   an `address::ADDRESS_TAG | n` header word
+//! - the exit status, as sent by `exit::exit`: an `exit::EXIT_TAG` header word followed by the
+//!   status
+//! - binary log records, as sent with the `binary-log` feature: a `record::RECORD_TAG | n` header
+//!   word followed by the message, with the layout of packed text, and by 4 more words; see the
+//!   `record` module
+//!
+//! If the device has the `crc` feature enabled the framed encodings end with a CRC word, which the
+//! decoder checks; see the `crc` module. With the `sequence` feature the header word is followed
+//! by a sequence number, which the decoder uses to detect lost frames. The decoder expects these
+//! words once it has received a handshake frame with the `FEATURE_CRC` or `FEATURE_SEQUENCE` bits
+//! set, or from the start if created with `Decoder::with_features`.
+//!
+//! ```
+//! use arm_dcc::decode::{Decoder, Item};
+//!
+//! let mut decoder = Decoder::new();
+//! let mut text = [0; 8];
+//! let mut len = 0;
+//!
+//! // "Hi" word-extended, then "there!" packed
+//! let words = [0x48, 0x69, arm_dcc::PACKED_TAG | 6, 0x7265_6874, 0x0000_2165];
+//! for word in words.iter() {
+//!     if let Some(Item::Text(chunk)) = decoder.push(*word) {
+//!         let bytes = chunk.as_bytes();
+//!         text[len..len + bytes.len()].copy_from_slice(bytes);
+//!         len += bytes.len();
+//!     }
+//! }
+//!
+//! assert_eq!(&text[..len], b"Hithere!");
+//! ```
+
+use crate::{
+    address::ADDRESS_TAG,
+    channel::{CHANNEL_MAX_LEN, CHANNEL_TAG},
+    compress::COMPRESSED_TAG,
+    crc::Crc32,
+    exit::EXIT_TAG,
+    handshake::{Handshake, FEATURE_CRC, FEATURE_SEQUENCE, HANDSHAKE_TAG},
+    record::RECORD_TAG,
+    BYTES_TAG, PACKED_TAG, VALUE_TAG, WORDS_TAG,
+};
+
+/// Mask that extracts the tag from a header word
+const TAG_MASK: u32 = 0xffff_0000;
+
+/// Up to 4 bytes of text or binary data
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Chunk {
+    buf: [u8; 4],
+    len: u8,
+}
+
+impl Chunk {
+    /// Returns the bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..usize::from(self.len)]
+    }
+}
+
+/// Data extracted from the DCC word stream
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Item {
+    /// Text bytes
+    Text(Chunk),
+    /// A binary word sent using `write_words_framed`
+    Word(u32),
+    /// Binary bytes sent using `write_bytes_framed`
+    Bytes(Chunk),
+    /// Serialized bytes of a value sent using `value::send_value`
+    Value(Chunk),
+    /// Bytes sent to the channel with the given ID; see the `channel` module
+    Channel(u8, Chunk),
+    /// Compressed text; see the `compress` module
+    Compressed(Chunk),
+    /// The handshake frame sent by `init`
+    Handshake(Handshake),
+    /// A code address and its position in its frame, `0` for the first one; see the `address`
+    /// module
+    Address(u16, u32),
+    /// Bytes of the message of a binary log record; see the `record` module
+    RecordBytes(Chunk),
+    /// The 4 words that end a binary log record, or the description of a module, whose message
+    /// was reported as `RecordBytes`; see the `record` module
+    ///
+    /// If frames end with a CRC word this is only reported once the CRC word has been received and
+    /// matches; a corrupted record is reported as `BadCrc` instead.
+    Record([u32; 4]),
+    /// The exit status of the program; see the `exit` module
+    ///
+    /// If frames end with a CRC word this is only reported once the CRC word has been received and
+    /// matches; a corrupted exit status is reported as `BadCrc` instead.
+    Exit(i32),
+    /// The CRC word that ends a frame doesn't match the frame; the items decoded from the frame
+    /// are corrupted
+    BadCrc,
+    /// The given number of frames were lost before the frame that's being received; see the
+    /// `sequence` feature
+    Lost(u32),
+    /// A word that's not part of any known encoding
+    Unknown(u32),
+}
+
+/// Stateful decoder of the DCC word stream
+#[derive(Clone, Debug, Default)]
+pub struct Decoder {
+    // bytes left in the current packed text frame
+    packed: u32,
+    // bytes left in the current binary bytes frame
+    bytes: u32,
+    // bytes left in the current value frame
+    value: u32,
+    // channel of the current channel frame and the bytes left in it
+    channel: u8,
+    channel_bytes: u32,
+    // bytes left in the current compressed text frame
+    compressed: u32,
+    // words left in the current binary frame
+    words: u32,
+    // addresses in the current frame of addresses and how many are left
+    addresses_len: u32,
+    addresses: u32,
+    // bytes left in the message of the current record frame, the words that follow the message
+    // and how many of them are left, and whether the record is waiting for the CRC word of its
+    // frame
+    record_bytes: u32,
+    record: [u32; 4],
+    record_words: u32,
+    record_done: bool,
+    // whether the next payload word is an exit status, and the exit status waiting for the CRC
+    // word of its frame
+    exit: bool,
+    status: Option<i32>,
+    // words of the handshake frame received so far; `0` if not receiving a handshake frame
+    handshake: usize,
+    handshake_buf: [u32; 3],
+    // whether frames end with a CRC word, the CRC of the current frame and whether the next word
+    // is its CRC
+    crc: bool,
+    frame_crc: Crc32,
+    check: bool,
+    // whether frames carry a sequence number, whether the next word is one and the sequence
+    // number of the next frame; `None` until the first frame is received
+    sequence: bool,
+    sequence_next: bool,
+    expected: Option<u32>,
+}
+
+impl Decoder {
+    /// Creates a new decoder
+    pub const fn new() -> Self {
+        Decoder {
+            packed: 0,
+            bytes: 0,
+            value: 0,
+            channel: 0,
+            channel_bytes: 0,
+            compressed: 0,
+            words: 0,
+            addresses_len: 0,
+            addresses: 0,
+            record_bytes: 0,
+            record: [0; 4],
+            record_words: 0,
+            record_done: false,
+            exit: false,
+            status: None,
+            handshake: 0,
+            handshake_buf: [0; 3],
+            crc: false,
+            frame_crc: Crc32::new(),
+            check: false,
+            sequence: false,
+            sequence_next: false,
+            expected: None,
+        }
+    }
+
+    /// Creates a new decoder for a device that has the given `handshake::FEATURE_*` bits set
+    ///
+    /// Use this when the device doesn't send a handshake frame (see `init`) but has the `crc` or
+    /// `sequence` features enabled.
+    pub const fn with_features(features: u32) -> Self {
+        let mut decoder = Decoder::new();
+        decoder.crc = features & FEATURE_CRC != 0;
+        decoder.sequence = features & FEATURE_SEQUENCE != 0;
+        decoder
+    }
+
+    /// Feeds the next word received from the DCC into the decoder
+    ///
+    /// Returns `None` if the word was a header word
+    pub fn push(&mut self, word: u32) -> Option<Item> {
+        if self.handshake != 0 {
+            self.handshake_buf[self.handshake] = word;
+            self.handshake += 1;
+
+            if self.handshake != self.handshake_buf.len() {
+                return None;
+            }
+
+            self.handshake = 0;
+            let handshake = Handshake {
+                version: self.handshake_buf[0] as u16,
+                features: self.handshake_buf[1],
+                crate_version: self.handshake_buf[2],
+            };
+            self.crc = handshake.has(FEATURE_CRC);
+            self.sequence = handshake.has(FEATURE_SEQUENCE);
+            // NOTE the device has (re)started so its sequence numbers start over
+            self.expected = None;
+            return Some(Item::Handshake(handshake));
+        }
+
+        if self.check {
+            self.check = false;
+            let status = self.status.take();
+            let record = core::mem::replace(&mut self.record_done, false);
+            return if word == self.frame_crc.finish() {
+                if record {
+                    Some(Item::Record(self.record))
+                } else {
+                    status.map(Item::Exit)
+                }
+            } else {
+                Some(Item::BadCrc)
+            };
+        }
+
+        if self.sequence_next {
+            self.sequence_next = false;
+            // NOTE only one of these is non-zero, or only the last two
+            let left = self.words
+                + self.packed
+                + self.bytes
+                + self.value
+                + self.channel_bytes
+                + self.compressed
+                + self.addresses
+                + self.record_bytes
+                + self.record_words
+                + u32::from(self.exit);
+            self.payload(word, left);
+
+            return match self.expected.replace(word.wrapping_add(1)) {
+                // NOTE `0` means that the device restarted
+                Some(expected) if word != expected && word != 0 => {
+                    Some(Item::Lost(word.wrapping_sub(expected)))
+                }
+                _ => None,
+            };
+        }
+
+        if self.addresses != 0 {
+            self.addresses -= 1;
+            self.payload(word, self.addresses);
+            let index = self.addresses_len - self.addresses - 1;
+            return Some(Item::Address(index as u16, word));
+        }
+
+        if self.exit {
+            self.exit = false;
+            self.payload(word, 0);
+
+            // NOTE the program is done once its exit status is reported so the CRC word is checked
+            // first
+            if self.check {
+                self.status = Some(word as i32);
+                return None;
+            }
+            return Some(Item::Exit(word as i32));
+        }
+
+        if self.words != 0 {
+            self.words -= 1;
+            self.payload(word, self.words);
+            return Some(Item::Word(word));
+        }
+
+        if self.packed != 0 {
+            let chunk = unpack(&mut self.packed, word);
+            self.payload(word, self.packed);
+            return Some(Item::Text(chunk));
+        }
+
+        if self.bytes != 0 {
+            let chunk = unpack(&mut self.bytes, word);
+            self.payload(word, self.bytes);
+            return Some(Item::Bytes(chunk));
+        }
+
+        if self.value != 0 {
+            let chunk = unpack(&mut self.value, word);
+            self.payload(word, self.value);
+            return Some(Item::Value(chunk));
+        }
+
+        if self.channel_bytes != 0 {
+            let chunk = unpack(&mut self.channel_bytes, word);
+            self.payload(word, self.channel_bytes);
+            return Some(Item::Channel(self.channel, chunk));
+        }
+
+        if self.compressed != 0 {
+            let chunk = unpack(&mut self.compressed, word);
+            self.payload(word, self.compressed);
+            return Some(Item::Compressed(chunk));
+        }
+
+        if self.record_bytes != 0 {
+            let chunk = unpack(&mut self.record_bytes, word);
+            self.payload(word, self.record_bytes + self.record_words);
+            return Some(Item::RecordBytes(chunk));
+        }
+
+        if self.record_words != 0 {
+            self.record[self.record.len() - self.record_words as usize] = word;
+            self.record_words -= 1;
+            self.payload(word, self.record_words);
+
+            if self.record_words != 0 {
+                return None;
+            }
+
+            // NOTE as with the exit status the record is only complete once its CRC word checks
+            // out
+            if self.check {
+                self.record_done = true;
+                return None;
+            }
+            return Some(Item::Record(self.record));
+        }
+
+        if word < 0x100 {
+            Some(Item::Text(Chunk {
+                buf: [word as u8, 0, 0, 0],
+                len: 1,
+            }))
+        } else if word & TAG_MASK == WORDS_TAG {
+            self.words = word & !TAG_MASK;
+            self.header(word, self.words);
+            None
+        } else if word & TAG_MASK == PACKED_TAG {
+            self.packed = word & !TAG_MASK;
+            self.header(word, self.packed);
+            None
+        } else if word & TAG_MASK == BYTES_TAG {
+            self.bytes = word & !TAG_MASK;
+            self.header(word, self.bytes);
+            None
+        } else if word & TAG_MASK == VALUE_TAG {
+            self.value = word & !TAG_MASK;
+            self.header(word, self.value);
+            None
+        } else if word & TAG_MASK == CHANNEL_TAG {
+            self.channel = (word >> 11) as u8 & 0x1f;
+            self.channel_bytes = word & CHANNEL_MAX_LEN as u32;
+            self.header(word, self.channel_bytes);
+            None
+        } else if word & TAG_MASK == COMPRESSED_TAG {
+            self.compressed = word & !TAG_MASK;
+            self.header(word, self.compressed);
+            None
+        } else if word & TAG_MASK == ADDRESS_TAG {
+            self.addresses_len = word & !TAG_MASK;
+            self.addresses = self.addresses_len;
+            self.header(word, self.addresses);
+            None
+        } else if word & TAG_MASK == RECORD_TAG {
+            self.record_bytes = word & !TAG_MASK;
+            self.record_words = self.record.len() as u32;
+            self.header(word, self.record_bytes + self.record_words);
+            None
+        } else if word == EXIT_TAG {
+            self.exit = true;
+            self.header(word, 1);
+            None
+        } else if word & TAG_MASK == HANDSHAKE_TAG {
+            self.handshake_buf[0] = word & !TAG_MASK;
+            self.handshake = 1;
+            None
+        } else {
+            Some(Item::Unknown(word))
+        }
+    }
+
+    // starts the CRC of a frame; `left` is the length of the frame
+    fn header(&mut self, word: u32, left: u32) {
+        if self.crc {
+            self.frame_crc = Crc32::new();
+            self.frame_crc.update_word(word);
+        }
+
+        self.sequence_next = self.sequence;
+        self.check = self.crc && !self.sequence && left == 0;
+    }
+
+    // adds a word of the frame to its CRC; `left` is what's left of the frame after this word
+    fn payload(&mut self, word: u32, left: u32) {
+        if self.crc {
+            self.frame_crc.update_word(word);
+            self.check = left == 0;
+        }
+    }
+}
+
+// takes the next (up to) 4 bytes of a packed frame out of `word`
+fn unpack(left: &mut u32, word: u32) -> Chunk {
+    let len = if *left < 4 { *left } else { 4 };
+    *left -= len;
+
+    Chunk {
+        buf: [
+            word as u8,
+            (word >> 8) as u8,
+            (word >> 16) as u8,
+            (word >> 24) as u8,
+        ],
+        len: len as u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::{Chunk, Decoder, Item};
+    use crate::{
+        address::ADDRESS_TAG,
+        channel::{CHANNEL_MAX_LEN, CHANNEL_TAG},
+        compress::{Compressor, Decompressor, COMPRESSED_TAG},
+        crc::Crc32,
+        exit::EXIT_TAG,
+        handshake::{FEATURE_CRC, FEATURE_SEQUENCE},
+        PACKED_TAG, VALUE_TAG, WORDS_TAG,
+    };
+
+    // feeds `words` into `decoder` and checks what each one decodes to
+    fn check(decoder: &mut Decoder, words: &[u32], items: &[Option<Item>]) {
+        assert_eq!(words.len(), items.len());
+        for (word, item) in words.iter().zip(items) {
+            assert_eq!(decoder.push(*word), *item);
+        }
+    }
+
+    fn crc(words: &[u32]) -> u32 {
+        let mut crc = Crc32::new();
+        words.iter().for_each(|word| crc.update_word(*word));
+        crc.finish()
+    }
+
+    // a word-extended text byte
+    fn text(byte: u8) -> Chunk {
+        Chunk {
+            buf: [byte, 0, 0, 0],
+            len: 1,
+        }
+    }
+
+    // the words of a frame of bytes packed 4 per word, as sent by `write_packed_frame`
+    fn packed(header: u32, bytes: &[u8]) -> Vec<u32> {
+        let mut words = Vec::new();
+        words.push(header);
+        words.extend(bytes.chunks(4).map(crate::pack));
+        words
+    }
+
+    // the bytes of the chunks that `f` picks out of the items that `words` decode to
+    fn bytes(words: &[u32], f: impl Fn(Item) -> Option<Chunk>) -> Vec<u8> {
+        let mut decoder = Decoder::new();
+        let mut bytes = Vec::new();
+        for item in words.iter().filter_map(|word| decoder.push(*word)) {
+            let chunk = f(item).unwrap_or_else(|| panic!("unexpected item: {:?}", item));
+            bytes.extend_from_slice(chunk.as_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn packed_text() {
+        for text in [&b""[..], b"Hi", b"Hello", b"Hello, world!\n"].iter() {
+            let words = packed(PACKED_TAG | text.len() as u32, text);
+            let bytes = bytes(&words, |item| match item {
+                Item::Text(chunk) => Some(chunk),
+                _ => None,
+            });
+            assert_eq!(bytes, *text);
+        }
+    }
+
+    #[test]
+    fn channel() {
+        // NOTE what `Channel::write_all` sends for 0x7ff + 3 bytes: a full frame, then the rest
+        let data = (0..CHANNEL_MAX_LEN + 3)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let (first, rest) = data.split_at(CHANNEL_MAX_LEN);
+        let mut words = packed(CHANNEL_TAG | 31 << 11 | first.len() as u32, first);
+        words.extend(packed(CHANNEL_TAG | 31 << 11 | rest.len() as u32, rest));
+        words.extend(packed(CHANNEL_TAG | 5 << 11 | 2, b"ok"));
+
+        let mut decoder = Decoder::new();
+        let mut channels = [Vec::new(), Vec::new()];
+        for item in words.iter().filter_map(|word| decoder.push(*word)) {
+            match item {
+                Item::Channel(31, chunk) => channels[0].extend_from_slice(chunk.as_bytes()),
+                Item::Channel(5, chunk) => channels[1].extend_from_slice(chunk.as_bytes()),
+                _ => panic!("unexpected item: {:?}", item),
+            }
+        }
+        assert_eq!(channels[0], data);
+        assert_eq!(channels[1], b"ok");
+    }
+
+    #[test]
+    fn addresses() {
+        let mut decoder = Decoder::new();
+        check(
+            &mut decoder,
+            &[ADDRESS_TAG | 3, 0x0800_0101, 0x0800_0203, 0x0800_0305, 0x48],
+            &[
+                None,
+                Some(Item::Address(0, 0x0800_0101)),
+                Some(Item::Address(1, 0x0800_0203)),
+                Some(Item::Address(2, 0x0800_0305)),
+                Some(Item::Text(text(0x48))),
+            ],
+        );
+    }
+
+    #[test]
+    fn compressed() {
+        let text = b"INFO sensor: 100 mV\nINFO sensor: 101 mV\n";
+
+        let mut compressed = Vec::new();
+        let mut compressor = Compressor::new();
+        compressor.compress(text, |byte| compressed.push(byte));
+        compressor.flush_with(|byte| compressed.push(byte));
+
+        let words = packed(COMPRESSED_TAG | compressed.len() as u32, &compressed);
+        let bytes = bytes(&words, |item| match item {
+            Item::Compressed(chunk) => Some(chunk),
+            _ => None,
+        });
+        assert_eq!(bytes, compressed);
+
+        let mut decompressed = Vec::new();
+        Decompressor::new().push(&bytes, |byte| decompressed.push(byte));
+        assert_eq!(decompressed, &text[..]);
+    }
+
+    #[test]
+    fn value() {
+        let words = packed(VALUE_TAG | 5, &[0xe4, 0x19, 0xfe, 0x07, 0x07]);
+        let bytes = bytes(&words, |item| match item {
+            Item::Value(chunk) => Some(chunk),
+            _ => None,
+        });
+        assert_eq!(bytes, [0xe4, 0x19, 0xfe, 0x07, 0x07]);
+    }
+
+    #[test]
+    fn crc_match() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let frame = [WORDS_TAG | 2, 0x1111, 0x2222];
+        check(
+            &mut decoder,
+            &[frame[0], frame[1], frame[2], crc(&frame), 0x48],
+            &[
+                None,
+                Some(Item::Word(0x1111)),
+                Some(Item::Word(0x2222)),
+                None,
+                Some(Item::Text(text(0x48))),
+            ],
+        );
+    }
+
+    #[test]
+    fn crc_mismatch() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let frame = [WORDS_TAG | 2, 0x1111, 0x2222];
+        check(
+            &mut decoder,
+            &[frame[0], 0x1011, frame[2], crc(&frame), 0x48],
+            &[
+                None,
+                Some(Item::Word(0x1011)),
+                Some(Item::Word(0x2222)),
+                Some(Item::BadCrc),
+                Some(Item::Text(text(0x48))),
+            ],
+        );
+    }
+
+    #[test]
+    fn crc_mismatch_exit() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let frame = [EXIT_TAG, 1];
+        check(
+            &mut decoder,
+            &[frame[0], 3, crc(&frame)],
+            &[None, None, Some(Item::BadCrc)],
+        );
+        check(
+            &mut decoder,
+            &[frame[0], frame[1], crc(&frame)],
+            &[None, None, Some(Item::Exit(1))],
+        );
+    }
+
+    #[test]
+    fn crc_empty_frame() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let frame = [WORDS_TAG];
+        check(
+            &mut decoder,
+            &[frame[0], !crc(&frame)],
+            &[None, Some(Item::BadCrc)],
+        );
+    }
+
+    #[test]
+    fn sequence_gap() {
+        let mut decoder = Decoder::with_features(FEATURE_SEQUENCE);
+        check(
+            &mut decoder,
+            &[WORDS_TAG | 1, 7, 0x1111, WORDS_TAG | 1, 8, 0x2222],
+            &[
+                None,
+                None,
+                Some(Item::Word(0x1111)),
+                None,
+                None,
+                Some(Item::Word(0x2222)),
+            ],
+        );
+        check(
+            &mut decoder,
+            &[WORDS_TAG | 1, 11, 0x3333],
+            &[None, Some(Item::Lost(2)), Some(Item::Word(0x3333))],
+        );
+    }
+
+    #[test]
+    fn sequence_wraps_around() {
+        let mut decoder = Decoder::with_features(FEATURE_SEQUENCE);
+        check(
+            &mut decoder,
+            &[WORDS_TAG, u32::MAX, WORDS_TAG, 1],
+            &[None, None, None, Some(Item::Lost(1))],
+        );
+    }
+
+    #[test]
+    fn sequence_restart() {
+        let mut decoder = Decoder::with_features(FEATURE_SEQUENCE);
+        // NOTE the device restarted between the two frames
+        check(
+            &mut decoder,
+            &[WORDS_TAG, 5, WORDS_TAG, 0, WORDS_TAG, 1],
+            &[None, None, None, None, None, None],
+        );
+    }
+
+    #[test]
+    fn sequence_and_crc() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC | FEATURE_SEQUENCE);
+        let first = [WORDS_TAG | 1, 0, 0x1111];
+        let second = [WORDS_TAG | 1, 3, 0x2222];
+        check(
+            &mut decoder,
+            &[first[0], first[1], first[2], crc(&first)],
+            &[None, None, Some(Item::Word(0x1111)), None],
+        );
+        check(
+            &mut decoder,
+            &[second[0], second[1], second[2], crc(&second)],
+            &[None, Some(Item::Lost(2)), Some(Item::Word(0x2222)), None],
+        );
+    }
+
+    #[test]
+    fn resync_after_corrupted_tag() {
+        let mut decoder = Decoder::new();
+        // NOTE `PACKED_TAG | 2` with a flipped bit
+        check(
+            &mut decoder,
+            &[0xDC01_0002, 0x6968, WORDS_TAG | 1, 0x1111],
+            &[
+                Some(Item::Unknown(0xDC01_0002)),
+                Some(Item::Unknown(0x6968)),
+                None,
+                Some(Item::Word(0x1111)),
+            ],
+        );
+    }
+
+    #[test]
+    fn resync_after_corrupted_length() {
+        let mut decoder = Decoder::with_features(FEATURE_CRC);
+        let packed = [PACKED_TAG | 2, 0x6968];
+        let first = [WORDS_TAG | 1, 0x1111];
+        let second = [WORDS_TAG | 1, 0x2222];
+
+        // NOTE the length in the header of `packed` is corrupted, 6 instead of 2, so its frame
+        // swallows its CRC word and the decoder takes the header of `first` as its CRC word
+        let mut words = [0; 9];
+        words[..3].copy_from_slice(&[PACKED_TAG | 6, packed[1], crc(&packed)]);
+        words[3..6].copy_from_slice(&[first[0], first[1], crc(&first)]);
+        words[6..].copy_from_slice(&[second[0], second[1], crc(&second)]);
+
+        let items = words.map(|word| decoder.push(word));
+        assert_eq!(items[3], Some(Item::BadCrc));
+        assert!(items[4..6]
+            .iter()
+            .all(|item| matches!(item, Some(Item::Unknown(_)))));
+        assert_eq!(items[6..], [None, Some(Item::Word(0x2222)), None]);
+        assert_eq!(decoder.push(0x48), Some(Item::Text(text(0x48))));
+    }
+}
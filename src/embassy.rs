@@ -0,0 +1,200 @@
+//! Interrupt driven async driver, for embassy-executor and other async executors
+//!
+//! `AsyncDcc` parks the task that's waiting for the DCC until the COMMTX (DBGDTRTX is empty) or
+//! COMMRX (DBGDTRRX is full) interrupt fires; the interrupt handlers call `on_commtx` and
+//! `on_commrx`, which wake the task. Both interrupts are level-sensitive so the driver unmasks
+//! them, through the functions passed to `AsyncDcc::new`, only while a task is waiting, and the
+//! handlers must mask them again.
+//!
+//! ``` ignore
+//! use arm_dcc::embassy::{self, AsyncDcc};
+//!
+//! fn unmask_commtx() {
+//!     // unmask COMMTX in the interrupt controller (e.g. the GIC or the VIM)
+//! }
+//!
+//! fn unmask_commrx() {
+//!     // unmask COMMRX in the interrupt controller
+//! }
+//!
+//! #[embassy_executor::task]
+//! async fn console() {
+//!     let mut dcc = AsyncDcc::new(unmask_commtx, unmask_commrx);
+//!
+//!     loop {
+//!         let mut command = [0; 1];
+//!         dcc.read_exact(&mut command).await;
+//!         dcc.write(b"ok\n").await;
+//!     }
+//! }
+//!
+//! // in the COMMTX interrupt handler
+//! embassy::on_commtx();
+//! // then mask COMMTX in the interrupt controller
+//!
+//! // in the COMMRX interrupt handler
+//! embassy::on_commrx();
+//! // then mask COMMRX in the interrupt controller
+//! ```
+//!
+//! The driver doesn't go through `write`, so the `Policy`, the transmit FIFO (see the `interrupt`
+//! module) and the `stats` feature, other than its `words_written` counter, don't apply to it.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::{frame::Framer, PACKED_MAX_LEN, PACKED_TAG};
+
+static TX_WAKER: AtomicWaker = AtomicWaker::new();
+static RX_WAKER: AtomicWaker = AtomicWaker::new();
+
+/// Wakes the task waiting to write to the DCC
+///
+/// Call this from the interrupt handler of the COMMTX interrupt and then mask COMMTX; it stays
+/// asserted while DBGDTRTX is empty.
+pub fn on_commtx() {
+    TX_WAKER.wake()
+}
+
+/// Wakes the task waiting to read from the DCC
+///
+/// Call this from the interrupt handler of the COMMRX interrupt and then mask COMMRX; it stays
+/// asserted until the task reads DBGDTRRX.
+pub fn on_commrx() {
+    RX_WAKER.wake()
+}
+
+/// Async DCC driver whose futures are woken by the COMMTX and COMMRX interrupts
+///
+/// **NOTE:** Only one task may use the transmit side, and one task the receive side, at a time.
+pub struct AsyncDcc {
+    unmask_commtx: fn(),
+    unmask_commrx: fn(),
+}
+
+impl AsyncDcc {
+    /// Creates the driver; the functions unmask the COMMTX and COMMRX interrupts in the interrupt
+    /// controller
+    pub const fn new(unmask_commtx: fn(), unmask_commrx: fn()) -> Self {
+        AsyncDcc {
+            unmask_commtx,
+            unmask_commrx,
+        }
+    }
+
+    /// Writes a single word, waiting for the COMMTX interrupt while the transmit register is full
+    pub fn write_word(&mut self, word: u32) -> WriteWord {
+        WriteWord {
+            word,
+            unmask: self.unmask_commtx,
+        }
+    }
+
+    /// Writes the words
+    pub async fn write_words(&mut self, words: &[u32]) {
+        for word in words {
+            self.write_word(*word).await
+        }
+    }
+
+    /// Writes the bytes, one per word or, with the `packed` feature, in the format of
+    /// `write_all_packed`
+    pub async fn write(&mut self, bytes: &[u8]) {
+        if cfg!(feature = "packed") {
+            for frame in bytes.chunks(PACKED_MAX_LEN) {
+                let (mut framer, prefix) = Framer::new(PACKED_TAG | frame.len() as u32);
+                for word in prefix {
+                    self.write_word(word).await
+                }
+
+                for chunk in frame.chunks(4) {
+                    self.write_word(framer.payload(crate::pack(chunk))).await
+                }
+
+                if let Some(word) = framer.finish() {
+                    self.write_word(word).await
+                }
+            }
+        } else {
+            for byte in bytes {
+                self.write_word(u32::from(*byte)).await
+            }
+        }
+    }
+
+    /// Reads a single word, waiting for the COMMRX interrupt until the host sends one
+    pub fn read_word(&mut self) -> ReadWord {
+        ReadWord {
+            unmask: self.unmask_commrx,
+        }
+    }
+
+    /// Fills `buf` with bytes sent by the host, one per word or, with the `packed` feature, 4 per
+    /// word; see `read_exact`
+    pub async fn read_exact(&mut self, buf: &mut [u8]) {
+        let per_word = if cfg!(feature = "packed") { 4 } else { 1 };
+
+        for chunk in buf.chunks_mut(per_word) {
+            let word = self.read_word().await;
+
+            for (i, byte) in chunk.iter_mut().enumerate() {
+                *byte = (word >> (8 * i)) as u8;
+            }
+        }
+    }
+}
+
+/// Future returned by `AsyncDcc::write_word`
+pub struct WriteWord {
+    word: u32,
+    unmask: fn(),
+}
+
+impl Future for WriteWord {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if crate::try_write_dtr(self.word).is_ok() {
+            return Poll::Ready(());
+        }
+
+        TX_WAKER.register(cx.waker());
+        (self.unmask)();
+
+        // NOTE the host may have drained the register before the waker was registered
+        if crate::try_write_dtr(self.word).is_ok() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Future returned by `AsyncDcc::read_word`
+pub struct ReadWord {
+    unmask: fn(),
+}
+
+impl Future for ReadWord {
+    type Output = u32;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<u32> {
+        if let Some(word) = crate::try_read() {
+            return Poll::Ready(word);
+        }
+
+        RX_WAKER.register(cx.waker());
+        (self.unmask)();
+
+        // NOTE the host may have sent a word before the waker was registered
+        match crate::try_read() {
+            Some(word) => Poll::Ready(word),
+            None => Poll::Pending,
+        }
+    }
+}
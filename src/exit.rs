@@ -0,0 +1,60 @@
+//! Exit status of the program
+//!
+//! `exit` tells the host that the program has finished, and with what status, so a test runner
+//! like the `dcc-run` tool can stop the core and exit with the same status; `cargo run` and
+//! `cargo test` then work as they do with programs that run on the host.
+//!
+//! The status is sent as a frame of its own: an `EXIT_TAG` header word followed by the status,
+//! with the sequence number and CRC of the framed encodings (see the `crc` and `sequence`
+//! features). `decode::Decoder` reports it as `decode::Item::Exit`.
+//!
+//! ``` no_run
+//! fn main() -> ! {
+//!     let passed = 2 + 2 == 4;
+//!
+//!     arm_dcc::dprintln!("test result: {}", if passed { "ok" } else { "FAILED" });
+//!     arm_dcc::exit::exit(if passed { 0 } else { 1 })
+//! }
+//! ```
+
+use crate::frame::FrameWriter;
+
+/// Header word of the exit status frame
+pub const EXIT_TAG: u32 = 0xDCC6_0000;
+
+/// Sends the exit status to the host without stopping the program
+///
+/// **NOTE:** This operation is blocking
+pub fn report(status: i32) {
+    let mut frame = FrameWriter::new(EXIT_TAG);
+    frame.write(status as u32);
+    frame.finish()
+}
+
+/// Sends the exit status to the host and then spins forever, waiting for the host to stop the core
+///
+/// On the host stub (see the `host` module) the process exits with `status` instead.
+///
+/// **NOTE:** This operation is blocking
+pub fn exit(status: i32) -> ! {
+    report(status);
+    end(status)
+}
+
+#[cfg(not(all(
+    not(any(target_arch = "arm", target_arch = "aarch64")),
+    feature = "std"
+)))]
+fn end(_: i32) -> ! {
+    loop {
+        core::hint::spin_loop()
+    }
+}
+
+#[cfg(all(
+    not(any(target_arch = "arm", target_arch = "aarch64")),
+    feature = "std"
+))]
+fn end(status: i32) -> ! {
+    std::process::exit(status)
+}
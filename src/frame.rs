@@ -0,0 +1,110 @@
+//! Framing shared by the framed encodings
+
+use core::{iter, option};
+
+use crate::{
+    atomic::{AtomicU32, Ordering},
+    crc::Crc32,
+};
+
+// sequence number of the next frame
+static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+
+/// Returns the sequence number of a new frame
+fn next_sequence() -> u32 {
+    SEQUENCE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The words that precede the payload of a frame
+pub(crate) type Prefix = iter::Chain<iter::Once<u32>, option::IntoIter<u32>>;
+
+/// Layout of a frame: the header; then, if the `sequence` feature is enabled, the sequence
+/// number; then the payload; and last, if the `crc` feature is enabled, the CRC
+///
+/// This only produces the words; `FrameWriter` and `embassy::AsyncDcc` send them.
+pub(crate) struct Framer {
+    crc: Crc32,
+}
+
+impl Framer {
+    /// Starts a new frame; returns it along with the words that precede its payload
+    pub(crate) fn new(header: u32) -> (Self, Prefix) {
+        let mut framer = Framer { crc: Crc32::new() };
+        framer.payload(header);
+
+        let sequence = if cfg!(feature = "sequence") {
+            Some(framer.payload(next_sequence()))
+        } else {
+            None
+        };
+
+        (framer, iter::once(header).chain(sequence))
+    }
+
+    /// Adds a word of the payload to the frame; returns the word
+    pub(crate) fn payload(&mut self, word: u32) -> u32 {
+        if cfg!(feature = "crc") {
+            self.crc.update_word(word);
+        }
+
+        word
+    }
+
+    /// Ends the frame; returns the word that follows the payload, if any
+    pub(crate) fn finish(&self) -> Option<u32> {
+        if cfg!(feature = "crc") {
+            Some(self.crc.finish())
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes a frame, laid out by `Framer`, word by word
+pub(crate) struct FrameWriter {
+    framer: Framer,
+    // whether the words of the frame go straight to the transmit register; see `crate::write`
+    direct: bool,
+}
+
+impl FrameWriter {
+    /// Writes the header word, and the sequence number, of a new frame
+    pub(crate) fn new(header: u32) -> Self {
+        let (framer, prefix) = Framer::new(header);
+        let frame = FrameWriter {
+            framer,
+            direct: crate::is_direct(),
+        };
+        prefix.for_each(|word| frame.send(word, 0));
+
+        frame
+    }
+
+    /// Writes a word of the payload
+    pub(crate) fn write(&mut self, word: u32) {
+        self.write_payload(word, 4)
+    }
+
+    /// Writes a word of the payload that carries `bytes` bytes, e.g. the last word of a packed
+    /// frame; see `Stats`
+    pub(crate) fn write_payload(&mut self, word: u32, bytes: u32) {
+        let word = self.framer.payload(word);
+        self.send(word, bytes)
+    }
+
+    /// Ends the frame
+    pub(crate) fn finish(self) {
+        if let Some(word) = self.framer.finish() {
+            self.send(word, 0)
+        }
+    }
+
+    // NOTE the policy is checked once per frame rather than once per word
+    fn send(&self, word: u32, bytes: u32) {
+        if self.direct {
+            crate::backend::write(word)
+        } else {
+            crate::write_payload(word, bytes)
+        }
+    }
+}
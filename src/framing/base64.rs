@@ -0,0 +1,271 @@
+//! Base64 encoding of binary payloads as text
+//!
+//! `Encoder` prints a payload as lines of Base64 (the standard alphabet, with padding) through the
+//! text API, so it ends up in a plain text DCC log, e.g. one captured with `readjtaguart`, from
+//! which it can be copied and decoded offline; `decode` skips the line breaks.
+//!
+//! ```
+//! use arm_dcc::framing::base64;
+//!
+//! let mut encoded = [0; base64::encoded_len(5)];
+//! let n = base64::encode(b"hello", &mut encoded);
+//! assert_eq!(&encoded[..n], b"aGVsbG8=");
+//!
+//! let mut decoded = [0; 5];
+//! let m = base64::decode(b"aGVs\nbG8=\n", &mut decoded).unwrap();
+//! assert_eq!(&decoded[..m], b"hello");
+//! ```
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Number of Base64 characters in each line printed by `Encoder`
+pub const LINE_LEN: usize = 76;
+
+/// Error returned by `decode`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The input contains a character that's not part of the Base64 alphabet
+    Character(u8),
+    /// The input ends in the middle of a group of 4 characters
+    Truncated,
+    /// The decoded payload doesn't fit in the output buffer
+    Overflow,
+}
+
+/// Size of the encoding of `len` bytes
+pub const fn encoded_len(len: usize) -> usize {
+    // NOTE the last group is padded to 4 characters
+    let padded = match len % 3 {
+        0 => 0,
+        _ => 4,
+    };
+    len / 3 * 4 + padded
+}
+
+// encodes up to 3 bytes into a group of 4 characters
+fn encode_group(bytes: &[u8]) -> [u8; 4] {
+    let b = [
+        bytes[0],
+        bytes.get(1).cloned().unwrap_or(0),
+        bytes.get(2).cloned().unwrap_or(0),
+    ];
+    let mut group = [
+        ALPHABET[usize::from(b[0] >> 2)],
+        ALPHABET[usize::from((b[0] & 0x03) << 4 | b[1] >> 4)],
+        ALPHABET[usize::from((b[1] & 0x0f) << 2 | b[2] >> 6)],
+        ALPHABET[usize::from(b[2] & 0x3f)],
+    ];
+
+    if bytes.len() < 3 {
+        group[3] = b'=';
+    }
+    if bytes.len() < 2 {
+        group[2] = b'=';
+    }
+    group
+}
+
+/// Encodes `bytes` into `output` and returns the length of the encoding
+///
+/// # Panics
+///
+/// If `output` is shorter than `encoded_len(bytes.len())`
+pub fn encode(bytes: &[u8], output: &mut [u8]) -> usize {
+    assert!(output.len() >= encoded_len(bytes.len()));
+
+    let mut n = 0;
+    for chunk in bytes.chunks(3) {
+        output[n..n + 4].copy_from_slice(&encode_group(chunk));
+        n += 4;
+    }
+    n
+}
+
+/// Decodes the Base64 `input` into `output` and returns the length of the payload
+///
+/// ASCII whitespace, e.g. line breaks, is skipped.
+pub fn decode(input: &[u8], output: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut group = [0; 4];
+    let mut len = 0;
+    let mut n = 0;
+    for c in input.iter().filter(|c| !c.is_ascii_whitespace()) {
+        group[len] = *c;
+        len += 1;
+
+        if len == group.len() {
+            len = 0;
+
+            let mut sextets = [0; 4];
+            let mut bytes = 3;
+            for (i, c) in group.iter().enumerate() {
+                sextets[i] = match *c {
+                    b'=' if i >= 2 => {
+                        bytes = bytes.min(i - 1);
+                        0
+                    }
+                    _ if bytes != 3 => return Err(DecodeError::Character(*c)),
+                    _ => ALPHABET
+                        .iter()
+                        .position(|a| a == c)
+                        .ok_or(DecodeError::Character(*c))? as u8,
+                };
+            }
+
+            let decoded = [
+                sextets[0] << 2 | sextets[1] >> 4,
+                sextets[1] << 4 | sextets[2] >> 2,
+                sextets[2] << 6 | sextets[3],
+            ];
+            output
+                .get_mut(n..n + bytes)
+                .ok_or(DecodeError::Overflow)?
+                .copy_from_slice(&decoded[..bytes]);
+            n += bytes;
+        }
+    }
+
+    if len != 0 {
+        return Err(DecodeError::Truncated);
+    }
+
+    Ok(n)
+}
+
+/// Prints a payload, fed in pieces, as lines of Base64 through the text API (see `write_str`)
+///
+/// Each line is printed with a single `write_str` call.
+///
+/// ``` no_run
+/// use arm_dcc::framing::base64::Encoder;
+///
+/// let mut encoder = Encoder::new();
+/// encoder.write(&[0xde, 0xad]);
+/// encoder.write(&[0xbe, 0xef]);
+/// // prints "3q2+7w==" and a line feed
+/// encoder.finish();
+/// ```
+#[derive(Clone, Debug)]
+pub struct Encoder {
+    // bytes that don't form a group of 3 yet
+    pending: [u8; 3],
+    pending_len: usize,
+    // the current line, followed by room for its line feed
+    line: [u8; LINE_LEN + 1],
+    line_len: usize,
+}
+
+impl Encoder {
+    /// Creates a new encoder
+    pub const fn new() -> Self {
+        Encoder {
+            pending: [0; 3],
+            pending_len: 0,
+            line: [0; LINE_LEN + 1],
+            line_len: 0,
+        }
+    }
+
+    /// Feeds the next bytes of the payload
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.pending[self.pending_len] = *byte;
+            self.pending_len += 1;
+
+            if self.pending_len == self.pending.len() {
+                self.encode_pending();
+            }
+        }
+    }
+
+    /// Prints the rest of the payload, and the padding, and ends the last line
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn finish(mut self) {
+        self.encode_pending();
+
+        if self.line_len != 0 {
+            self.print_line();
+        }
+    }
+
+    fn encode_pending(&mut self) {
+        if self.pending_len == 0 {
+            return;
+        }
+
+        let group = encode_group(&self.pending[..self.pending_len]);
+        self.line[self.line_len..self.line_len + 4].copy_from_slice(&group);
+        self.line_len += 4;
+        self.pending_len = 0;
+
+        if self.line_len == LINE_LEN {
+            self.print_line();
+        }
+    }
+
+    fn print_line(&mut self) {
+        self.line[self.line_len] = b'\n';
+        crate::sink::write_text(&self.line[..self.line_len + 1]);
+        self.line_len = 0;
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, encoded_len, DecodeError};
+
+    #[test]
+    fn padding() {
+        let mut encoded = [0; encoded_len(4)];
+        let n = encode(b"hell", &mut encoded);
+        assert_eq!(&encoded[..n], b"aGVsbA==");
+
+        let mut decoded = [0; 4];
+        assert_eq!(decode(&encoded[..n], &mut decoded), Ok(4));
+        assert_eq!(&decoded, b"hell");
+    }
+
+    #[test]
+    fn character() {
+        let mut decoded = [0; 6];
+        assert_eq!(
+            decode(b"aGVs*G8=", &mut decoded),
+            Err(DecodeError::Character(b'*'))
+        );
+        // NOTE padding only goes at the end of a group
+        assert_eq!(
+            decode(b"a=Vs", &mut decoded),
+            Err(DecodeError::Character(b'='))
+        );
+        assert_eq!(
+            decode(b"aG=s", &mut decoded),
+            Err(DecodeError::Character(b's'))
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let mut decoded = [0; 6];
+        assert_eq!(
+            decode(b"aGVsbG8\n", &mut decoded),
+            Err(DecodeError::Truncated)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut decoded = [0; 4];
+        assert_eq!(
+            decode(b"aGVsbG8=", &mut decoded),
+            Err(DecodeError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Consistent Overhead Byte Stuffing (COBS)
+//!
+//! The payload is split into blocks of up to 254 non-zero bytes; each block is preceded by a code
+//! byte, the number of bytes in the block plus one. A code below `0xff` also stands for a zero
+//! byte that follows the block, except after the last block. The encoded payload contains no zero
+//! bytes so `write_frame` terminates it with one, which the host uses to delimit frames.
+//!
+//! ```
+//! use arm_dcc::framing::cobs;
+//!
+//! let payload = [0x11, 0x00, 0x22, 0x33];
+//!
+//! let mut encoded = [0; cobs::max_encoded_len(4)];
+//! let n = cobs::encode(&payload, &mut encoded);
+//! assert_eq!(&encoded[..n], &[0x02, 0x11, 0x03, 0x22, 0x33]);
+//!
+//! let mut decoded = [0; 4];
+//! let m = cobs::decode(&encoded[..n], &mut decoded).unwrap();
+//! assert_eq!(&decoded[..m], &payload);
+//! ```
+
+/// Longest block of non-zero bytes, the block whose code is `0xff`
+const MAX_BLOCK: usize = 254;
+
+/// Error returned by `decode`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The input contains a zero byte, which never appears in encoded data
+    Zero,
+    /// The input ends in the middle of a block
+    Truncated,
+    /// The decoded payload doesn't fit in the output buffer
+    Overflow,
+}
+
+/// Maximum size of the encoding of `len` bytes, not including the terminating zero byte
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / MAX_BLOCK + 1
+}
+
+// calls `f` with the code and the bytes of each block of the encoding of `bytes`
+fn blocks(mut bytes: &[u8], mut f: impl FnMut(u8, &[u8])) {
+    loop {
+        let window = &bytes[..bytes.len().min(MAX_BLOCK)];
+
+        match window.iter().position(|byte| *byte == 0) {
+            Some(zero) => {
+                f(zero as u8 + 1, &bytes[..zero]);
+                bytes = &bytes[zero + 1..];
+            }
+            None => {
+                f(window.len() as u8 + 1, window);
+                bytes = &bytes[window.len()..];
+
+                // NOTE a full block makes no claim about what follows so one more (maybe empty)
+                // block is needed
+                if window.len() != MAX_BLOCK {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Encodes `bytes` into `output` and returns the length of the encoding
+///
+/// The terminating zero byte is not included.
+///
+/// # Panics
+///
+/// If `output` is shorter than `max_encoded_len(bytes.len())`
+pub fn encode(bytes: &[u8], output: &mut [u8]) -> usize {
+    assert!(output.len() >= max_encoded_len(bytes.len()));
+
+    let mut n = 0;
+    blocks(bytes, |code, block| {
+        output[n] = code;
+        output[n + 1..n + 1 + block.len()].copy_from_slice(block);
+        n += 1 + block.len();
+    });
+    n
+}
+
+/// Decodes the COBS encoded `input` into `output` and returns the length of the payload
+///
+/// `input` must not include the terminating zero byte.
+pub fn decode(input: &[u8], output: &mut [u8]) -> Result<usize, DecodeError> {
+    let mut i = 0;
+    let mut n = 0;
+    while i < input.len() {
+        let code = usize::from(input[i]);
+        if code == 0 {
+            return Err(DecodeError::Zero);
+        }
+
+        let block = input.get(i + 1..i + code).ok_or(DecodeError::Truncated)?;
+        if block.contains(&0) {
+            return Err(DecodeError::Zero);
+        }
+        output
+            .get_mut(n..n + block.len())
+            .ok_or(DecodeError::Overflow)?
+            .copy_from_slice(block);
+        n += block.len();
+        i += code;
+
+        if code != MAX_BLOCK + 1 && i != input.len() {
+            *output.get_mut(n).ok_or(DecodeError::Overflow)? = 0;
+            n += 1;
+        }
+    }
+
+    Ok(n)
+}
+
+/// Sends `bytes` COBS encoded, followed by a zero byte, over the DCC using `write_all`
+///
+/// Only one block, up to 255 bytes, is buffered at a time.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_frame(bytes: &[u8]) {
+    let mut buffer = [0; MAX_BLOCK + 1];
+    blocks(bytes, |code, block| {
+        buffer[0] = code;
+        buffer[1..1 + block.len()].copy_from_slice(block);
+        crate::write_all(&buffer[..1 + block.len()]);
+    });
+    crate::write_all(&[0]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, max_encoded_len, DecodeError, MAX_BLOCK};
+
+    #[test]
+    fn long_block() {
+        let payload = [0x11; MAX_BLOCK + 1];
+
+        let mut encoded = [0; max_encoded_len(MAX_BLOCK + 1)];
+        let n = encode(&payload, &mut encoded);
+        assert_eq!(n, MAX_BLOCK + 3);
+        assert_eq!(encoded[0], 0xff);
+        assert_eq!(encoded[MAX_BLOCK + 1], 0x02);
+
+        let mut decoded = [0; MAX_BLOCK + 1];
+        assert_eq!(decode(&encoded[..n], &mut decoded), Ok(MAX_BLOCK + 1));
+        assert_eq!(&decoded[..], &payload[..]);
+    }
+
+    #[test]
+    fn zero() {
+        let mut decoded = [0; 4];
+        assert_eq!(decode(&[0x00, 0x11], &mut decoded), Err(DecodeError::Zero));
+        assert_eq!(
+            decode(&[0x03, 0x11, 0x00], &mut decoded),
+            Err(DecodeError::Zero)
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let mut decoded = [0; 4];
+        assert_eq!(
+            decode(&[0x04, 0x11, 0x22], &mut decoded),
+            Err(DecodeError::Truncated)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let mut decoded = [0; 2];
+        assert_eq!(
+            decode(&[0x04, 0x11, 0x22, 0x33], &mut decoded),
+            Err(DecodeError::Overflow)
+        );
+        // the implicit zero byte doesn't fit either
+        assert_eq!(
+            decode(&[0x03, 0x11, 0x22, 0x01], &mut decoded),
+            Err(DecodeError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Byte stuffing of a configurable set of reserved bytes
+//!
+//! Some capture paths mangle certain bytes, e.g. tools that treat the output of `readjtaguart` as
+//! text and rewrite line endings or stop at a NUL byte. An `Escaper` replaces each reserved byte,
+//! and the escape byte itself, with the escape byte followed by the byte XOR `0x20`, as PPP does,
+//! so the encoded payload contains no reserved bytes. With the line feed reserved `write_line`
+//! sends each payload as a single line of the DCC log.
+//!
+//! ```
+//! use arm_dcc::framing::escape::{self, Escaper};
+//!
+//! let escaper = Escaper::default();
+//! let payload = [0x11, 0x00, b'\n', 0x7d];
+//!
+//! let mut encoded = [0; escape::max_encoded_len(4)];
+//! let n = escaper.encode(&payload, &mut encoded);
+//! assert_eq!(&encoded[..n], &[0x11, 0x7d, 0x20, 0x7d, 0x2a, 0x7d, 0x5d]);
+//!
+//! let mut decoded = [0; 4];
+//! let m = escaper.decode(&encoded[..n], &mut decoded).unwrap();
+//! assert_eq!(&decoded[..m], &payload);
+//! ```
+
+/// Bytes reserved by `Escaper::default`: NUL, line feed and carriage return
+pub const DEFAULT_RESERVED: &[u8] = &[0x00, b'\n', b'\r'];
+
+/// Escape byte used by `Escaper::default`, `}` as in PPP
+pub const DEFAULT_ESCAPE: u8 = 0x7d;
+
+/// Value XOR-ed into escaped bytes
+const FLIP: u8 = 0x20;
+
+/// Error returned by `Escaper::decode`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The input contains a reserved byte, which never appears in encoded data
+    Reserved,
+    /// The input ends with the escape byte
+    Truncated,
+    /// The decoded payload doesn't fit in the output buffer
+    Overflow,
+}
+
+/// Maximum size of the encoding of `len` bytes
+pub const fn max_encoded_len(len: usize) -> usize {
+    2 * len
+}
+
+/// Byte stuffing codec
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Escaper {
+    escape: u8,
+    reserved: &'static [u8],
+}
+
+impl Escaper {
+    /// Creates a codec that escapes the `reserved` bytes with the `escape` byte
+    ///
+    /// # Panics
+    ///
+    /// If the encoding would contain a reserved byte: `escape`, or any reserved byte XOR `0x20`,
+    /// is itself reserved
+    pub const fn new(escape: u8, reserved: &'static [u8]) -> Self {
+        assert!(!contains(reserved, escape), "the escape byte is reserved");
+        assert!(
+            !contains(reserved, escape ^ FLIP),
+            "an escaped byte is reserved"
+        );
+
+        let mut i = 0;
+        while i < reserved.len() {
+            assert!(
+                !contains(reserved, reserved[i] ^ FLIP),
+                "an escaped byte is reserved"
+            );
+            i += 1;
+        }
+
+        Escaper { escape, reserved }
+    }
+
+    /// Returns `true` if `byte` must be escaped
+    pub fn needs_escape(&self, byte: u8) -> bool {
+        byte == self.escape || self.reserved.contains(&byte)
+    }
+
+    /// Encodes `bytes` into `output` and returns the length of the encoding
+    ///
+    /// # Panics
+    ///
+    /// If `output` is too short; `max_encoded_len(bytes.len())` bytes are always enough
+    pub fn encode(&self, bytes: &[u8], output: &mut [u8]) -> usize {
+        let mut n = 0;
+        for byte in bytes {
+            if self.needs_escape(*byte) {
+                output[n] = self.escape;
+                output[n + 1] = byte ^ FLIP;
+                n += 2;
+            } else {
+                output[n] = *byte;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// Decodes the escaped `input` into `output` and returns the length of the payload
+    pub fn decode(&self, input: &[u8], output: &mut [u8]) -> Result<usize, DecodeError> {
+        let mut bytes = input.iter();
+        let mut n = 0;
+        while let Some(byte) = bytes.next() {
+            if self.reserved.contains(byte) {
+                return Err(DecodeError::Reserved);
+            }
+
+            let byte = if *byte == self.escape {
+                let escaped = bytes.next().ok_or(DecodeError::Truncated)?;
+                if self.reserved.contains(escaped) {
+                    return Err(DecodeError::Reserved);
+                }
+
+                escaped ^ FLIP
+            } else {
+                *byte
+            };
+
+            *output.get_mut(n).ok_or(DecodeError::Overflow)? = byte;
+            n += 1;
+        }
+
+        Ok(n)
+    }
+
+    /// Sends `bytes` escaped over the DCC using `write_all`
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn write_all(&self, bytes: &[u8]) {
+        let mut buffer = [0; max_encoded_len(32)];
+        for chunk in bytes.chunks(32) {
+            let n = self.encode(chunk, &mut buffer);
+            crate::write_all(&buffer[..n]);
+        }
+    }
+
+    /// Sends `bytes` escaped, followed by a line feed, over the DCC using `write_all`
+    ///
+    /// If the line feed is reserved the payload ends up on a line of its own of the DCC log.
+    ///
+    /// **NOTE:** This operation is blocking
+    pub fn write_line(&self, bytes: &[u8]) {
+        self.write_all(bytes);
+        crate::write_all(b"\n");
+    }
+}
+
+/// Escapes `DEFAULT_RESERVED` with `DEFAULT_ESCAPE`
+impl Default for Escaper {
+    fn default() -> Self {
+        Escaper::new(DEFAULT_ESCAPE, DEFAULT_RESERVED)
+    }
+}
+
+const fn contains(bytes: &[u8], byte: u8) -> bool {
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == byte {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_encoded_len, DecodeError, Escaper};
+
+    #[test]
+    fn reserved() {
+        let escaper = Escaper::default();
+        let mut decoded = [0; 4];
+        assert_eq!(
+            escaper.decode(&[0x11, b'\n'], &mut decoded),
+            Err(DecodeError::Reserved)
+        );
+        // NOTE an escaped byte is never reserved either
+        assert_eq!(
+            escaper.decode(&[0x7d, 0x00], &mut decoded),
+            Err(DecodeError::Reserved)
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let escaper = Escaper::default();
+        let mut decoded = [0; 4];
+        assert_eq!(
+            escaper.decode(&[0x11, 0x7d], &mut decoded),
+            Err(DecodeError::Truncated)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let escaper = Escaper::default();
+        let mut decoded = [0; 2];
+        assert_eq!(
+            escaper.decode(&[0x11, 0x7d, 0x20, 0x22], &mut decoded),
+            Err(DecodeError::Overflow)
+        );
+    }
+
+    #[test]
+    fn custom_reserved() {
+        let escaper = Escaper::new(b'\\', b"\"");
+        let payload = *b"say \"hi\" \\o/";
+
+        let mut encoded = [0; max_encoded_len(12)];
+        let n = escaper.encode(&payload, &mut encoded);
+        assert!(!encoded[..n].contains(&b'"'));
+
+        let mut decoded = [0; 12];
+        assert_eq!(escaper.decode(&encoded[..n], &mut decoded), Ok(12));
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    #[should_panic(expected = "the escape byte is reserved")]
+    fn escape_reserved() {
+        Escaper::new(b'\n', b"\n");
+    }
+}
@@ -0,0 +1,12 @@
+//! Framing of binary payloads in the DCC byte stream
+//!
+//! - `base64`: Base64 encoding, which prints a payload as lines of text that can be copied out of
+//!   a plain text DCC log
+//! - `cobs`: Consistent Overhead Byte Stuffing, which encodes a payload without zero bytes and
+//!   terminates it with one, so payloads can be told apart in a plain byte stream
+//! - `escape`: byte stuffing of a configurable set of reserved bytes, so payloads survive capture
+//!   paths that mangle some bytes, e.g. text oriented ones
+
+pub mod base64;
+pub mod cobs;
+pub mod escape;
@@ -0,0 +1,85 @@
+//! Protocol handshake
+//!
+//! `init` sends a handshake frame that lets host tooling detect how the device encodes its
+//! output, instead of guessing. The frame is three words long:
+//!
+//! - `HANDSHAKE_TAG | PROTOCOL_VERSION`
+//! - the `FEATURE_*` bitmask of the encodings enabled in the device
+//! - the version of this crate, encoded as `major << 16 | minor << 8 | patch`
+//!
+//! `decode::Decoder` reports this frame as `decode::Item::Handshake`.
+//!
+//! With `init_and_wait` the device first waits for the host to send the `HOST_READY` word, which
+//! host tooling should send once it has started draining the DCC.
+
+/// Upper half-word of the first word of the handshake frame
+pub const HANDSHAKE_TAG: u32 = 0xDCC2_0000;
+
+/// Word the host sends to signal that it's ready to receive data; see `init_and_wait`
+pub const HOST_READY: u32 = 0xDCC3_0000;
+
+/// Version of the wire protocol; it's bumped every time an encoding changes in an incompatible way
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// `write_all` packs 4 bytes per word (the `packed` feature is enabled)
+pub const FEATURE_PACKED: u32 = 1 << 0;
+/// The device keeps transfer statistics (the `stats` feature is enabled)
+pub const FEATURE_STATS: u32 = 1 << 1;
+/// Each frame is followed by its CRC (the `crc` feature is enabled); see the `crc` module
+pub const FEATURE_CRC: u32 = 1 << 2;
+/// Each frame carries a sequence number (the `sequence` feature is enabled)
+pub const FEATURE_SEQUENCE: u32 = 1 << 3;
+
+/// Encodings enabled in this build
+pub const FEATURES: u32 = if cfg!(feature = "packed") { FEATURE_PACKED } else { 0 }
+    | if cfg!(feature = "stats") { FEATURE_STATS } else { 0 }
+    | if cfg!(feature = "crc") { FEATURE_CRC } else { 0 }
+    | if cfg!(feature = "sequence") { FEATURE_SEQUENCE } else { 0 };
+
+/// Version of this crate, encoded as in the handshake frame
+pub const CRATE_VERSION: u32 = (parse(env!("CARGO_PKG_VERSION_MAJOR")) << 16)
+    | (parse(env!("CARGO_PKG_VERSION_MINOR")) << 8)
+    | parse(env!("CARGO_PKG_VERSION_PATCH"));
+
+/// Contents of a handshake frame
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Handshake {
+    /// Protocol version used by the device
+    pub version: u16,
+    /// `FEATURE_*` bitmask
+    pub features: u32,
+    /// Version of this crate used by the device, encoded as `major << 16 | minor << 8 | patch`
+    pub crate_version: u32,
+}
+
+impl Handshake {
+    /// Returns `true` if the given `FEATURE_*` bit is set
+    pub fn has(&self, feature: u32) -> bool {
+        self.features & feature != 0
+    }
+}
+
+/// Sends the handshake frame
+///
+/// With the `openocd` feature enabled this does nothing: OpenOCD would take the first word for the
+/// header of an ASCII message and swallow the output that follows as its contents
+pub(crate) fn send() {
+    if cfg!(feature = "openocd") {
+        return;
+    }
+
+    crate::write_payload(HANDSHAKE_TAG | u32::from(PROTOCOL_VERSION), 0);
+    crate::write_payload(FEATURES, 0);
+    crate::write_payload(CRATE_VERSION, 0);
+}
+
+const fn parse(s: &str) -> u32 {
+    let bytes = s.as_bytes();
+    let mut n = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        n = n * 10 + (bytes[i] - b'0') as u32;
+        i += 1;
+    }
+    n
+}
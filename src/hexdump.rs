@@ -0,0 +1,65 @@
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+// "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |0123456789abcdef|\n"
+const ASCII: usize = 8 + 2 + 16 * 3 + 2;
+const LINE_LEN: usize = ASCII + 1 + 16 + 1 + 1;
+
+/// Prints `bytes` to the DCC in the canonical hexdump format, i.e. `hexdump -C`
+///
+/// Each line has the offset of its first byte, up to 16 bytes in hexadecimal and their ASCII
+/// rendering; non printable bytes are shown as `.`. The last line is the total length.
+///
+/// ``` text
+/// 00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 ff  |Hello, world!...|
+/// 00000010  2a                                                |*|
+/// 00000011
+/// ```
+///
+/// The output is formatted without going through `core::fmt`.
+///
+/// **NOTE:** This operation is blocking
+pub fn hexdump(bytes: &[u8]) {
+    let mut line = [b' '; LINE_LEN];
+
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        offset(&mut line, i * 16);
+
+        for (j, byte) in chunk.iter().enumerate() {
+            let pos = 10 + j * 3 + if j < 8 { 0 } else { 1 };
+            line[pos] = HEX[usize::from(byte >> 4)];
+            line[pos + 1] = HEX[usize::from(byte & 0xf)];
+        }
+        // NOTE a short last line is padded so its ASCII column lines up
+        for j in chunk.len()..16 {
+            let pos = 10 + j * 3 + if j < 8 { 0 } else { 1 };
+            line[pos] = b' ';
+            line[pos + 1] = b' ';
+        }
+
+        line[ASCII] = b'|';
+        let mut len = ASCII + 1;
+        for byte in chunk {
+            line[len] = if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte
+            } else {
+                b'.'
+            };
+            len += 1;
+        }
+        line[len] = b'|';
+        line[len + 1] = b'\n';
+
+        crate::sink::write_text(&line[..len + 2]);
+    }
+
+    offset(&mut line, bytes.len());
+    line[8] = b'\n';
+    crate::sink::write_text(&line[..9]);
+}
+
+// writes `offset` as 8 hex digits at the start of `line`
+fn offset(line: &mut [u8; LINE_LEN], offset: usize) {
+    for (i, digit) in line[..8].iter_mut().enumerate() {
+        *digit = HEX[(offset >> (28 - i * 4)) & 0xf];
+    }
+}
@@ -0,0 +1,150 @@
+//! Host stub of the DCC, for running code that uses this crate in host-side tests
+//!
+//! This module is only available when the `std` feature is enabled and the target is not an ARM
+//! target. By default text sent to the DCC is decoded (see the `decode` module) and printed to
+//! stdout, decompressing it if needed, and binary words, binary bytes and channel data are printed
+//! to stderr; `set_output` replaces that with a function that receives every word. `push_input`
+//! queues the words that the read functions will return.
+//!
+//! The input queue and the text decoder are per thread so tests that run in parallel don't see
+//! each other's data.
+//!
+//! ```
+//! arm_dcc::host::push_input(&[0x2a]);
+//! assert_eq!(arm_dcc::read(), 0x2a);
+//! ```
+
+use std::{cell::RefCell, collections::VecDeque, str, thread, vec::Vec};
+
+use crate::{
+    atomic::FnCell,
+    compress::Decompressor,
+    decode::{Decoder, Item},
+    handshake,
+};
+
+static OUTPUT: FnCell<fn(u32)> = FnCell::new();
+
+std::thread_local! {
+    static DECODER: RefCell<Decoder> = const {
+        RefCell::new(Decoder::with_features(handshake::FEATURES))
+    };
+    static DECOMPRESSOR: RefCell<Decompressor> = const { RefCell::new(Decompressor::new()) };
+    static INPUT: RefCell<VecDeque<u32>> = const { RefCell::new(VecDeque::new()) };
+    // text bytes that don't form a complete UTF-8 sequence yet
+    static TEXT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Registers the function that will receive every word written to the DCC, replacing the default
+/// stdout / stderr output
+pub fn set_output(output: fn(u32)) {
+    OUTPUT.set(output);
+}
+
+/// Restores the default stdout / stderr output
+pub fn clear_output() {
+    OUTPUT.clear();
+}
+
+/// Queues words that the current thread will receive from the DCC
+pub fn push_input(words: &[u32]) {
+    INPUT.with(|input| input.borrow_mut().extend(words.iter().cloned()));
+}
+
+pub(crate) fn has_input() -> bool {
+    INPUT.with(|input| !input.borrow().is_empty())
+}
+
+pub(crate) fn pop_input() -> u32 {
+    INPUT.with(|input| input.borrow_mut().pop_front().unwrap_or(0))
+}
+
+pub(crate) fn output(word: u32) {
+    if let Some(output) = OUTPUT.get() {
+        return output(word);
+    }
+
+    match DECODER.with(|decoder| decoder.borrow_mut().push(word)) {
+        Some(Item::Text(chunk)) => {
+            TEXT.with(|text| print_text(&mut text.borrow_mut(), chunk.as_bytes()))
+        }
+        Some(Item::Compressed(chunk)) => {
+            let mut bytes = Vec::new();
+            DECOMPRESSOR.with(|decompressor| {
+                decompressor
+                    .borrow_mut()
+                    .push(chunk.as_bytes(), |byte| bytes.push(byte))
+            });
+            TEXT.with(|text| print_text(&mut text.borrow_mut(), &bytes))
+        }
+        Some(Item::Word(word)) | Some(Item::Unknown(word)) => {
+            std::eprintln!("{:#010x}", word);
+        }
+        Some(Item::Bytes(chunk)) => {
+            for byte in chunk.as_bytes() {
+                std::eprintln!("{:#04x}", byte);
+            }
+        }
+        Some(Item::Value(chunk)) => {
+            for byte in chunk.as_bytes() {
+                std::eprintln!("value: {:#04x}", byte);
+            }
+        }
+        Some(Item::Channel(id, chunk)) => {
+            for byte in chunk.as_bytes() {
+                std::eprintln!("channel {}: {:#04x}", id, byte);
+            }
+        }
+        Some(Item::RecordBytes(chunk)) => {
+            for byte in chunk.as_bytes() {
+                std::eprintln!("record: {:#04x}", byte);
+            }
+        }
+        Some(Item::Record([kind, hash, line, uptime])) => {
+            std::eprintln!("record {} {:#010x}:{} at {} ms", kind, hash, line, uptime)
+        }
+        Some(Item::BadCrc) => std::eprintln!("CRC mismatch"),
+        Some(Item::Lost(frames)) => std::eprintln!("{} frames lost", frames),
+        Some(Item::Address(index, address)) => std::eprintln!("{:>4}: {:#010x}", index, address),
+        Some(Item::Exit(status)) => std::eprintln!("exit status {}", status),
+        Some(Item::Handshake(_)) | None => {}
+    }
+}
+
+// NOTE this goes through `print!`, rather than `io::stdout`, so the test harness captures it
+fn print_text(text: &mut Vec<u8>, bytes: &[u8]) {
+    text.extend_from_slice(bytes);
+
+    loop {
+        match str::from_utf8(text) {
+            Ok(s) => {
+                std::print!("{}", s);
+                text.clear();
+                return;
+            }
+            Err(e) => {
+                let (valid, rest) = text.split_at(e.valid_up_to());
+                // NOTE(unsafe) `valid` has just been validated
+                std::print!("{}", unsafe { str::from_utf8_unchecked(valid) });
+
+                match e.error_len() {
+                    // wait for the rest of the sequence
+                    None => {
+                        let rest = rest.to_vec();
+                        *text = rest;
+                        return;
+                    }
+                    Some(len) => {
+                        std::print!("{}", char::REPLACEMENT_CHARACTER);
+                        let rest = rest[len..].to_vec();
+                        *text = rest;
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn wait() {
+    thread::yield_now()
+}
@@ -0,0 +1,90 @@
+//! Runtime support of the `#[dcc_instrument]` attribute: the entry and exit lines of instrumented
+//! functions and the cycle counter they're timed with
+
+use core::fmt::{self, Write};
+
+use crate::{atomic::FnCell, Writer};
+
+static CYCLES: FnCell<fn() -> u32> = FnCell::new();
+
+/// Registers the function that `#[dcc_instrument(cycles)]` calls to read a cycle counter, e.g. the
+/// PMCCNTR register of ARMv7 cores or a free running timer
+pub fn set_cycle_counter(counter: fn() -> u32) {
+    CYCLES.set(counter);
+}
+
+fn cycles() -> Option<u32> {
+    CYCLES.get().map(|counter| counter())
+}
+
+/// An argument of an instrumented function
+///
+/// Which of `TraceDebug` and `TraceOpaque` gets used is decided by method resolution: `TraceDebug`
+/// is implemented on `TraceArg` itself so it's picked when the argument implements `Debug`;
+/// otherwise the autoref'd `TraceOpaque` implementation is used.
+#[doc(hidden)]
+pub struct TraceArg<'a, T: ?Sized>(pub &'a T);
+
+/// Prints an argument with `{:?}`
+#[doc(hidden)]
+pub trait TraceDebug {
+    /// Prints `name = value`
+    fn trace(&self, name: &str);
+}
+
+impl<T> TraceDebug for TraceArg<'_, T>
+where
+    T: fmt::Debug + ?Sized,
+{
+    fn trace(&self, name: &str) {
+        // NOTE this operation never returns `Err`
+        write!(Writer, "{} = {:?}", name, self.0).ok();
+    }
+}
+
+/// Prints an argument that doesn't implement `Debug`
+#[doc(hidden)]
+pub trait TraceOpaque {
+    /// Prints `name = _`
+    fn trace(&self, name: &str);
+}
+
+impl<T> TraceOpaque for &TraceArg<'_, T>
+where
+    T: ?Sized,
+{
+    fn trace(&self, name: &str) {
+        crate::write_str(name);
+        crate::write_str(" = _");
+    }
+}
+
+/// Prints the exit of an instrumented function when dropped
+#[doc(hidden)]
+pub struct TraceExit {
+    name: &'static str,
+    start: Option<u32>,
+}
+
+impl TraceExit {
+    /// Starts timing the function if `cycles` is set and a cycle counter has been registered
+    pub fn new(name: &'static str, cycles: bool) -> Self {
+        TraceExit {
+            name,
+            start: if cycles { self::cycles() } else { None },
+        }
+    }
+}
+
+impl Drop for TraceExit {
+    fn drop(&mut self) {
+        crate::write_str("<- ");
+        crate::write_str(self.name);
+
+        if let (Some(start), Some(end)) = (self.start, cycles()) {
+            // NOTE this operation never returns `Err`
+            write!(Writer, " ({} cycles)", end.wrapping_sub(start)).ok();
+        }
+        Writer.write_str("\n").ok();
+    }
+}
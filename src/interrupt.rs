@@ -0,0 +1,178 @@
+//! Interrupt driven DCC
+//!
+//! The core signals the state of the DCC to the interrupt controller through the COMMRX (DBGDTRRX
+//! is full) and COMMTX (DBGDTRTX is empty) lines. These interrupts are enabled, masked and
+//! prioritized in the interrupt controller of the device (e.g. the GIC or the VIM); the DCC itself
+//! has no interrupt enable bits. This module provides the DCC side of the interrupt handlers.
+
+use core::ptr;
+
+use crate::{
+    atomic::{AtomicBool, AtomicPtr, AtomicUsize, FnCell, Ordering},
+    WouldBlock,
+};
+
+static RX_HANDLER: FnCell<fn(u32)> = FnCell::new();
+
+/// Registers the function that `on_commrx` will call with each word received from the host
+pub fn set_rx_handler(handler: fn(u32)) {
+    RX_HANDLER.set(handler);
+}
+
+/// Unregisters the function set with `set_rx_handler`
+pub fn clear_rx_handler() {
+    RX_HANDLER.clear();
+}
+
+/// Drains the DCC receive register, passing each word to the registered handler
+///
+/// Call this from the interrupt handler of the COMMRX interrupt. Returns the number of words
+/// that were received. If no handler has been registered the words are discarded, otherwise the
+/// COMMRX interrupt would fire again right away.
+///
+/// ``` no_run
+/// use arm_dcc::interrupt;
+///
+/// fn on_word(word: u32) {
+///     // e.g. push `word` into a queue that `main` consumes
+/// }
+///
+/// // during initialization
+/// interrupt::set_rx_handler(on_word);
+///
+/// // in the COMMRX interrupt handler
+/// interrupt::on_commrx();
+/// ```
+pub fn on_commrx() -> usize {
+    let handler = RX_HANDLER.get();
+
+    let mut n = 0;
+    while let Some(word) = crate::try_read() {
+        if let Some(handler) = handler {
+            handler(word);
+        }
+
+        n += 1;
+    }
+
+    n
+}
+
+static TX_ENABLED: AtomicBool = AtomicBool::new(false);
+static TX_BUFFER: AtomicPtr<u32> = AtomicPtr::new(ptr::null_mut());
+static TX_CAPACITY: AtomicUsize = AtomicUsize::new(0);
+// NOTE these indices wrap around at twice the capacity, so a full queue (`capacity` words apart)
+// can be told apart from an empty one (`0` words apart); the slot of an index is `index % capacity`
+static TX_HEAD: AtomicUsize = AtomicUsize::new(0);
+static TX_TAIL: AtomicUsize = AtomicUsize::new(0);
+static TX_NOTIFY: FnCell<fn()> = FnCell::new();
+
+/// Makes `write` and `try_write` (and all the API built on top of them) queue words in `buffer`
+/// instead of writing them to the DCC; `on_commtx` moves them from the queue to the DCC
+///
+/// Call this once, during initialization. `write` will only block (or drop words, in `Lossy` mode)
+/// when the queue is full. Note that, unless the COMMTX interrupt is enabled, nothing will be sent
+/// to the host.
+///
+/// **NOTE:** The queue supports a single producer: words must be written from one execution
+/// context, or from contexts that can't preempt each other. With the `nop` feature enabled the
+/// queue is never used.
+///
+/// # Panics
+///
+/// If `buffer` is empty
+///
+/// ``` no_run
+/// use arm_dcc::{dprintln, interrupt};
+///
+/// static mut BUFFER: [u32; 256] = [0; 256];
+///
+/// // during initialization
+/// interrupt::enable_tx_fifo(unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) });
+///
+/// // no longer blocks, unless the queue is full
+/// dprintln!("Hello, world!");
+///
+/// // in the COMMTX interrupt handler
+/// if interrupt::on_commtx() {
+///     // queue is empty: mask COMMTX in the interrupt controller
+/// }
+/// ```
+pub fn enable_tx_fifo(buffer: &'static mut [u32]) {
+    assert!(!buffer.is_empty(), "transmit FIFO buffer is empty");
+
+    TX_ENABLED.store(false, Ordering::Release);
+    TX_HEAD.store(0, Ordering::Relaxed);
+    TX_TAIL.store(0, Ordering::Relaxed);
+    TX_CAPACITY.store(buffer.len(), Ordering::Relaxed);
+    TX_BUFFER.store(buffer.as_mut_ptr(), Ordering::Relaxed);
+    TX_ENABLED.store(true, Ordering::Release);
+}
+
+/// Registers a function that `write` calls after queueing a word
+///
+/// Use this to unmask the COMMTX interrupt when `on_commtx` masks it on an empty queue. COMMTX
+/// is a level-sensitive signal that stays asserted while DBGDTRTX is empty so it must be masked
+/// when there's nothing to send.
+pub fn set_tx_notify(notify: fn()) {
+    TX_NOTIFY.set(notify);
+}
+
+/// Moves as many queued words as possible into the DCC
+///
+/// Call this from the interrupt handler of the COMMTX interrupt. Returns `true` if the queue is
+/// now empty, in which case the COMMTX interrupt should be masked.
+pub fn on_commtx() -> bool {
+    let buffer = TX_BUFFER.load(Ordering::Acquire);
+    let capacity = TX_CAPACITY.load(Ordering::Relaxed);
+    let mut tail = TX_TAIL.load(Ordering::Relaxed);
+
+    loop {
+        if tail == TX_HEAD.load(Ordering::Acquire) {
+            return true;
+        }
+
+        // NOTE(unsafe) the producer doesn't touch slots between `TX_TAIL` and `TX_HEAD`
+        let word = unsafe { ptr::read_volatile(buffer.add(tail % capacity)) };
+        if crate::try_write_dtr(word).is_err() {
+            return false;
+        }
+
+        tail = advance(tail, capacity);
+        TX_TAIL.store(tail, Ordering::Release);
+    }
+}
+
+pub(crate) fn tx_fifo_enabled() -> bool {
+    !cfg!(all(target_arch = "arm", feature = "nop")) && TX_ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn enqueue(word: u32) -> Result<(), WouldBlock> {
+    let buffer = TX_BUFFER.load(Ordering::Acquire);
+    let capacity = TX_CAPACITY.load(Ordering::Relaxed);
+    let head = TX_HEAD.load(Ordering::Relaxed);
+
+    let tail = TX_TAIL.load(Ordering::Acquire);
+    if (head + 2 * capacity - tail) % (2 * capacity) >= capacity {
+        return Err(WouldBlock);
+    }
+
+    // NOTE(unsafe) the consumer doesn't touch slots outside `TX_TAIL..TX_HEAD`
+    unsafe { ptr::write_volatile(buffer.add(head % capacity), word) }
+    TX_HEAD.store(advance(head, capacity), Ordering::Release);
+
+    if let Some(notify) = TX_NOTIFY.get() {
+        notify();
+    }
+
+    Ok(())
+}
+
+// the index that follows `index`; see `TX_HEAD`
+fn advance(index: usize, capacity: usize) -> usize {
+    if index + 1 == 2 * capacity {
+        0
+    } else {
+        index + 1
+    }
+}
@@ -0,0 +1,68 @@
+//! `embedded-io` implementations for `Dcc`
+
+use core::convert::Infallible;
+
+use embedded_io::{ErrorType, Read, ReadReady, Write, WriteReady};
+
+use crate::{leftover::Leftover, Dcc, RXFULL, TXFULL};
+
+impl ErrorType for Dcc {
+    type Error = Infallible;
+}
+
+/// Sends the bytes with `write_all`, so this blocks until all of them have been sent (see
+/// `set_policy` for the exception)
+impl Write for Dcc {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        crate::write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Blocks until the host sends a word and then also takes the words that are already waiting,
+/// as long as they fit in `buf`
+///
+/// Each word carries one byte, or 4 bytes if the `packed` feature is enabled, as in `read_exact`.
+/// In `packed` mode, the bytes of a word that don't fit in `buf` are kept and returned by the next
+/// `read`, before any new word is read.
+impl Read for Dcc {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut n = self.leftover.take(buf);
+        if n == 0 {
+            self.leftover = Leftover::from(crate::read());
+            n = self.leftover.take(buf);
+        }
+
+        while n < buf.len() {
+            match crate::try_read() {
+                Some(word) => {
+                    self.leftover = Leftover::from(word);
+                    n += self.leftover.take(&mut buf[n..]);
+                }
+                None => break,
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+impl ReadReady for Dcc {
+    fn read_ready(&mut self) -> Result<bool, Infallible> {
+        Ok(crate::dscr() & RXFULL != 0)
+    }
+}
+
+impl WriteReady for Dcc {
+    fn write_ready(&mut self) -> Result<bool, Infallible> {
+        Ok(crate::dscr() & TXFULL == 0)
+    }
+}
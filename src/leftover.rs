@@ -0,0 +1,33 @@
+//! Bytes of a received word that the byte oriented reader traits have not returned yet
+
+// bytes of a received word that have not been returned yet, least significant byte first
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Leftover {
+    word: u32,
+    len: u8,
+}
+
+impl Leftover {
+    pub(crate) const fn new() -> Self {
+        Leftover { word: 0, len: 0 }
+    }
+
+    pub(crate) fn from(word: u32) -> Self {
+        Leftover {
+            word,
+            len: if cfg!(feature = "packed") { 4 } else { 1 },
+        }
+    }
+
+    // moves as many bytes as fit into `buf`; returns how many were moved
+    pub(crate) fn take(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(usize::from(self.len));
+        for byte in &mut buf[..n] {
+            *byte = self.word as u8;
+            self.word >>= 8;
+        }
+        self.len -= n as u8;
+
+        n
+    }
+}
@@ -0,0 +1,357 @@
+//! Log levels and the filters that decide which log records are printed
+//!
+//! Records are filtered at compile time by `STATIC_MAX_LEVEL` and at run time by `set_max_level`
+//! and by the per module filters of `ARM_DCC_LOG` and `set_module_filters`.
+
+use core::{fmt, ptr};
+
+use crate::atomic::{AtomicPtr, AtomicU8, Ordering};
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(LevelFilter::Trace as u8);
+// NOTE a pointer to the (fat) slice reference so the pointer and the length change together
+static MODULE_FILTERS: AtomicPtr<&'static [ModuleFilter]> = AtomicPtr::new(ptr::null_mut());
+
+// NOTE the `ARM_DCC_LOG` filters, parsed by the build script; see `set_module_filters`
+const ENV_FILTERS: &[ModuleFilter] = include!(concat!(env!("OUT_DIR"), "/module_filters.rs"));
+
+/// Verbosity level of a log record
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Level {
+    /// Serious errors
+    Error = 1,
+    /// Hazardous situations
+    Warn,
+    /// Useful information
+    Info,
+    /// Lower priority information
+    Debug,
+    /// Very low priority, often extremely verbose, information
+    Trace,
+}
+
+impl Level {
+    /// Returns the name of the level in upper case, e.g. `"WARN"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    // ANSI escape code that sets the foreground color of the lines of this level
+    #[cfg(all(feature = "colors", not(feature = "binary-log")))]
+    fn color(self) -> &'static str {
+        match self {
+            Level::Error => "\x1b[31m",
+            Level::Warn => "\x1b[33m",
+            Level::Info => "\x1b[32m",
+            Level::Debug => "\x1b[34m",
+            Level::Trace => "\x1b[36m",
+        }
+    }
+
+    /// Returns `true` if records of this level pass `filter`
+    pub const fn passes(self, filter: LevelFilter) -> bool {
+        self as u8 <= filter as u8
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Most verbose `Level` that's let through, or `Off` to let nothing through
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LevelFilter {
+    /// Disables all records
+    Off,
+    /// Only `Error` records
+    Error,
+    /// `Warn` records and higher
+    Warn,
+    /// `Info` records and higher
+    Info,
+    /// `Debug` records and higher
+    Debug,
+    /// All records
+    Trace,
+}
+
+impl LevelFilter {
+    /// Parses the name of a filter, e.g. `"debug"`; the name is case insensitive
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            ("off", LevelFilter::Off),
+            ("error", LevelFilter::Error),
+            ("warn", LevelFilter::Warn),
+            ("info", LevelFilter::Info),
+            ("debug", LevelFilter::Debug),
+            ("trace", LevelFilter::Trace),
+        ]
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, filter)| *filter)
+    }
+
+    /// Decodes a filter from its numeric value: `0` is `Off`, `5` is `Trace`; values above `5` are
+    /// also `Trace`
+    pub const fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LevelFilter::Off,
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<log::LevelFilter> for LevelFilter {
+    fn from(filter: log::LevelFilter) -> Self {
+        match filter {
+            log::LevelFilter::Off => LevelFilter::Off,
+            log::LevelFilter::Error => LevelFilter::Error,
+            log::LevelFilter::Warn => LevelFilter::Warn,
+            log::LevelFilter::Info => LevelFilter::Info,
+            log::LevelFilter::Debug => LevelFilter::Debug,
+            log::LevelFilter::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<LevelFilter> for log::LevelFilter {
+    fn from(filter: LevelFilter) -> Self {
+        match filter {
+            LevelFilter::Off => log::LevelFilter::Off,
+            LevelFilter::Error => log::LevelFilter::Error,
+            LevelFilter::Warn => log::LevelFilter::Warn,
+            LevelFilter::Info => log::LevelFilter::Info,
+            LevelFilter::Debug => log::LevelFilter::Debug,
+            LevelFilter::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Maximum level of the log records that are compiled in
+///
+/// This is set with the `max-level-*` and `release-max-level-*` features (the `release-*`
+/// variants only apply when debug assertions are disabled). If several are enabled, the most
+/// restrictive one applies. The `silent` feature sets this to `Off`. `dlog!` and friends, and
+/// `logger::DccLogger`, drop the records above this level; the compiler removes the `dlog!` calls
+/// that can never pass this filter.
+pub const STATIC_MAX_LEVEL: LevelFilter = static_max_level();
+
+const fn static_max_level() -> LevelFilter {
+    let release = !cfg!(debug_assertions);
+
+    if cfg!(feature = "silent")
+        || cfg!(feature = "max-level-off")
+        || release && cfg!(feature = "release-max-level-off")
+    {
+        LevelFilter::Off
+    } else if cfg!(feature = "max-level-error")
+        || release && cfg!(feature = "release-max-level-error")
+    {
+        LevelFilter::Error
+    } else if cfg!(feature = "max-level-warn")
+        || release && cfg!(feature = "release-max-level-warn")
+    {
+        LevelFilter::Warn
+    } else if cfg!(feature = "max-level-info")
+        || release && cfg!(feature = "release-max-level-info")
+    {
+        LevelFilter::Info
+    } else if cfg!(feature = "max-level-debug")
+        || release && cfg!(feature = "release-max-level-debug")
+    {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Trace
+    }
+}
+
+/// Changes the maximum level of the log records that are printed at runtime
+///
+/// This filter applies on top of `STATIC_MAX_LEVEL`, to the modules that have no filter of their
+/// own (see `set_module_filters`); it's `Trace` until changed. The host can also change it with
+/// the `control::SET_LOG_LEVEL` command.
+pub fn set_max_level(filter: LevelFilter) {
+    MAX_LEVEL.store(filter as u8, Ordering::Relaxed)
+}
+
+/// Returns the runtime filter set with `set_max_level`
+pub fn max_level() -> LevelFilter {
+    LevelFilter::from_u8(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// The filter of the records of a module and its submodules
+#[derive(Clone, Copy, Debug)]
+pub struct ModuleFilter {
+    /// The module path, e.g. `my_app::net`
+    pub module: &'static str,
+    /// The filter that applies to the module
+    pub level: LevelFilter,
+}
+
+/// Registers per module filters
+///
+/// The records of a module are filtered by the entry with the longest module path that matches
+/// the module or one of its parents, instead of by the global runtime filter (see
+/// `set_max_level`). `STATIC_MAX_LEVEL` still applies.
+///
+/// Filters can also be set at build time with the `ARM_DCC_LOG` environment variable, a comma
+/// separated list of `module::path=level` entries (a path without level lets everything through),
+/// e.g. `ARM_DCC_LOG="my_app::spi=off,my_app::net=debug"`. When an entry of the environment
+/// variable is as specific as a registered one, the registered one wins.
+///
+/// ``` no_run
+/// use arm_dcc::{LevelFilter, ModuleFilter};
+///
+/// static FILTERS: &[ModuleFilter] = &[
+///     ModuleFilter {
+///         module: "my_app::spi",
+///         level: LevelFilter::Off,
+///     },
+///     ModuleFilter {
+///         module: "my_app::net",
+///         level: LevelFilter::Debug,
+///     },
+/// ];
+///
+/// arm_dcc::set_module_filters(&FILTERS);
+/// ```
+pub fn set_module_filters(filters: &'static &'static [ModuleFilter]) {
+    MODULE_FILTERS.store(
+        filters as *const &'static [ModuleFilter] as *mut _,
+        Ordering::Release,
+    );
+}
+
+/// Unregisters the filters set with `set_module_filters`
+pub fn clear_module_filters() {
+    MODULE_FILTERS.store(ptr::null_mut(), Ordering::Release);
+}
+
+fn module_filters() -> &'static [ModuleFilter] {
+    let filters = MODULE_FILTERS.load(Ordering::Acquire);
+
+    if filters.is_null() {
+        &[]
+    } else {
+        // NOTE(unsafe) `set_module_filters` stored this from a `&'static &'static [ModuleFilter]`
+        unsafe { *filters }
+    }
+}
+
+// `true` if `target` is `module` or one of its submodules
+fn is_within(target: &str, module: &str) -> bool {
+    target.starts_with(module)
+        && (target.len() == module.len() || target[module.len()..].starts_with("::"))
+}
+
+// finds the most specific filter that applies to `target`
+fn module_filter(target: &str) -> Option<LevelFilter> {
+    let mut best = None;
+    let mut best_len = 0;
+    let mut consider = |module: &str, level| {
+        if is_within(target, module) && (best.is_none() || module.len() > best_len) {
+            best = Some(level);
+            best_len = module.len();
+        }
+    };
+
+    for filter in module_filters().iter().chain(ENV_FILTERS) {
+        consider(filter.module, filter.level);
+    }
+
+    best
+}
+
+/// Returns `true` if a record with the given level and target (usually the module path) would be
+/// printed
+pub fn log_enabled(level: Level, target: &str) -> bool {
+    level.passes(STATIC_MAX_LEVEL) && level.passes(module_filter(target).unwrap_or_else(max_level))
+}
+
+/// Implementation detail of `dlog!`; also used by `logger::DccLogger`
+#[doc(hidden)]
+pub fn record(level: Level, target: &str, line: u32, args: fmt::Arguments) {
+    #[cfg(feature = "binary-log")]
+    crate::record::write(level, target, line, args);
+
+    #[cfg(not(feature = "binary-log"))]
+    {
+        use core::fmt::Write;
+
+        use crate::Writer;
+
+        // NOTE the line number is only included in binary records
+        let _ = line;
+        #[cfg(feature = "colors")]
+        crate::write_str(level.color());
+        // NOTE these operations never return `Err`
+        if let Some(timestamp) = crate::timestamp() {
+            write!(Writer, "{} ", timestamp).ok();
+        }
+        write!(Writer, "{} {}: {}", level, target, args).ok();
+        // NOTE the color is reset before the newline so it doesn't leak into the next line
+        #[cfg(feature = "colors")]
+        crate::write_str("\x1b[0m\n");
+        #[cfg(not(feature = "colors"))]
+        crate::write_str("\n");
+    }
+}
+
+/// Implementation detail of the structured form of `dlog!`
+#[doc(hidden)]
+pub fn record_fields(
+    level: Level,
+    target: &str,
+    line: u32,
+    msg: &str,
+    fields: &[(&str, &dyn fmt::Debug)],
+) {
+    record(
+        level,
+        target,
+        line,
+        format_args!("{}", Fields { msg, fields }),
+    )
+}
+
+// the message of a structured record: `msg key1=value1 key2=value2`
+struct Fields<'a> {
+    msg: &'a str,
+    fields: &'a [(&'a str, &'a dyn fmt::Debug)],
+}
+
+impl fmt::Display for Fields<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.msg)?;
+        for (key, value) in self.fields {
+            write!(f, " {}={:?}", key, value)?;
+        }
+        Ok(())
+    }
+}
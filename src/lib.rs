@@ -48,38 +48,356 @@
 //! Hello, world!
 //! ```
 //!
+//! The `arm-dcc-host` crate of this repository decodes captures of the raw DCC words, as needed
+//! when the device uses the binary encodings of this crate (see the `decode` module), e.g. with the
+//! `packed`, `binary-log`, `compress` or `crc` features enabled:
+//!
+//! ``` text
+//! $ arm-dcc-host dcc.bin
+//! device: protocol version 1, arm-dcc 0.1.0, features 0x5
+//! [   0.012] INFO hello: Hello, world!
+//! ```
+//!
 //! # Supported Rust version
 //!
-//! - Rust >=1.31 when the target is one of the 4 ARMv7 Cortex-R targets.
+//! This crate is guaranteed to compile on stable Rust 1.59 and up (the release that stabilized
+//! `asm!`) on the following targets:
+//!
+//! - the 4 ARMv7 Cortex-R targets and the ARMv8-R target (`armv8r-none-eabihf`, e.g. Cortex-R52)
+//!
+//! - the ARMv7-A targets (`armv7a-none-eabi`, `armv7a-none-eabihf`, `armv7-unknown-linux-gnueabi`
+//!   and `armv7-unknown-linux-gnueabihf`)
+//!
+//! - the ARMv6 targets (`armv6-none-eabi`, `armv6-none-eabihf`, `arm-unknown-linux-gnueabi` and
+//!   `arm-unknown-linux-gnueabihf`, e.g. ARM11 cores like the one in the BCM2835)
+//!
+//!   On Linux the kernel must allow user space access to the DCC (the UDCCdis bit of DBGDSCR
+//!   must be cleared), otherwise the DCC instructions are undefined.
 //!
-//! - All the other ARM targets require enabling the `inline-asm`, which requires a nightly
-//! compiler.
+//! - the ARMv4T / ARMv5TE targets (`armv4t-none-eabi`, `armv5te-none-eabi`,
+//!   `armv4t-unknown-linux-gnueabi` and `armv5te-unknown-linux-gnueabi`, e.g. ARM7TDMI and
+//!   ARM926EJ-S). These cores use the EmbeddedICE comms channel.
+//!
+//!   The bare metal ARMv4T / ARMv5TE targets have no atomic instructions; on them the global
+//!   state of this crate (e.g. the write policy and the statistics) is updated with plain loads
+//!   and stores, so it must not be modified from interrupt handlers.
+//!
+//! - the Thumb variants of the above targets (`thumbv4t-none-eabi`, `thumbv5te-none-eabi`,
+//!   `thumbv6-none-eabi`, `thumbv7a-none-eabi`, `thumbv7a-none-eabihf`,
+//!   `thumbv7neon-unknown-linux-gnueabihf`, `thumbv7r-none-eabi`, `thumbv7r-none-eabihf` and
+//!   `thumbv8r-none-eabihf`). Thumb-1 has no coprocessor instructions so on the ARMv4T, ARMv5TE
+//!   and ARMv6 ones the DCC is accessed through ARM state routines.
+//!
+//! - the AArch64 targets, e.g. `aarch64-unknown-none`. On AArch64 the DCC is accessed through the
+//!   MDCCSR_EL0, DBGDTRTX_EL0 and DBGDTRRX_EL0 registers.
+//!
+//! - the Cortex-M targets (`thumbv6m-none-eabi`, `thumbv7m-none-eabi`, `thumbv7em-none-eabi`,
+//!   `thumbv7em-none-eabihf` and the `thumbv8m.*` targets). M-profile cores have no DCC so the
+//!   output goes to stimulus port 0 of the ITM instead, one 4-byte packet per DCC word. The
+//!   debugger must enable the ITM and stimulus port 0. The ITM can't receive data so the read
+//!   functions of this crate never complete on these targets.
+//!
+//! With the `extern-asm` feature this crate also compiles on Rust 1.51 to 1.58 when the target is
+//! one of the A-profile or R-profile 32-bit targets listed above.
 //!
 //! # Optional features
 //!
+//! ## `max-level-*` and `release-max-level-*`
+//!
+//! `max-level-off`, `max-level-error`, `max-level-warn`, `max-level-info`, `max-level-debug` and
+//! `max-level-trace` set `STATIC_MAX_LEVEL`, the most verbose log level that's compiled in. The
+//! `release-max-level-*` variants do the same but only when debug assertions are disabled. Log
+//! records above that level, printed with `dlog!` and friends or with `logger::DccLogger`, are
+//! dropped.
+//!
 //! ## `nop`
 //!
 //! Turns `dcc::write` into a "no-operation" (not the instruction). This is useful when the DCC is
 //! disabled as `dcc::write` blocks forever in that case. This feature has precedence over the
-//! `inline-asm` feature.
+//! `extern-asm` feature.
 //!
-//! ## `inline-asm`
+//! ## `log`
+//!
+//! Adds the `logger` module, a backend for the `log` crate that prints the log records to the DCC.
+//!
+//! ## `macros`
+//!
+//! Adds the `dcc_instrument` attribute, which traces the calls to a function, and
+//! `set_cycle_counter`. The attribute is implemented in the `arm-dcc-macros` crate.
+//!
+//! ## `packed`
+//!
+//! Makes `dcc::write_all` (and therefore `dcc::write_str`, `Writer` and the `dprint*` macros)
+//! send 4 bytes per DCC word using `dcc::write_all_packed`. The host needs to decode the stream,
+//! see the `decode` module. It also makes `dcc::read_exact` expect 4 bytes per word from the host,
+//! see `dcc::read_exact_packed`.
+//!
+//! ## `openocd`
+//!
+//! Makes the text API (`dcc::write_str`, `Writer`, the `dprint*` macros, the log macros, etc.) send
+//! its output as OpenOCD debug messages, which OpenOCD prints after `target_request debugmsgs
+//! enable`; see the `openocd` module. This feature has precedence over the `packed` feature for
+//! text. `dcc::init` sends no handshake frame in this mode.
+//!
+//! ## `compress`
+//!
+//! Makes the text API send its output compressed, as `compress::COMPRESSED_TAG` frames, which
+//! usually takes a half or a third of the DCC words for log lines; see the `compress` module. The
+//! `dcc-log` tool, and `decode::Decoder` with `compress::Decompressor`, restore the text on the
+//! host. The `openocd` feature has precedence over this feature.
+//!
+//! ## `binary-log`
+//!
+//! Makes `dlog!` and friends, and `logger::DccLogger`, send log records in a compact binary format
+//! instead of as lines of text; see the `record` module. The `dcc-log` tool renders them on the
+//! host.
+//!
+//! ## `c-api`
+//!
+//! Exports `dcc_write`, `dcc_write_str`, `dcc_write_bytes` and `dcc_putc`, C callable wrappers of
+//! the write functions, so C and assembly code linked into the same image can log through this
+//! crate. Their prototypes are in the `arm_dcc.h` header at the root of this crate. E.g. newlib's
+//! `_write` can be implemented on top of `dcc_write_bytes`.
+//!
+//! ## `colors`
 //!
-//! When this feature is enabled `dcc::write` is implemented using inline assembly (`asm!`) and
-//! compiling this crate requires nightly. Note that this feature requires that the compilation
-//! target is one of the 4 ARMv7 Cortex-R targets.
+//! Wraps the lines printed by `dlog!` and friends, and `logger::DccLogger`, in ANSI escape codes
+//! that color them by level: errors in red, warnings in yellow, info in green, debug in blue and
+//! trace in cyan. Handy when following the DCC log in a terminal, e.g. with `tail -f`. Binary log
+//! records are not affected.
 //!
-//! When this feature is disabled `dcc::write` is implemented using FFI calls into an external
-//! assembly file and compiling this crate works on stable and beta.
+//! ## `crc`
+//!
+//! Appends a CRC-32 word to each frame sent by `dcc::write_all_packed`, `dcc::write_words_framed`,
+//! `dcc::write_bytes_framed` and the `channel` module, and to what's built on them, e.g. binary log
+//! records, so the host decoder can detect corrupted words; see the `crc` module.
+//!
+//! ## `sequence`
+//!
+//! Adds a sequence number, incremented by one on every frame, to each frame of the framed
+//! encodings, the same ones as the `crc` feature, so the host decoder can report frames that were
+//! lost, e.g. dropped in `Lossy` mode or sent while the debugger was not connected; see
+//! `decode::Item::Lost`.
+//!
+//! ## `async`
+//!
+//! Adds `Dcc::poll_write`, `Dcc::poll_flush` and `Dcc::write_all_async`, which return `Pending`
+//! instead of busy waiting while the transmit register is full, so tasks of an async executor can
+//! log without blocking the executor thread.
+//!
+//! ## `embassy`
+//!
+//! Adds the `embassy` module, an async driver whose futures are woken by the COMMTX and COMMRX
+//! interrupts, for embassy-executor and other async executors. It uses the `AtomicWaker` of
+//! `embassy-sync`, so a `critical-section` implementation must be linked in. Not available on the
+//! bare metal ARMv4T / ARMv5TE targets, which have no atomic instructions.
+//!
+//! ## `embedded-hal-nb`
+//!
+//! Implements the `serial::Read<u8>` and `serial::Write<u8>` traits of the `embedded-hal-nb` crate
+//! for `Dcc`, so code written against the HAL serial traits can use the DCC as a serial port.
+//!
+//! ## `embedded-io`
+//!
+//! Implements the `Read`, `Write`, `ReadReady` and `WriteReady` traits of the `embedded-io` crate
+//! for `Dcc`, so drivers and protocol stacks written against those traits can run over the DCC.
+//!
+//! ## `heapless`
+//!
+//! Adds `dcc::read_line` and the `dreadln!` macro, which read a line sent by the host into a
+//! `heapless::String`, and the `dformat!` macro, which formats a message into one.
+//!
+//! ## `stats`
+//!
+//! Keeps track of the number of words and bytes sent and of the longest busy-wait in `dcc::write`;
+//! see `dcc::stats`. This makes `dcc::write` poll the DCC status from Rust, which is slightly
+//! slower than the default path.
+//!
+//! ## `values`
+//!
+//! Adds the `value` module, which sends `serde::Serialize` values serialized with postcard, and
+//! collects them on the host.
+//!
+//! ## `xscale`
+//!
+//! Makes this crate use the CP14 TX, RX and TXRXCTRL registers of XScale cores (e.g. the PXA2xx
+//! family) instead of the EmbeddedICE comms channel. XScale cores implement ARMv5TE so this
+//! feature can only be used with the `armv5te-none-eabi`, `armv5te-unknown-linux-gnueabi` and
+//! `thumbv5te-none-eabi` targets.
+//!
+//! ## `silent`
+//!
+//! Turns `dprint!` and `dprintln!`, and the macros built on top of them (`ddbg!`, `dtime!`,
+//! `dprint_once!`, `dprintln_once!` and `dprintln_throttled!`), into code that type checks the
+//! arguments but neither formats them nor touches the DCC. The arguments are not evaluated, except
+//! for the expressions passed to `ddbg!` and `dtime!`, which are still returned. It also sets
+//! `STATIC_MAX_LEVEL` to `Off`. Useful to remove logging from release builds without `cfg`
+//! attributes in the application.
+//!
+//! ## `std`
+//!
+//! When the target is not an ARM target, replaces the `unimplemented!` stubs with a host
+//! implementation that prints the output to stdout, so code that uses this crate can run in
+//! host-side tests; see the `host` module. This feature has no effect on ARM targets.
+//!
+//! ## `extern-asm`
+//!
+//! Makes this crate call assembly routines that are shipped as prebuilt static libraries, instead
+//! of using `asm!` and `global_asm!`. This lets Rust 1.51 to 1.58 compile this crate on the
+//! 32-bit ARM targets.
+//!
+//! ## `inline-asm`
+//!
+//! Deprecated and ignored. Inline assembly is used by default.
 
-#![cfg_attr(feature = "inline-asm", feature(asm))]
 #![deny(missing_docs)]
 #![deny(warnings)]
 #![no_std]
 
 use core::fmt;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "heapless")]
+pub use heapless;
+
+use crate::{
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    backend::{dscr, dtrrx, dtrtx},
+    frame::FrameWriter,
+};
+
+pub use crate::{
+    buffered::{BufferedBytesWriter, BufferedWriter},
+    hexdump::hexdump,
+    level::{
+        clear_module_filters, log_enabled, max_level, set_max_level, set_module_filters, Level,
+        LevelFilter, ModuleFilter, STATIC_MAX_LEVEL,
+    },
+    reset::init_report,
+    show::Show,
+    sink::{set_sink, DccSink, SetSinkError},
+    timestamp::{
+        set_timestamp_source, timestamp, SetTimestampSourceError, Timestamp, TimestampSource,
+    },
+    trace::{trace, trace_data},
+    unwrap::UnwrapDcc,
+    wait::{set_wait, Wait},
+    watchdog::{set_uptime, set_watchdog_state, watchdog_report_at},
+};
+
+#[cfg(feature = "async")]
+pub use crate::async_io::WriteAll;
+
+#[cfg(feature = "heapless")]
+pub use crate::line::{read_line, ReadLineError};
+
+/// Traces calls to a function over the DCC
+///
+/// On entry the instrumented function prints its name and its arguments; arguments that implement
+/// `Debug` are printed with `{:?}` and the rest as `_`. `self` is never printed. On exit, including
+/// early returns, it prints its name again.
+///
+/// With `#[dcc_instrument(cycles)]` the exit line also includes the number of cycles the call took,
+/// as measured with the counter registered with `set_cycle_counter`.
+///
+/// ``` ignore
+/// use arm_dcc::dcc_instrument;
+///
+/// #[dcc_instrument(cycles)]
+/// fn configure(channel: u8, baud: u32) -> bool {
+///     // ..
+///     # true
+/// }
+///
+/// // prints
+/// // -> configure(channel = 1, baud = 115200)
+/// // <- configure (3568 cycles)
+/// configure(1, 115_200);
+/// ```
+///
+/// This attribute is only available when the `macros` feature is enabled.
+#[cfg(feature = "macros")]
+pub use arm_dcc_macros::dcc_instrument;
+
+#[cfg(feature = "macros")]
+pub use crate::instrument::set_cycle_counter;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub use crate::instrument::{TraceArg, TraceDebug, TraceExit, TraceOpaque};
+
+#[doc(hidden)]
+pub use crate::{
+    atomic::FnCell,
+    level::{record, record_fields},
+    once::Once,
+    show::{assert_failed, assert_operands_failed, not_implemented},
+    throttle::Throttle,
+    timestamp::{elapsed_since, Elapsed},
+};
+
+pub mod address;
+#[cfg(feature = "async")]
+mod async_io;
+mod atomic;
+mod backend;
+mod buffered;
+#[cfg(feature = "c-api")]
+mod c_api;
+pub mod channel;
+pub mod compress;
+pub mod control;
+pub mod coredump;
+pub mod crc;
+pub mod decode;
+#[cfg(feature = "embassy")]
+pub mod embassy;
+pub mod exit;
+mod frame;
+pub mod framing;
+pub mod handshake;
+mod hexdump;
+#[cfg(all(
+    not(any(target_arch = "arm", target_arch = "aarch64")),
+    feature = "std"
+))]
+pub mod host;
+#[cfg(feature = "macros")]
+mod instrument;
+pub mod interrupt;
+#[cfg(feature = "embedded-io")]
+mod io;
+#[cfg(any(feature = "embedded-io", feature = "embedded-hal-nb"))]
+mod leftover;
+mod level;
+#[cfg(feature = "heapless")]
+mod line;
+#[cfg(feature = "log")]
+pub mod logger;
+mod once;
+pub mod openocd;
+pub mod record;
+mod registry;
+pub mod reset;
+#[cfg(feature = "embedded-hal-nb")]
+mod serial;
+pub mod shell;
+mod show;
+mod sink;
+mod throttle;
+mod timestamp;
+mod trace;
+mod unwrap;
+#[cfg(feature = "values")]
+pub mod value;
+pub mod varint;
+mod wait;
+mod watchdog;
+
 /// Macro for printing to the DCC
+#[cfg(not(feature = "silent"))]
 #[macro_export]
 macro_rules! dprint {
     ($s:expr) => {
@@ -90,7 +408,26 @@ macro_rules! dprint {
     };
 }
 
+/// Macro for printing to the DCC
+///
+/// The `silent` feature is enabled: the arguments are type checked but nothing is printed
+#[cfg(feature = "silent")]
+#[macro_export]
+macro_rules! dprint {
+    ($s:expr) => {
+        if false {
+            $crate::write_str($s)
+        }
+    };
+    ($($tt:tt)*) => {
+        if false {
+            $crate::write_fmt(format_args!($($tt)*))
+        }
+    };
+}
+
 /// Macro for printing to the DCC, with a newline.
+#[cfg(not(feature = "silent"))]
 #[macro_export]
 macro_rules! dprintln {
     () => {
@@ -104,9 +441,518 @@ macro_rules! dprintln {
     };
 }
 
+/// Macro for printing to the DCC, with a newline.
+///
+/// The `silent` feature is enabled: the arguments are type checked but nothing is printed
+#[cfg(feature = "silent")]
+#[macro_export]
+macro_rules! dprintln {
+    () => {
+        if false {
+            $crate::write_str("\n")
+        }
+    };
+    ($s:expr) => {
+        if false {
+            $crate::write_str(concat!($s, "\n"))
+        }
+    };
+    ($s:expr, $($tt:tt)*) => {
+        if false {
+            $crate::write_fmt(format_args!(concat!($s, "\n"), $($tt)*))
+        }
+    };
+}
+
+/// Prints a log record with the given `Level` to the DCC
+///
+/// The record is printed as a line of text: `LEVEL module::path: message`, the same format that
+/// `logger::DccLogger` uses, prefixed with a timestamp if a `TimestampSource` was registered.
+/// Records above `STATIC_MAX_LEVEL` are compiled out; their arguments are type checked but not
+/// evaluated. Records filtered out at runtime (see `set_max_level` and `set_module_filters`) are
+/// skipped without evaluating their arguments.
+///
+/// A message (a string literal) followed by a `;` and `key = value` pairs is a structured record:
+/// the values are formatted with `{:?}` and appended to the message as `key=value`, separated by
+/// spaces, so host tools can split the line into fields. Without the `;` the arguments are
+/// formatting arguments, named or not.
+///
+/// ``` no_run
+/// use arm_dcc::{dinfo, dlog, Level};
+///
+/// # let (mac, retries, speed) = ([0; 6], 3, 100);
+/// dlog!(Level::Warn, "link down; retrying {} more times", retries);
+/// // prints "INFO my_app::net: MAC = [0, 0, 0, 0, 0, 0]"
+/// dinfo!("MAC = {:?}", mac);
+/// // prints "INFO my_app::net: link up speed=100 duplex=\"full\""
+/// dinfo!("link up"; speed = speed, duplex = "full");
+/// ```
+///
+/// Named formatting arguments are formatted into the message:
+///
+/// ```
+/// # #[cfg(all(feature = "std", not(feature = "openocd")))] {
+/// # use std::{cell::RefCell, vec::Vec};
+/// # use arm_dcc::{compress::Decompressor, decode::{Decoder, Item}, handshake};
+/// use arm_dcc::dinfo;
+///
+/// # std::thread_local! {
+/// #     static DECODER: RefCell<Decoder> =
+/// #         RefCell::new(Decoder::with_features(handshake::FEATURES));
+/// #     static DECOMPRESSOR: RefCell<Decompressor> = RefCell::new(Decompressor::new());
+/// #     static TEXT: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+/// # }
+/// # arm_dcc::host::set_output(|word| {
+/// #     TEXT.with(|text| match DECODER.with(|decoder| decoder.borrow_mut().push(word)) {
+/// #         Some(Item::Text(chunk)) => text.borrow_mut().extend_from_slice(chunk.as_bytes()),
+/// #         Some(Item::Compressed(chunk)) => DECOMPRESSOR.with(|decompressor| {
+/// #             let mut decompressor = decompressor.borrow_mut();
+/// #             decompressor.push(chunk.as_bytes(), |byte| text.borrow_mut().push(byte))
+/// #         }),
+/// #         _ => {}
+/// #     })
+/// # });
+/// let x = 7;
+/// // prints "INFO my_app: value 7"
+/// dinfo!("value {x}", x = x);
+/// # arm_dcc::host::clear_output();
+/// # let text = TEXT.with(|text| String::from_utf8(text.borrow().clone()).unwrap());
+/// # assert!(text.contains(": value 7"), "{:?}", text);
+/// # assert!(!text.contains("x="), "{:?}", text);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! dlog {
+    ($level:expr, $msg:literal; $($key:ident = $value:expr),+ $(,)?) => {{
+        let level: $crate::Level = $level;
+        if level.passes($crate::STATIC_MAX_LEVEL) && $crate::log_enabled(level, module_path!()) {
+            $crate::record_fields(
+                level,
+                module_path!(),
+                line!(),
+                $msg,
+                &[$((stringify!($key), &$value as &dyn ::core::fmt::Debug)),+],
+            )
+        }
+    }};
+    ($level:expr, $($arg:tt)+) => {{
+        let level: $crate::Level = $level;
+        if level.passes($crate::STATIC_MAX_LEVEL) && $crate::log_enabled(level, module_path!()) {
+            $crate::record(level, module_path!(), line!(), format_args!($($arg)+))
+        }
+    }};
+}
+
+/// Prints a log record with the `Error` level; see `dlog!`
+#[macro_export]
+macro_rules! derror {
+    ($($arg:tt)+) => {
+        $crate::dlog!($crate::Level::Error, $($arg)+)
+    };
+}
+
+/// Prints a log record with the `Warn` level; see `dlog!`
+#[macro_export]
+macro_rules! dwarn {
+    ($($arg:tt)+) => {
+        $crate::dlog!($crate::Level::Warn, $($arg)+)
+    };
+}
+
+/// Prints a log record with the `Info` level; see `dlog!`
+#[macro_export]
+macro_rules! dinfo {
+    ($($arg:tt)+) => {
+        $crate::dlog!($crate::Level::Info, $($arg)+)
+    };
+}
+
+/// Prints a log record with the `Debug` level; see `dlog!`
+#[macro_export]
+macro_rules! ddebug {
+    ($($arg:tt)+) => {
+        $crate::dlog!($crate::Level::Debug, $($arg)+)
+    };
+}
+
+/// Prints a log record with the `Trace` level; see `dlog!`
+#[macro_export]
+macro_rules! dtrace {
+    ($($arg:tt)+) => {
+        $crate::dlog!($crate::Level::Trace, $($arg)+)
+    };
+}
+
+/// Like `dprint!` but returns `Err` if part of the text couldn't be sent
+///
+/// Sending only fails under the `Lossy` policy (see `set_policy`), when the host doesn't read the
+/// DCC in time and words are dropped. `dprint!` ignores that.
+///
+/// ``` no_run
+/// use arm_dcc::{dwriteln, Policy};
+///
+/// arm_dcc::set_policy(Policy::Lossy { max_spins: 1_000 });
+///
+/// # let sample = 0;
+/// if dwriteln!("sample = {}", sample).is_err() {
+///     // the host is not keeping up; send less data
+/// }
+/// ```
+#[macro_export]
+macro_rules! dwrite {
+    ($s:expr) => {
+        $crate::try_write_str($s)
+    };
+    ($($tt:tt)*) => {
+        $crate::try_write_fmt(format_args!($($tt)*))
+    };
+}
+
+/// Like `dprintln!` but returns `Err` if part of the text couldn't be sent; see `dwrite!`
+#[macro_export]
+macro_rules! dwriteln {
+    () => {
+        $crate::try_write_str("\n")
+    };
+    ($s:expr) => {
+        $crate::try_write_str(concat!($s, "\n"))
+    };
+    ($s:expr, $($tt:tt)*) => {
+        $crate::try_write_fmt(format_args!(concat!($s, "\n"), $($tt)*))
+    };
+}
+
+/// Like `dprint!` but only prints the first time this call site is reached
+///
+/// Each invocation of the macro has its own flag so the message is printed once per call site, not
+/// once per program.
+///
+/// ``` no_run
+/// use arm_dcc::dprint_once;
+///
+/// loop {
+///     # let overrun = true;
+///     if overrun {
+///         dprint_once!("RX FIFO overrun\n");
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! dprint_once {
+    ($($tt:tt)*) => {{
+        static ONCE: $crate::Once = $crate::Once::new();
+        if ONCE.first() {
+            $crate::dprint!($($tt)*)
+        }
+    }};
+}
+
+/// Like `dprintln!` but only prints the first time this call site is reached; see `dprint_once!`
+#[macro_export]
+macro_rules! dprintln_once {
+    ($($tt:tt)*) => {{
+        static ONCE: $crate::Once = $crate::Once::new();
+        if ONCE.first() {
+            $crate::dprintln!($($tt)*)
+        }
+    }};
+}
+
+/// Like `dprintln!` but rate limited, so a message printed from a hot path (e.g. an interrupt
+/// handler) doesn't saturate the DCC
+///
+/// - `dprintln_throttled!(every = N, ...)` prints on the first invocation and then once every `N`
+///   invocations
+/// - `dprintln_throttled!(ms = N, ...)` prints at most once every `N` milliseconds, as reported by
+///   the clock registered with `set_uptime`. Without a clock it prints only once.
+///
+/// Each call site is throttled on its own.
+///
+/// ``` no_run
+/// use arm_dcc::dprintln_throttled;
+///
+/// // in an interrupt handler
+/// # let status = 0;
+/// dprintln_throttled!(every = 1000, "spurious interrupt, status = {:#x}", status);
+/// dprintln_throttled!(ms = 500, "RX overrun");
+/// ```
+#[macro_export]
+macro_rules! dprintln_throttled {
+    (every = $n:expr, $($tt:tt)*) => {{
+        static THROTTLE: $crate::Throttle = $crate::Throttle::new();
+        if THROTTLE.every($n) {
+            $crate::dprintln!($($tt)*)
+        }
+    }};
+    (ms = $period:expr, $($tt:tt)*) => {{
+        static THROTTLE: $crate::Throttle = $crate::Throttle::new();
+        if THROTTLE.ms($period) {
+            $crate::dprintln!($($tt)*)
+        }
+    }};
+}
+
+/// Prints, like `std::dbg!`, the value of an expression to the DCC and returns it
+///
+/// The output is `file:line: expr = value`, with the value formatted with `{:?}`. `ddbg!()`
+/// prints only the location; several expressions are returned as a tuple.
+///
+/// ``` no_run
+/// use arm_dcc::ddbg;
+///
+/// let len = 4;
+/// // prints "src/main.rs:5: len * 2 = 8"
+/// let words = ddbg!(len * 2);
+/// ```
+#[macro_export]
+macro_rules! ddbg {
+    () => {
+        $crate::dprintln!("{}:{}:", file!(), line!())
+    };
+    ($val:expr $(,)?) => {
+        // NOTE `match` extends the lifetime of temporaries, same as `std::dbg!`
+        match $val {
+            tmp => {
+                $crate::dprintln!("{}:{}: {} = {:?}", file!(), line!(), stringify!($val), &tmp);
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::ddbg!($val)),+,)
+    };
+}
+
+/// Prints how long an expression took to evaluate and returns its value
+///
+/// The output is `file:line: expr took N us`, or `N ticks` if the rate of the source is unknown,
+/// as measured with the `TimestampSource`. `dtime!()` prints the location and the current
+/// timestamp.
+///
+/// ``` no_run
+/// use arm_dcc::dtime;
+///
+/// # fn checksum(_: &[u8]) -> u32 { 0 }
+/// # let frame = [0; 64];
+/// // prints "src/main.rs:6: checksum(&frame) took 12 us"
+/// let crc = dtime!(checksum(&frame));
+/// ```
+#[macro_export]
+macro_rules! dtime {
+    () => {
+        match $crate::timestamp() {
+            Some(now) => $crate::dprintln!("{}:{}: {}", file!(), line!(), now),
+            None => $crate::dprintln!("{}:{}: (no timestamp source)", file!(), line!()),
+        }
+    };
+    ($val:expr $(,)?) => {{
+        let start = $crate::timestamp();
+        // NOTE `match` extends the lifetime of temporaries, same as `std::dbg!`
+        match $val {
+            tmp => {
+                let elapsed = $crate::elapsed_since(start);
+                $crate::dprintln!(
+                    "{}:{}: {} took {}",
+                    file!(),
+                    line!(),
+                    stringify!($val),
+                    elapsed
+                );
+                tmp
+            }
+        }
+    }};
+}
+
+/// Prints the expression and then its bytes in the canonical hexdump format; see `hexdump`
+///
+/// The expression can be anything that can be sliced into a `[u8]`, e.g. an array or a slice.
+///
+/// ``` no_run
+/// use arm_dcc::dhexdump;
+///
+/// let packet = [0x45, 0x00, 0x00, 0x54];
+/// // prints "packet:" and then the hexdump
+/// dhexdump!(packet);
+/// ```
+#[macro_export]
+macro_rules! dhexdump {
+    ($bytes:expr $(,)?) => {{
+        $crate::write_str(concat!(stringify!($bytes), ":\n"));
+        $crate::hexdump(&($bytes)[..])
+    }};
+}
+
+/// Asserts that a boolean expression is `true`; on failure the expression (or the message, a
+/// `&str`) and its location are printed to the DCC before panicking
+///
+/// The report is printed without going through `core::fmt`.
+///
+/// ``` no_run
+/// use arm_dcc::dassert;
+///
+/// let ready = true;
+/// dassert!(ready);
+/// dassert!(ready, "the PLL didn't lock");
+/// ```
+#[macro_export]
+macro_rules! dassert {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::assert_failed(stringify!($cond))
+        }
+    };
+    ($cond:expr, $msg:expr $(,)?) => {
+        if !$cond {
+            $crate::assert_failed($msg)
+        }
+    };
+}
+
+/// Asserts that two expressions are equal; on failure both values and the location are printed
+/// to the DCC before panicking
+///
+/// The operands must implement `Show` (and `PartialEq`); unlike `assert_eq!` this doesn't go
+/// through `core::fmt`.
+///
+/// ``` no_run
+/// use arm_dcc::dassert_eq;
+///
+/// let id = 0x2a_u32;
+/// dassert_eq!(id, 42);
+/// ```
+///
+/// prints, when `id` is not 42:
+///
+/// ``` text
+/// assertion failed: `(left == right)` at src/main.rs:4:1
+///   left: `41`
+///  right: `42`
+/// ```
+#[macro_export]
+macro_rules! dassert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if !(*left == *right) {
+                    $crate::assert_operands_failed("==", left, right)
+                }
+            }
+        }
+    };
+}
+
+/// Asserts that two expressions are not equal; see `dassert_eq!`
+#[macro_export]
+macro_rules! dassert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if *left == *right {
+                    $crate::assert_operands_failed("!=", left, right)
+                }
+            }
+        }
+    };
+}
+
+/// Like `todo!`: prints "not yet implemented at file:line:column" to the DCC and then panics
+///
+/// An optional message (a `&str`) is printed after "not yet implemented". The message is printed
+/// before panicking, without going through `core::fmt`, so it shows up even if the panic handler
+/// doesn't report anything.
+///
+/// ``` no_run
+/// use arm_dcc::dtodo;
+///
+/// fn crc16(_bytes: &[u8]) -> u16 {
+///     dtodo!("CRC-16/XMODEM")
+/// }
+/// ```
+#[macro_export]
+macro_rules! dtodo {
+    () => {
+        $crate::not_implemented("not yet implemented", None)
+    };
+    ($msg:expr $(,)?) => {
+        $crate::not_implemented("not yet implemented", Some($msg))
+    };
+}
+
+/// Like `unimplemented!`: prints "not implemented at file:line:column" to the DCC and then panics;
+/// see `dtodo!`
+#[macro_export]
+macro_rules! dunimplemented {
+    () => {
+        $crate::not_implemented("not implemented", None)
+    };
+    ($msg:expr $(,)?) => {
+        $crate::not_implemented("not implemented", Some($msg))
+    };
+}
+
+/// Macro for reading a line sent by the host
+///
+/// `dreadln!(N)` returns a `Result<heapless::String<N>, ReadLineError>`; see `read_line`. This
+/// macro is only available when the `heapless` feature is enabled.
+///
+/// ``` no_run
+/// use arm_dcc::{dprintln, dreadln};
+///
+/// if let Ok(line) = dreadln!(32) {
+///     dprintln!("got: {}", line);
+/// }
+/// ```
+#[cfg(feature = "heapless")]
+#[macro_export]
+macro_rules! dreadln {
+    ($n:expr) => {{
+        let mut line = $crate::heapless::String::<$n>::new();
+        $crate::read_line(&mut line).map(|_| line)
+    }};
+}
+
+/// Formats a message into a `heapless::String` without sending it
+///
+/// `dformat!(N, ...)` returns a `Result<heapless::String<N>, fmt::Error>`; the error means the
+/// message didn't fit in `N` bytes. A message built this way can be sent with a single
+/// `write_str` call, one frame in `packed` mode, or also be shown somewhere else, e.g. on a
+/// display. This macro is only available when the `heapless` feature is enabled.
+///
+/// ``` no_run
+/// use arm_dcc::dformat;
+///
+/// # let (volts, amps) = (12, 3);
+/// if let Ok(status) = dformat!(32, "{} V, {} A", volts, amps) {
+///     arm_dcc::write_str(&status);
+/// }
+/// ```
+#[cfg(feature = "heapless")]
+#[macro_export]
+macro_rules! dformat {
+    ($n:expr, $($arg:tt)+) => {{
+        let mut string = $crate::heapless::String::<$n>::new();
+        ::core::fmt::Write::write_fmt(&mut string, format_args!($($arg)+)).map(|_| string)
+    }};
+}
+
 /// Proxy struct that implements the `fmt::Write`
 ///
-/// The main use case for this is using the `write!` macro
+/// The main use case for this is using the `write!` macro, or handing the DCC to code that only
+/// knows `core::fmt`, e.g. to print `Display` types.
+///
+/// ``` no_run
+/// use core::fmt::Write;
+///
+/// use arm_dcc::Writer;
+///
+/// # let temperature = 25;
+/// // NOTE this operation never returns `Err`
+/// writeln!(Writer, "temperature: {} C", temperature).ok();
+/// ```
+///
+/// The formatting machinery is only linked in if `Writer` (or one of the macros that use it) is
+/// actually used; `write_str` and `write_all` don't depend on it.
 pub struct Writer;
 
 impl fmt::Write for Writer {
@@ -116,49 +962,584 @@ impl fmt::Write for Writer {
     }
 }
 
+/// Handle to the DCC, for use with the I/O traits of other crates
+///
+/// With the `embedded-io` feature enabled this implements the `embedded_io` traits; with the
+/// `embedded-hal-nb` feature enabled, the `embedded_hal_nb::serial` traits. The `async` feature
+/// adds non-blocking, `Poll` based, methods.
+///
+/// ``` ignore
+/// use embedded_io::{Read, Write};
+///
+/// let mut dcc = arm_dcc::Dcc::new();
+/// dcc.write_all(b"> ").ok();
+///
+/// let mut buf = [0; 16];
+/// let n = dcc.read(&mut buf).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Dcc {
+    // bytes of the last word received that `embedded_io::Read::read`, or
+    // `embedded_hal_nb::serial::Read::read`, couldn't return yet
+    #[cfg(any(feature = "embedded-io", feature = "embedded-hal-nb"))]
+    leftover: leftover::Leftover,
+}
+
+impl Dcc {
+    /// Returns a handle to the DCC
+    pub const fn new() -> Self {
+        Dcc {
+            #[cfg(any(feature = "embedded-io", feature = "embedded-hal-nb"))]
+            leftover: leftover::Leftover::new(),
+        }
+    }
+}
+
+/// Proxy struct that implements `fmt::Write` on top of `write_with_timeout`
+///
+/// Unlike `Writer`, the `write!` macro returns `Err` if the host stops consuming data, instead of
+/// blocking forever
+pub struct TimeoutWriter {
+    max_spins: u32,
+}
+
+impl TimeoutWriter {
+    /// Creates a writer that polls the DCC status at most `max_spins` times per word
+    pub const fn new(max_spins: u32) -> Self {
+        TimeoutWriter { max_spins }
+    }
+}
+
+impl fmt::Write for TimeoutWriter {
+    fn write_str(&mut self, s: &str) -> Result<(), fmt::Error> {
+        write_all_with_timeout(s.as_bytes(), self.max_spins).map_err(|_| fmt::Error)
+    }
+}
+
+/// What `write` does when the host is not reading the DCC
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Policy {
+    /// Wait until the host reads the previous word. This is the default
+    Block,
+    /// Give up after polling the status register `max_spins` more times, once the word couldn't
+    /// be sent right away, and count the word as dropped
+    Lossy {
+        /// Status register polls, after the first one, before dropping a word
+        max_spins: u32,
+    },
+}
+
+static LOSSY: AtomicBool = AtomicBool::new(false);
+static MAX_SPINS: AtomicU32 = AtomicU32::new(0);
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+static BYTES_DROPPED: AtomicU32 = AtomicU32::new(0);
+
+/// Changes the policy that `write` (and all the blocking API built on top of it) follows
+///
+/// **NOTE:** In `Lossy` mode words may be dropped from the middle of a packed or binary frame;
+/// host decoders will lose track of the stream when that happens
+pub fn set_policy(policy: Policy) {
+    match policy {
+        Policy::Block => LOSSY.store(false, Ordering::Release),
+        Policy::Lossy { max_spins } => {
+            MAX_SPINS.store(max_spins, Ordering::Relaxed);
+            LOSSY.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Returns the current `write` policy
+pub fn policy() -> Policy {
+    if LOSSY.load(Ordering::Acquire) {
+        Policy::Lossy {
+            max_spins: MAX_SPINS.load(Ordering::Relaxed),
+        }
+    } else {
+        Policy::Block
+    }
+}
+
+/// Returns the number of words that have been dropped in `Lossy` mode
+pub fn dropped() -> u32 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+static WRITTEN: AtomicU32 = AtomicU32::new(0);
+static BYTES_WRITTEN: AtomicU32 = AtomicU32::new(0);
+static MAX_WAIT: AtomicU32 = AtomicU32::new(0);
+
+/// Transfer statistics
+///
+/// The unit of transfer is the word; in the default (not `packed`) mode each byte is sent as one
+/// word. The byte counters count the bytes of payload that went through `write` and the API built
+/// on top of it: one per word of word-extended text, up to 4 per word of a packed or binary frame
+/// and 4 per word written with `write`. Frame headers, sequence numbers and CRCs carry no payload.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Words that have been sent to the host
+    pub words_written: u32,
+    /// Words that have been dropped in `Lossy` mode
+    pub words_dropped: u32,
+    /// Bytes of payload that have been sent to the host, or queued in the transmit FIFO
+    pub bytes_written: u32,
+    /// Bytes of payload that have been dropped in `Lossy` mode
+    pub bytes_dropped: u32,
+    /// Longest busy-wait observed by `write`, in status register polls
+    pub max_wait: u32,
+}
+
+/// Returns the transfer statistics collected so far
+///
+/// **NOTE:** `words_written`, `bytes_written` and `max_wait` are only tracked when the `stats`
+/// feature is enabled; otherwise they are always zero
+pub fn stats() -> Stats {
+    Stats {
+        words_written: WRITTEN.load(Ordering::Relaxed),
+        words_dropped: DROPPED.load(Ordering::Relaxed),
+        bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+        bytes_dropped: BYTES_DROPPED.load(Ordering::Relaxed),
+        max_wait: MAX_WAIT.load(Ordering::Relaxed),
+    }
+}
+
+/// Resets all the transfer statistics to zero
+pub fn reset_stats() {
+    WRITTEN.store(0, Ordering::Relaxed);
+    DROPPED.store(0, Ordering::Relaxed);
+    BYTES_WRITTEN.store(0, Ordering::Relaxed);
+    BYTES_DROPPED.store(0, Ordering::Relaxed);
+    MAX_WAIT.store(0, Ordering::Relaxed);
+}
+
+/// Initializes the DCC output
+///
+/// This sends the handshake frame (see the `handshake` module) so host tooling can detect the
+/// encodings used by the device. Calling this is optional but it should be done before anything
+/// else is written to the DCC.
+///
+/// With the `openocd` feature enabled this sends nothing, as OpenOCD doesn't understand the
+/// handshake frame.
+pub fn init() {
+    handshake::send()
+}
+
+/// Waits for the host to send the `handshake::HOST_READY` word, then does what `init` does
+///
+/// This guarantees that no output is lost because the host was not yet listening. Other words
+/// received while waiting are discarded. After `max_spins` polls of the DCC status this gives up
+/// and returns `Err(Timeout)`, without sending the handshake frame, so the device can proceed
+/// when no host is attached.
+///
+/// **NOTE:** With the `nop` feature enabled this returns `Err(Timeout)` right away
+pub fn init_and_wait(max_spins: u32) -> Result<(), Timeout> {
+    if cfg!(all(target_arch = "arm", feature = "nop")) {
+        return Err(Timeout);
+    }
+
+    for _ in 0..max_spins {
+        if try_read() == Some(handshake::HOST_READY) {
+            init();
+            return Ok(());
+        }
+
+        wait::wait();
+    }
+
+    Err(Timeout)
+}
+
 /// Writes a single word to the DCC
 ///
-/// **NOTE:** This operation is blocking
-#[allow(unused_variables)]
+/// **NOTE:** This operation is blocking, unless the `Lossy` policy is in use. When the transmit
+/// FIFO is enabled (see `interrupt::enable_tx_fifo`) this only blocks while the FIFO is full.
 #[inline(always)]
 pub fn write(word: u32) {
-    match () {
-        #[cfg(not(target_arch = "arm"))]
-        () => unimplemented!(),
-        #[cfg(all(target_arch = "arm", feature = "nop"))]
-        () => {}
-        #[cfg(all(target_arch = "arm", not(feature = "nop"), feature = "inline-asm"))]
-        () => {
-            const W: u32 = 1 << 29;
-
-            unsafe {
-                let mut r: u32;
-                loop {
-                    // busy wait until we can send data
-                    asm!("MRC p14, 0, $0, c0, c1, 0" : "=r"(r) : : : "volatile");
-                    if r & W == 0 {
-                        break;
-                    }
-                }
-                asm!("MCR p14, 0, $0, c0, c5, 0" : : "r"(word) : : "volatile");
+    write_payload(word, 4)
+}
+
+// writes a word that carries `bytes` bytes of payload; see `Stats`
+#[inline(always)]
+pub(crate) fn write_payload(word: u32, bytes: u32) {
+    if is_direct() {
+        return backend::write(word);
+    }
+
+    let policy = policy();
+
+    let mut spins = 0;
+    loop {
+        if try_write(word).is_ok() {
+            if cfg!(feature = "stats") {
+                MAX_WAIT.fetch_max(spins, Ordering::Relaxed);
+                BYTES_WRITTEN.fetch_add(bytes, Ordering::Relaxed);
             }
+
+            return;
         }
-        #[cfg(all(target_arch = "arm", not(feature = "nop"), not(feature = "inline-asm")))]
-        () => {
-            extern "C" {
-                fn __dcc_write(word: u32);
-            }
 
-            unsafe { __dcc_write(word) }
+        // NOTE the word is always tried once so `max_spins: 0` only drops it if it can't be sent
+        // right away
+        if let Policy::Lossy { max_spins } = policy {
+            if spins == max_spins {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                BYTES_DROPPED.fetch_add(bytes, Ordering::Relaxed);
+                return;
+            }
         }
+
+        wait::wait();
+        spins = spins.saturating_add(1);
     }
 }
 
+// whether words can go straight to the transmit register: the `Block` policy, the `spin` wait
+// strategy, no transmit FIFO and no statistics to update
+#[inline(always)]
+pub(crate) fn is_direct() -> bool {
+    policy() == Policy::Block
+        && !interrupt::tx_fifo_enabled()
+        && !cfg!(feature = "stats")
+        && wait::is_spin()
+}
+
 /// Writes the bytes to the DCC
 ///
-/// NOTE: each byte will be word-extended before being `write`-n to the DCC
+/// NOTE: each byte will be word-extended before being `write`-n to the DCC, unless the `packed`
+/// feature is enabled in which case this is equivalent to `write_all_packed`
 pub fn write_all(bytes: &[u8]) {
-    bytes.iter().for_each(|byte| write(u32::from(*byte)))
+    if cfg!(feature = "packed") {
+        write_all_packed(bytes)
+    } else if is_direct() {
+        // NOTE the policy is checked once for all the bytes; the backend sends them from a single
+        // loop, which still waits for TXfull to clear before each word as the transmit register
+        // only holds one word
+        backend::write_bytes(bytes)
+    } else {
+        bytes
+            .iter()
+            .for_each(|byte| write_payload(u32::from(*byte), 1))
+    }
+}
+
+/// Upper half-word of the header word that precedes each frame sent by `write_all_packed`
+pub const PACKED_TAG: u32 = 0xDCC1_0000;
+
+/// Maximum number of bytes in a single `write_all_packed` frame
+///
+/// The length of the frame, in bytes, is encoded in the lower half-word of the header
+pub const PACKED_MAX_LEN: usize = 0xffff;
+
+/// Writes the bytes to the DCC packing 4 of them in each word
+///
+/// The bytes are sent as a `PACKED_TAG | n` header word followed by `ceil(n / 4)` words. The first
+/// byte goes in the least significant byte of a word; the unused bytes of the last word are
+/// zero. Inputs longer than `PACKED_MAX_LEN` are split into several frames. See the `decode`
+/// module for the host side of this encoding. With the `crc` feature each frame is followed by its
+/// CRC; see the `crc` module.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_all_packed(bytes: &[u8]) {
+    for frame in bytes.chunks(PACKED_MAX_LEN) {
+        write_packed_frame(PACKED_TAG | frame.len() as u32, frame)
+    }
+}
+
+// sends the header followed by the bytes packed 4 per word and, with the `crc` feature, the CRC
+pub(crate) fn write_packed_frame(header: u32, bytes: &[u8]) {
+    let mut frame = FrameWriter::new(header);
+    bytes
+        .chunks(4)
+        .for_each(|chunk| frame.write_payload(pack(chunk), chunk.len() as u32));
+    frame.finish()
+}
+
+/// Packs up to 4 bytes into a word; the first byte goes in the least significant byte
+///
+/// This is the encoding used by `write_all_packed` and `read_exact_packed`. Host tooling can use
+/// it to prepare data for the device. Missing bytes are zero.
+pub fn pack(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .take(4)
+        .enumerate()
+        .fold(0, |word, (i, byte)| word | u32::from(*byte) << (8 * i))
+}
+
+/// Upper half-word of the header word that precedes each frame sent by `write_words_framed`
+///
+/// Text sent via `write_all` is word-extended so its words are always below `0x100`; a header
+/// word can't be mistaken for text
+pub const WORDS_TAG: u32 = 0xDCC0_0000;
+
+/// Maximum number of words in a single `write_words_framed` frame
+///
+/// The length of the frame is encoded in the lower half-word of the header
+pub const WORDS_MAX_LEN: usize = 0xffff;
+
+/// Writes the words to the DCC as they are
+///
+/// **NOTE:** This operation is blocking
+pub fn write_words(words: &[u32]) {
+    words.iter().for_each(|word| write(*word))
+}
+
+/// Writes the words to the DCC preceded by a header that identifies them as binary data
+///
+/// The header is `WORDS_TAG | n` where `n` is the number of words that follow it. Slices longer
+/// than `WORDS_MAX_LEN` are split into several frames. With the `crc` feature each frame is
+/// followed by its CRC; see the `crc` module.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_words_framed(words: &[u32]) {
+    for chunk in words.chunks(WORDS_MAX_LEN) {
+        let mut frame = FrameWriter::new(WORDS_TAG | chunk.len() as u32);
+        chunk.iter().for_each(|word| frame.write(*word));
+        frame.finish()
+    }
+}
+
+/// Upper half-word of the header word that precedes each frame sent by `write_bytes_framed`
+///
+/// This is not `0xDCC3`, the upper half-word of `handshake::HOST_READY`, so the words that the
+/// host sends can't be mistaken for the words that the device sends
+pub const BYTES_TAG: u32 = 0xDCC8_0000;
+
+/// Maximum number of bytes in a single `write_bytes_framed` frame
+///
+/// The length of the frame, in bytes, is encoded in the lower half-word of the header
+pub const BYTES_MAX_LEN: usize = 0xffff;
+
+/// Upper half-word of the header word that precedes each frame of the serialized bytes of a value
+/// sent by `value::send_value`
+///
+/// Values have their own tag so consumers of `BYTES_TAG` frames, e.g. `defmt-dcc`, don't see them
+pub const VALUE_TAG: u32 = 0xDCCA_0000;
+
+/// Writes the bytes to the DCC, packed 4 per word, preceded by a header that identifies them as
+/// binary data
+///
+/// This is the binary counterpart of `write_all_packed`: the frame has the same layout but its
+/// header is `BYTES_TAG | n`, so host decoders report the bytes as `decode::Item::Bytes` rather
+/// than as text. Inputs longer than `BYTES_MAX_LEN` are split into several frames. With the `crc`
+/// feature each frame is followed by its CRC; see the `crc` module.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_bytes_framed(bytes: &[u8]) {
+    for frame in bytes.chunks(BYTES_MAX_LEN) {
+        write_packed_frame(BYTES_TAG | frame.len() as u32, frame)
+    }
+}
+
+/// Reads a single word sent by the host
+///
+/// **NOTE:** This operation is blocking. With the `nop` feature enabled this returns `0` right
+/// away.
+pub fn read() -> u32 {
+    if cfg!(all(target_arch = "arm", feature = "nop")) {
+        return 0;
+    }
+
+    // busy wait until there's data to read
+    while dscr() & RXFULL == 0 {
+        wait::wait();
+    }
+
+    dtrrx()
+}
+
+/// Fills `buf` with bytes sent by the host
+///
+/// Each received word carries one byte in its least significant byte; the rest of the word is
+/// ignored. So the host must send `buf.len()` words. If the `packed` feature is enabled this is
+/// equivalent to `read_exact_packed`.
+///
+/// **NOTE:** This operation is blocking
+pub fn read_exact(buf: &mut [u8]) {
+    if cfg!(feature = "packed") {
+        read_exact_packed(buf)
+    } else {
+        buf.iter_mut().for_each(|byte| *byte = read() as u8)
+    }
+}
+
+/// Fills `buf` with bytes sent by the host, 4 bytes per word
+///
+/// The host must send `ceil(buf.len() / 4)` words encoded as `pack` does: the first byte in the
+/// least significant byte of the word. The unused bytes of the last word are ignored.
+///
+/// **NOTE:** This operation is blocking
+pub fn read_exact_packed(buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(4) {
+        let word = read();
+
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = (word >> (8 * i)) as u8;
+        }
+    }
+}
+
+/// Reads a single word sent by the host, if there's one
+///
+/// Returns `None` if the host has not written anything since the last read
+#[inline(always)]
+pub fn try_read() -> Option<u32> {
+    if dscr() & RXFULL == 0 {
+        None
+    } else {
+        Some(dtrrx())
+    }
+}
+
+/// Returns `true` if an external debugger has enabled halting debug-mode
+///
+/// This reports the HDBGen bit of the DBGDSCR register, which debuggers like XSDB and OpenOCD set
+/// when they attach to the core. It can be used to skip logging when no host is listening, as
+/// `write` blocks forever in that case.
+///
+/// **NOTE:** A debugger may be attached without enabling halting debug-mode so this is a
+/// heuristic. With the `nop` feature enabled, and on ARMv8 (where this bit is not part of the
+/// registers accessible to software), this always returns `false`.
+pub fn is_debugger_attached() -> bool {
+    read_status().halting_debug_enabled()
+}
+
+/// Snapshot of the DCC state, as reported by the DBGDSCR register
+///
+/// **NOTE:** The COMMTX / COMMRX interrupts are not enabled through the DBGDSCR register; these
+/// signals are routed to the interrupt controller (e.g. GIC or VIM) and are masked there.
+///
+/// On AArch64 the status comes from the MDCCSR_EL0 register, which only has the `tx_full` and
+/// `rx_full` flags; all the other flags are reported as cleared. On ARMv8-R the HDBGen and INTdis
+/// bits are not part of the DBGDSCRint register so the matching flags are always cleared. ARMv6
+/// cores have no INTdis bit; `interrupts_disabled` is meaningless on them. On ARMv4T / ARMv5 cores
+/// the status comes from the EmbeddedICE comms control register (or, with the `xscale` feature,
+/// from the TXRXCTRL register), which only has the `tx_full` and `rx_full` flags. On Cortex-M
+/// cores `tx_full` reflects ITM stimulus port 0, `halting_debug_enabled` reports the C_DEBUGEN bit
+/// of the DHCSR register and all the other flags are cleared.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DccStatus {
+    bits: u32,
+}
+
+impl DccStatus {
+    /// Returns the raw value of the DBGDSCR register
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// DBGDTRTX has not been read by the host yet; a `write` would block
+    pub fn tx_full(&self) -> bool {
+        self.bits & TXFULL != 0
+    }
+
+    /// DBGDTRRX holds a word sent by the host that has not been read yet
+    pub fn rx_full(&self) -> bool {
+        self.bits & RXFULL != 0
+    }
+
+    /// Halting debug-mode has been enabled by an external debugger (HDBGen)
+    pub fn halting_debug_enabled(&self) -> bool {
+        self.bits & HDBGEN != 0
+    }
+
+    /// Monitor debug-mode is enabled (MDBGen)
+    pub fn monitor_debug_enabled(&self) -> bool {
+        self.bits & MDBGEN != 0
+    }
+
+    /// User mode access to the DCC is disabled (UDCCdis)
+    pub fn user_dcc_disabled(&self) -> bool {
+        self.bits & UDCCDIS != 0
+    }
+
+    /// IRQ and FIQ are masked while the core is being debugged (INTdis)
+    pub fn interrupts_disabled(&self) -> bool {
+        self.bits & INTDIS != 0
+    }
+}
+
+/// Reads the current DCC status
+///
+/// With the `nop` feature enabled all the flags are reported as cleared
+pub fn read_status() -> DccStatus {
+    DccStatus { bits: dscr() }
+}
+
+/// Error returned by the non-blocking API when the DCC transmit register is full
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WouldBlock;
+
+/// Writes a single word to the DCC, if the channel is free
+///
+/// Returns `Err(WouldBlock)` if the host has not yet consumed the previously written word. When
+/// the transmit FIFO is enabled (see `interrupt::enable_tx_fifo`) the word is queued instead, so
+/// it's sent after the words queued before it, and this returns `Err(WouldBlock)` if the FIFO is
+/// full.
+#[inline(always)]
+pub fn try_write(word: u32) -> Result<(), WouldBlock> {
+    if interrupt::tx_fifo_enabled() {
+        interrupt::enqueue(word)
+    } else {
+        try_write_dtr(word)
+    }
+}
+
+// writes the word straight to the transmit register, bypassing the transmit FIFO
+#[inline(always)]
+pub(crate) fn try_write_dtr(word: u32) -> Result<(), WouldBlock> {
+    if dscr() & TXFULL != 0 {
+        Err(WouldBlock)
+    } else {
+        dtrtx(word);
+
+        if cfg!(feature = "stats") {
+            WRITTEN.fetch_add(1, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when the host did not consume the DCC data within the retry budget
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Timeout;
+
+/// Writes a single word to the DCC, giving up after `max_spins` polls of the status register
+///
+/// This never blocks forever so it's safe to use when no debugger may be attached
+#[inline(always)]
+pub fn write_with_timeout(word: u32, max_spins: u32) -> Result<(), Timeout> {
+    for _ in 0..max_spins {
+        if try_write(word).is_ok() {
+            return Ok(());
+        }
+
+        wait::wait();
+    }
+
+    Err(Timeout)
+}
+
+/// Writes the bytes to the DCC, giving up after `max_spins` status polls on any single byte
+///
+/// NOTE: each byte will be word-extended before being `write_with_timeout`-n to the DCC
+pub fn write_all_with_timeout(bytes: &[u8], max_spins: u32) -> Result<(), Timeout> {
+    bytes
+        .iter()
+        .try_for_each(|byte| write_with_timeout(u32::from(*byte), max_spins))
+}
+
+/// Writes as many bytes as possible to the DCC without blocking
+///
+/// Returns the number of bytes that were sent; the remaining bytes (`&bytes[n..]`) were not sent
+/// because the channel became full
+///
+/// NOTE: each byte will be word-extended before being `try_write`-n to the DCC
+pub fn try_write_all(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take_while(|byte| try_write(u32::from(**byte)).is_ok())
+        .count()
 }
 
 #[doc(hidden)]
@@ -168,7 +1549,50 @@ pub fn write_fmt(args: fmt::Arguments) {
     Writer.write_fmt(args).ok();
 }
 
-/// Writes the string to the DCC
+/// Writes the string to the DCC, or to the sink registered with `set_sink`
 pub fn write_str(string: &str) {
-    write_all(string.as_bytes())
+    sink::write_text(string.as_bytes())
 }
+
+#[doc(hidden)]
+pub fn try_write_fmt(args: fmt::Arguments) -> fmt::Result {
+    use core::fmt::Write;
+
+    let before = dropped();
+    Writer.write_fmt(args)?;
+    check_dropped(before)
+}
+
+#[doc(hidden)]
+pub fn try_write_str(string: &str) -> fmt::Result {
+    let before = dropped();
+    write_str(string);
+    check_dropped(before)
+}
+
+// NOTE words dropped by an interrupt handler that preempted the write are also counted here
+fn check_dropped(before: u32) -> fmt::Result {
+    if dropped() == before {
+        Ok(())
+    } else {
+        Err(fmt::Error)
+    }
+}
+
+/// INTdis (interrupts disable) bit of the DBGDSCR register
+const INTDIS: u32 = 1 << 11;
+
+/// UDCCdis (user mode access to DCC disable) bit of the DBGDSCR register
+const UDCCDIS: u32 = 1 << 12;
+
+/// HDBGen (halting debug-mode enable) bit of the DBGDSCR register
+const HDBGEN: u32 = 1 << 14;
+
+/// MDBGen (monitor debug-mode enable) bit of the DBGDSCR register
+const MDBGEN: u32 = 1 << 15;
+
+/// TXfull bit of the DBGDSCR register
+const TXFULL: u32 = 1 << 29;
+
+/// RXfull bit of the DBGDSCR register
+const RXFULL: u32 = 1 << 30;
@@ -0,0 +1,58 @@
+use heapless::String;
+
+/// Error returned by `read_line`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReadLineError {
+    /// The line didn't fit in the buffer; the rest of the line was discarded
+    Overflow,
+    /// The line is not valid UTF-8; the buffer was cleared
+    Utf8,
+}
+
+/// Reads bytes sent by the host into `line` until a newline (`\n`) is received
+///
+/// `line` is cleared first. The newline, and a carriage return (`\r`) right before it, are not
+/// included in `line`. Each received word carries one byte, or 4 bytes if the `packed` feature is
+/// enabled, as in `read_exact`. In `packed` mode the bytes that follow the newline in its word are
+/// discarded, so the host must start each line in a new word.
+///
+/// **NOTE:** This operation is blocking
+pub fn read_line<const N: usize>(line: &mut String<N>) -> Result<(), ReadLineError> {
+    line.clear();
+
+    let per_word = if cfg!(feature = "packed") { 4 } else { 1 };
+
+    // NOTE(unsafe) the contents are checked to be valid UTF-8 before returning
+    let bytes = unsafe { line.as_mut_vec() };
+    let mut overflow = false;
+    'line: loop {
+        let word = crate::read();
+
+        for i in 0..per_word {
+            let byte = (word >> (8 * i)) as u8;
+
+            if byte == b'\n' {
+                break 'line;
+            }
+
+            if bytes.push(byte).is_err() {
+                overflow = true;
+            }
+        }
+    }
+
+    if bytes.last() == Some(&b'\r') {
+        bytes.pop();
+    }
+
+    if core::str::from_utf8(bytes).is_err() {
+        bytes.clear();
+        return Err(ReadLineError::Utf8);
+    }
+
+    if overflow {
+        Err(ReadLineError::Overflow)
+    } else {
+        Ok(())
+    }
+}
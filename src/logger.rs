@@ -0,0 +1,82 @@
+//! `log` backend
+//!
+//! `DccLogger` implements the `log::Log` trait so records logged with the `log` macros, e.g. by
+//! drivers and other third party crates, are printed to the DCC. Each record is printed as a line
+//! of text: `LEVEL target: message`, like the records of `dlog!`.
+//!
+//! Records above `STATIC_MAX_LEVEL` (see the `max-level-*` features) or filtered out at runtime, by
+//! target (see `set_max_level` and `set_module_filters`), are dropped. `init` sets the maximum
+//! level of `log` to `STATIC_MAX_LEVEL` so the host can raise the runtime filter later. To also
+//! remove the `log` call sites from the binary, use the `max_level_*` features of the `log` crate.
+//!
+//! This module is only available when the `log` feature is enabled.
+//!
+//! ``` no_run
+//! use log::{info, LevelFilter};
+//!
+//! arm_dcc::logger::init(LevelFilter::Info).ok();
+//!
+//! // prints "INFO my_app: booted"
+//! info!(target: "my_app", "booted");
+//! ```
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::STATIC_MAX_LEVEL;
+
+/// A `log::Log` implementation that prints the records to the DCC
+pub struct DccLogger;
+
+static LOGGER: DccLogger = DccLogger;
+
+impl Log for DccLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+            && crate::log_enabled(metadata.level().into(), metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        crate::record(
+            record.level().into(),
+            record.target(),
+            record.line().unwrap_or(0),
+            *record.args(),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Registers `DccLogger` as the global logger and sets the runtime filter (see `set_max_level`) to
+/// `level`
+///
+/// Returns an error if a logger has already been registered
+#[cfg(not(dcc_no_atomics))]
+pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(STATIC_MAX_LEVEL.into());
+    crate::set_max_level(level.into());
+    Ok(())
+}
+
+/// Registers `DccLogger` as the global logger and sets the runtime filter (see `set_max_level`) to
+/// `level`
+///
+/// Returns an error if a logger has already been registered
+///
+/// **NOTE:** This target has no atomic instructions; this function must not be preempted by code
+/// that logs or registers a logger, e.g. call it before enabling interrupts
+#[cfg(dcc_no_atomics)]
+pub fn init(level: LevelFilter) -> Result<(), SetLoggerError> {
+    // NOTE(unsafe) single core target; the caller makes sure nothing else runs concurrently
+    unsafe {
+        log::set_logger_racy(&LOGGER)?;
+        log::set_max_level_racy(STATIC_MAX_LEVEL.into());
+    }
+    crate::set_max_level(level.into());
+    Ok(())
+}
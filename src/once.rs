@@ -0,0 +1,28 @@
+//! State of the `dprint_once!` and `dprintln_once!` macros
+
+use crate::atomic::{AtomicBool, Ordering};
+
+/// Flag behind the `dprint_once!` and `dprintln_once!` macros
+pub struct Once {
+    done: AtomicBool,
+}
+
+impl Once {
+    /// Creates a flag that hasn't been set yet
+    pub const fn new() -> Self {
+        Once {
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets the flag; returns `true` if this was the first call
+    pub fn first(&self) -> bool {
+        !self.done.swap(true, Ordering::Relaxed)
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Once::new()
+    }
+}
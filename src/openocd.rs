@@ -0,0 +1,87 @@
+//! OpenOCD debug messages
+//!
+//! OpenOCD understands the DCC protocol of its `libdcc` target library: once enabled with the
+//! `target_request debugmsgs enable` command, OpenOCD polls the DCC and prints the debug messages
+//! sent by the device in its telnet / GDB session, with no extra host tooling. The first word of
+//! each request carries the request type in its least significant byte:
+//!
+//! - ASCII message: `n << 16 | 0x01` followed by the `n` bytes packed 4 per word, as `pack` does
+//! - hex message: `n << 16 | 4 << 8 | 0x01` followed by the `n` words, printed in hexadecimal
+//! - character: `c << 16 | 0x02`
+//! - trace point: `number << 8 | 0x00`
+//!
+//! The functions of this module send these requests; `trace` and `trace_data` are built on top of
+//! them. With the `openocd` feature enabled the text API of this crate (`write_str`, `Writer`,
+//! `dprint!`, `dprintln!`, the log macros, etc.) sends its output as ASCII messages.
+//!
+//! ``` text
+//! > target_request debugmsgs enable
+//! > resume
+//! Hello, world!
+//! ```
+//!
+//! **NOTE:** OpenOCD only understands these requests: the binary encodings of this crate (see the
+//! `decode` module) must not be used in this mode. `init` doesn't send the handshake frame when
+//! the `openocd` feature is enabled.
+
+/// Request type of a trace point
+const TRACE_POINT: u32 = 0x00;
+
+/// Request type of ASCII and hex messages
+const DEBUG_MSG: u32 = 0x01;
+
+/// Request type of a single character
+const DEBUG_CHAR: u32 = 0x02;
+
+/// Maximum number of bytes, or words, in a single message
+///
+/// The length of the message is encoded in the upper half-word of the request word
+pub const MAX_LEN: usize = 0xffff;
+
+/// Sends the bytes as ASCII messages
+///
+/// Inputs longer than `MAX_LEN` are split into several messages.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_all(bytes: &[u8]) {
+    for message in bytes.chunks(MAX_LEN) {
+        crate::write_payload((message.len() as u32) << 16 | DEBUG_MSG, 0);
+
+        message
+            .chunks(4)
+            .for_each(|chunk| crate::write_payload(crate::pack(chunk), chunk.len() as u32))
+    }
+}
+
+/// Sends the string as ASCII messages
+///
+/// **NOTE:** This operation is blocking
+pub fn write_str(string: &str) {
+    write_all(string.as_bytes())
+}
+
+/// Sends a single character
+pub fn write_char(c: u8) {
+    crate::write_payload(u32::from(c) << 16 | DEBUG_CHAR, 1)
+}
+
+/// Sends the words as hex messages, which OpenOCD prints in hexadecimal
+///
+/// Inputs longer than `MAX_LEN` are split into several messages.
+///
+/// **NOTE:** This operation is blocking
+pub fn write_words(words: &[u32]) {
+    for message in words.chunks(MAX_LEN) {
+        crate::write_payload((message.len() as u32) << 16 | 4 << 8 | DEBUG_MSG, 0);
+
+        message.iter().for_each(|word| crate::write(*word))
+    }
+}
+
+/// Signals that the trace point with the given number was hit
+///
+/// OpenOCD counts the hits of each trace point; see its `trace point` command. Only the lower 24
+/// bits of `number` are sent.
+pub fn trace_point(number: u32) {
+    crate::write_payload(number << 8 | TRACE_POINT, 0)
+}
@@ -0,0 +1,176 @@
+//! Binary log records
+//!
+//! With the `binary-log` feature enabled, `dlog!` and friends, and `logger::DccLogger`, send each
+//! log record in a compact binary format instead of as a line of text; the `dcc-log` tool turns
+//! the stream back into lines of text. The level and the module path, which make up most of a
+//! short text line, are sent as one word each.
+//!
+//! A record is sent as a single frame: a `RECORD_TAG | n` header word, the `n` bytes of the
+//! formatted message packed 4 per word, as in `write_all_packed`, and then 4 words:
+//!
+//! - the level of the record
+//! - the `module_hash` of the module path
+//! - the line number (`0` if unknown)
+//! - the time in milliseconds, read from the `TimestampSource` or else from the clock registered
+//!   with `set_uptime` (`0` if neither is available)
+//!
+//! With the `sequence` and `crc` features the frame carries a sequence number and a CRC, as the
+//! other framed encodings do. `decode::Decoder` reports the message as `decode::Item::RecordBytes`
+//! and the 4 words, once the whole frame has been received, as `decode::Item::Record`.
+//!
+//! The module path is sent once, before the first record of that module, in a frame of the same
+//! layout: the path in place of the message, `MODULE_KIND` in place of the level, the hash, and
+//! `0` as both line number and time. The device keeps track of the last few modules it has
+//! described; the description of a module is sent again after it falls out of that set.
+//!
+//! **NOTE:** Frames must not interleave: an interrupt handler that logs while a record is being
+//! sent corrupts it, as with the other framed encodings.
+
+/// Upper half-word of the header word that precedes each record frame
+pub const RECORD_TAG: u32 = 0xDCC9_0000;
+
+/// Maximum number of bytes in the message of a record; longer messages are truncated
+///
+/// The length of the message is encoded in the lower half-word of the header
+pub const RECORD_MAX_LEN: usize = 0xffff;
+
+/// First word after the message of a frame that describes a module; the first word after the
+/// message of a record is its level, which is never `0`
+pub const MODULE_KIND: u32 = 0;
+
+/// Hash of a module path, as used in records: 32-bit FNV-1a of its bytes
+pub const fn module_hash(path: &str) -> u32 {
+    let bytes = path.as_bytes();
+    let mut hash = 0x811c_9dc5_u32;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    hash
+}
+
+#[cfg(feature = "binary-log")]
+pub(crate) use self::device::write;
+
+#[cfg(feature = "binary-log")]
+mod device {
+    use core::fmt::{self, Write};
+
+    use crate::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        frame::FrameWriter,
+        Level,
+    };
+
+    use super::{module_hash, MODULE_KIND, RECORD_MAX_LEN, RECORD_TAG};
+
+    const MODULES: usize = 16;
+
+    // NOTE `0` means "empty slot"; a module whose hash is `0` is never described
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicU32 = AtomicU32::new(0);
+    static DESCRIBED: [AtomicU32; MODULES] = [EMPTY; MODULES];
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    // counts the bytes of the formatted message
+    struct Counter(usize);
+
+    impl fmt::Write for Counter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    // packs the message into the words of the frame; `left` is the length given in the header
+    struct Message {
+        frame: FrameWriter,
+        word: u32,
+        len: usize,
+        left: usize,
+    }
+
+    impl Message {
+        fn push(&mut self, byte: u8) {
+            if self.left == 0 {
+                return;
+            }
+
+            self.word |= u32::from(byte) << (8 * self.len);
+            self.len += 1;
+            self.left -= 1;
+
+            if self.len == 4 || self.left == 0 {
+                self.frame.write_payload(self.word, self.len as u32);
+                self.word = 0;
+                self.len = 0;
+            }
+        }
+
+        // NOTE the message is padded with zeros if it's shorter this time around, e.g. because
+        // some `Display` implementation is not deterministic, so the frame has the length given in
+        // its header
+        fn finish(mut self, fields: [u32; 4]) {
+            while self.left != 0 {
+                self.push(0);
+            }
+
+            fields.iter().for_each(|field| self.frame.write(*field));
+            self.frame.finish()
+        }
+    }
+
+    impl fmt::Write for Message {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            s.bytes().for_each(|byte| self.push(byte));
+            Ok(())
+        }
+    }
+
+    // NOTE the message is formatted twice: once to get its length, which goes in the header, and
+    // once to send it
+    fn write_frame(fields: [u32; 4], args: fmt::Arguments) {
+        let mut counter = Counter(0);
+        // NOTE these operations never return `Err`
+        counter.write_fmt(args).ok();
+        let len = counter.0.min(RECORD_MAX_LEN);
+
+        let mut message = Message {
+            frame: FrameWriter::new(RECORD_TAG | len as u32),
+            word: 0,
+            len: 0,
+            left: len,
+        };
+        message.write_fmt(args).ok();
+        message.finish(fields)
+    }
+
+    fn describe(target: &str, hash: u32) {
+        if DESCRIBED
+            .iter()
+            .any(|slot| slot.load(Ordering::Relaxed) == hash)
+        {
+            return;
+        }
+
+        // NOTE racing writers may describe a module twice, which is harmless
+        let next = NEXT.load(Ordering::Relaxed);
+        NEXT.store((next + 1) % MODULES, Ordering::Relaxed);
+        DESCRIBED[next].store(hash, Ordering::Relaxed);
+
+        write_frame([MODULE_KIND, hash, 0, 0], format_args!("{}", target));
+    }
+
+    pub(crate) fn write(level: Level, target: &str, line: u32, args: fmt::Arguments) {
+        let hash = module_hash(target);
+        describe(target, hash);
+
+        let uptime = crate::timestamp()
+            .and_then(|timestamp| timestamp.millis())
+            .or_else(crate::watchdog::uptime)
+            .unwrap_or(0) as u32;
+        write_frame([level as u32, hash, line, uptime], args);
+    }
+}
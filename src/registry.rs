@@ -0,0 +1,53 @@
+//! Storage for values that are registered once and read from any context
+
+use core::cell::UnsafeCell;
+
+use crate::atomic::{AtomicU8, Ordering};
+
+const UNSET: u8 = 0;
+const BUSY: u8 = 1;
+const SET: u8 = 2;
+
+/// A value that's registered once, e.g. a `&'static dyn Trait`, which doesn't fit in an atomic
+pub(crate) struct Registry<T> {
+    state: AtomicU8,
+    value: UnsafeCell<Option<T>>,
+}
+
+// NOTE(unsafe) `value` is written once, before `state` becomes `SET`, and only read after that
+unsafe impl<T: Send + Sync> Sync for Registry<T> {}
+
+impl<T: Copy> Registry<T> {
+    pub(crate) const fn new() -> Self {
+        Registry {
+            state: AtomicU8::new(UNSET),
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// Returns `Err` if a value was already registered
+    pub(crate) fn set(&self, value: T) -> Result<(), ()> {
+        // NOTE a failed `set` leaves `state` as is so `get` keeps returning the registered value
+        if self
+            .state
+            .compare_exchange(UNSET, BUSY, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(());
+        }
+
+        // NOTE(unsafe) `state` was `UNSET` so no one else is accessing `value`
+        unsafe { *self.value.get() = Some(value) }
+        self.state.store(SET, Ordering::Release);
+        Ok(())
+    }
+
+    pub(crate) fn get(&self) -> Option<T> {
+        if self.state.load(Ordering::Acquire) == SET {
+            // NOTE(unsafe) `value` is never written again once `state` is `SET`
+            unsafe { *self.value.get() }
+        } else {
+            None
+        }
+    }
+}
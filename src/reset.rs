@@ -0,0 +1,151 @@
+//! Reporting of the cause of the last reset
+//!
+//! `init_report` does what `init` does and then prints, as the first line of text, why the device
+//! was reset, e.g. "reset cause: watchdog". Where that information lives is device specific; it's
+//! provided by an implementation of the `ResetCause` trait. This module includes implementations
+//! for the Zynq-7000 and Zynq UltraScale+ devices.
+//!
+//! ``` no_run
+//! use arm_dcc::reset::ZynqMp;
+//!
+//! // first thing after boot
+//! arm_dcc::init_report(&ZynqMp);
+//! ```
+
+use core::{
+    fmt::{self, Write},
+    ptr,
+};
+
+use crate::Writer;
+
+/// Why the device was reset
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cause {
+    /// Power-on reset
+    PowerOn,
+    /// Reset pin asserted by external hardware
+    External,
+    /// A watchdog expired
+    Watchdog,
+    /// Reset requested by software
+    Software,
+    /// Reset requested by a debugger
+    Debugger,
+    /// Another device specific cause
+    Other(&'static str),
+    /// The cause is not known
+    Unknown,
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Cause::PowerOn => "power-on reset",
+            Cause::External => "external reset",
+            Cause::Watchdog => "watchdog",
+            Cause::Software => "software reset",
+            Cause::Debugger => "debugger reset",
+            Cause::Other(cause) => cause,
+            Cause::Unknown => "unknown",
+        })
+    }
+}
+
+/// A source of the cause of the last reset, usually a register of the reset controller
+pub trait ResetCause {
+    /// Returns the cause of the last reset
+    fn cause(&self) -> Cause;
+}
+
+/// Does what `init` does and then prints the cause of the last reset
+///
+/// **NOTE:** This operation is blocking
+pub fn init_report<R>(reset: &R)
+where
+    R: ResetCause,
+{
+    crate::init();
+
+    // NOTE this operation never returns `Err`
+    writeln!(Writer, "reset cause: {}", reset.cause()).ok();
+}
+
+/// The REBOOT_STATUS register of the Zynq-7000 SLCR
+///
+/// The BootROM fills this register from the reset status of the device; it's preserved by all
+/// resets other than power-on
+pub struct Zynq7000;
+
+impl Zynq7000 {
+    const REBOOT_STATUS: usize = 0xF800_0258;
+
+    const POR: u32 = 1 << 22;
+    const SRST_B: u32 = 1 << 21;
+    const DBG_RST: u32 = 1 << 20;
+    const SLC_RST: u32 = 1 << 19;
+    const AWDT1_RST: u32 = 1 << 18;
+    const AWDT0_RST: u32 = 1 << 17;
+    const SWDT_RST: u32 = 1 << 16;
+}
+
+impl ResetCause for Zynq7000 {
+    fn cause(&self) -> Cause {
+        // NOTE(unsafe) reading REBOOT_STATUS has no side effects
+        let status = unsafe { ptr::read_volatile(Self::REBOOT_STATUS as *const u32) };
+
+        if status & (Self::SWDT_RST | Self::AWDT0_RST | Self::AWDT1_RST) != 0 {
+            Cause::Watchdog
+        } else if status & Self::SLC_RST != 0 {
+            Cause::Software
+        } else if status & Self::DBG_RST != 0 {
+            Cause::Debugger
+        } else if status & Self::SRST_B != 0 {
+            Cause::External
+        } else if status & Self::POR != 0 {
+            Cause::PowerOn
+        } else {
+            Cause::Unknown
+        }
+    }
+}
+
+/// The RESET_REASON register of the Zynq UltraScale+ CRL_APB
+///
+/// Watchdog timeouts are signaled through the error manager, which the PMU firmware usually
+/// turns into a PS-only or a system reset; they are reported as such
+pub struct ZynqMp;
+
+impl ZynqMp {
+    const RESET_REASON: usize = 0xFF5E_0220;
+
+    const PSONLY_RESET_REQ: u32 = 1 << 6;
+    const SRST: u32 = 1 << 5;
+    const SOFT: u32 = 1 << 4;
+    const DEBUG_SYS: u32 = 1 << 3;
+    const INTERNAL: u32 = 1 << 1;
+    const EXTERNAL: u32 = 1 << 0;
+}
+
+impl ResetCause for ZynqMp {
+    fn cause(&self) -> Cause {
+        // NOTE(unsafe) reading RESET_REASON has no side effects
+        let reason = unsafe { ptr::read_volatile(Self::RESET_REASON as *const u32) };
+
+        if reason & Self::PSONLY_RESET_REQ != 0 {
+            Cause::Other("PS-only reset")
+        } else if reason & Self::SOFT != 0 {
+            Cause::Software
+        } else if reason & Self::DEBUG_SYS != 0 {
+            Cause::Debugger
+        } else if reason & Self::SRST != 0 {
+            Cause::External
+        } else if reason & Self::INTERNAL != 0 {
+            Cause::Other("internal power-on reset")
+        } else if reason & Self::EXTERNAL != 0 {
+            Cause::PowerOn
+        } else {
+            Cause::Unknown
+        }
+    }
+}
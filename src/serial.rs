@@ -0,0 +1,47 @@
+//! `embedded-hal-nb` implementations for `Dcc`
+//!
+//! The serial traits move one byte at a time so each byte written travels in its own word, the
+//! least significant byte, even when the `packed` feature is enabled; the host decoder accepts such
+//! word-extended bytes at any point of the stream. Each word read carries one byte, or 4 bytes if
+//! the `packed` feature is enabled, as in `read_exact`; `read` returns them one at a time.
+
+use core::convert::Infallible;
+
+use embedded_hal_nb::{
+    nb,
+    serial::{ErrorType, Read, Write},
+};
+
+use crate::{leftover::Leftover, Dcc, TXFULL};
+
+impl ErrorType for Dcc {
+    type Error = Infallible;
+}
+
+impl Read<u8> for Dcc {
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        let mut byte = [0];
+        if self.leftover.take(&mut byte) == 0 {
+            let word = crate::try_read().ok_or(nb::Error::WouldBlock)?;
+            self.leftover = Leftover::from(word);
+            self.leftover.take(&mut byte);
+        }
+
+        Ok(byte[0])
+    }
+}
+
+impl Write<u8> for Dcc {
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        crate::try_write(u32::from(byte)).map_err(|_| nb::Error::WouldBlock)
+    }
+
+    // NOTE the host has consumed all the data once the transmit register is empty
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        if crate::dscr() & TXFULL == 0 {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Interactive command shell over the DCC
+//!
+//! The host sends lines of text, one byte per word, or 4 bytes per word if the `packed` feature is
+//! enabled, as in `read_exact`; each line is a command name followed by whitespace separated
+//! arguments. As in `read_line`, the bytes that follow a newline in its word are discarded so in
+//! `packed` mode the host must start each line in a new word. The shell runs the matching command,
+//! whose output goes to the DCC like any other `dprint!` output. The `help` command lists all the
+//! commands.
+//!
+//! ``` no_run
+//! use core::str::SplitWhitespace;
+//!
+//! use arm_dcc::{
+//!     dprintln,
+//!     shell::{Command, Shell},
+//! };
+//!
+//! fn echo(args: SplitWhitespace) {
+//!     for arg in args {
+//!         dprintln!("{}", arg);
+//!     }
+//! }
+//!
+//! static COMMANDS: &[Command] = &[Command {
+//!     name: "echo",
+//!     help: "prints its arguments, one per line",
+//!     run: echo,
+//! }];
+//!
+//! let mut shell = Shell::<64>::new(COMMANDS);
+//! shell.run()
+//! ```
+
+use core::{
+    fmt::Write,
+    str::{self, SplitWhitespace},
+};
+
+use crate::Writer;
+
+/// A shell command
+pub struct Command {
+    /// Name used to invoke the command
+    pub name: &'static str,
+    /// One line description shown by `help`
+    pub help: &'static str,
+    /// The command itself; receives the arguments that followed the command name
+    pub run: fn(SplitWhitespace),
+}
+
+/// Command shell that buffers up to `N` bytes of input
+pub struct Shell<const N: usize> {
+    commands: &'static [Command],
+    buf: [u8; N],
+    len: usize,
+    overflow: bool,
+}
+
+impl<const N: usize> Shell<N> {
+    /// Creates a shell that provides the given commands
+    pub const fn new(commands: &'static [Command]) -> Self {
+        Shell {
+            commands,
+            buf: [0; N],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Processes the input the host has sent so far, running the commands that have been
+    /// completely received
+    ///
+    /// This doesn't block (commands themselves may)
+    pub fn poll(&mut self) {
+        while let Some(word) = crate::try_read() {
+            self.push_word(word);
+        }
+    }
+
+    /// Prints a prompt and then processes commands forever
+    pub fn run(&mut self) -> ! {
+        crate::write_str("> ");
+
+        loop {
+            self.push_word(crate::read());
+        }
+    }
+
+    fn push_word(&mut self, word: u32) {
+        let per_word = if cfg!(feature = "packed") { 4 } else { 1 };
+
+        for i in 0..per_word {
+            let byte = (word >> (8 * i)) as u8;
+            self.push(byte);
+
+            if byte == b'\n' {
+                break;
+            }
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if byte != b'\n' {
+            if self.len < N {
+                self.buf[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+
+            return;
+        }
+
+        if self.overflow {
+            // NOTE this operation never returns `Err`
+            writeln!(Writer, "error: line is longer than {} bytes", N).ok();
+        } else {
+            match str::from_utf8(&self.buf[..self.len]) {
+                Ok(line) => self.execute(line),
+                Err(_) => crate::write_str("error: line is not valid UTF-8\n"),
+            }
+        }
+
+        self.len = 0;
+        self.overflow = false;
+        crate::write_str("> ");
+    }
+
+    fn execute(&self, line: &str) {
+        let mut args = line.split_whitespace();
+
+        let name = match args.next() {
+            Some(name) => name,
+            None => return,
+        };
+
+        // NOTE these operations never return `Err`
+        if name == "help" {
+            for command in self.commands {
+                writeln!(Writer, "{} - {}", command.name, command.help).ok();
+            }
+            crate::write_str("help - lists the available commands\n");
+        } else if let Some(command) = self.commands.iter().find(|c| c.name == name) {
+            (command.run)(args)
+        } else {
+            writeln!(Writer, "error: unknown command `{}`; try `help`", name).ok();
+        }
+    }
+}
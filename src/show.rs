@@ -0,0 +1,173 @@
+//! Printing of values without `core::fmt`, for the `dassert*!` macros
+
+use core::panic::Location;
+
+/// Types that can print themselves to the DCC without going through `core::fmt`
+///
+/// This is what the `dassert*!` macros use to print the values of the operands. Integers are
+/// printed in decimal.
+pub trait Show {
+    /// Writes `self` to the DCC
+    fn show(&self);
+}
+
+impl<T> Show for &T
+where
+    T: Show + ?Sized,
+{
+    fn show(&self) {
+        (**self).show()
+    }
+}
+
+impl Show for str {
+    fn show(&self) {
+        crate::write_str(self)
+    }
+}
+
+impl Show for bool {
+    fn show(&self) {
+        crate::write_str(if *self { "true" } else { "false" })
+    }
+}
+
+impl Show for char {
+    fn show(&self) {
+        crate::write_str(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<T> Show for [T]
+where
+    T: Show,
+{
+    fn show(&self) {
+        crate::write_str("[");
+        for (i, item) in self.iter().enumerate() {
+            if i != 0 {
+                crate::write_str(", ");
+            }
+            item.show();
+        }
+        crate::write_str("]");
+    }
+}
+
+impl<T, const N: usize> Show for [T; N]
+where
+    T: Show,
+{
+    fn show(&self) {
+        self[..].show()
+    }
+}
+
+fn show_u64(mut n: u64) {
+    let mut buf = [0; 20];
+    let mut i = buf.len();
+    loop {
+        i -= 1;
+        buf[i] = b'0' + (n % 10) as u8;
+        n /= 10;
+
+        if n == 0 {
+            break;
+        }
+    }
+
+    crate::sink::write_text(&buf[i..])
+}
+
+fn show_i64(n: i64) {
+    if n < 0 {
+        crate::write_str("-");
+    }
+
+    show_u64(n.unsigned_abs())
+}
+
+macro_rules! unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl Show for $ty {
+                fn show(&self) {
+                    show_u64(*self as u64)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! signed {
+    ($($ty:ty),*) => {
+        $(
+            impl Show for $ty {
+                fn show(&self) {
+                    show_i64(*self as i64)
+                }
+            }
+        )*
+    };
+}
+
+unsigned!(u8, u16, u32, u64, usize);
+signed!(i8, i16, i32, i64, isize);
+
+fn show_location(location: &Location) {
+    crate::write_str(location.file());
+    crate::write_str(":");
+    location.line().show();
+    crate::write_str(":");
+    location.column().show();
+}
+
+/// Implementation detail of `dassert!`
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_failed(condition: &str) -> ! {
+    crate::write_str("assertion failed: ");
+    crate::write_str(condition);
+    crate::write_str(" at ");
+    show_location(Location::caller());
+    crate::write_str("\n");
+
+    panic!("assertion failed")
+}
+
+/// Implementation detail of `dassert_eq!` and `dassert_ne!`
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_operands_failed<L, R>(op: &str, left: &L, right: &R) -> !
+where
+    L: Show + ?Sized,
+    R: Show + ?Sized,
+{
+    crate::write_str("assertion failed: `(left ");
+    crate::write_str(op);
+    crate::write_str(" right)` at ");
+    show_location(Location::caller());
+    crate::write_str("\n  left: `");
+    left.show();
+    crate::write_str("`\n right: `");
+    right.show();
+    crate::write_str("`\n");
+
+    panic!("assertion failed")
+}
+
+/// Implementation detail of `dtodo!` and `dunimplemented!`
+#[doc(hidden)]
+#[track_caller]
+pub fn not_implemented(what: &'static str, message: Option<&str>) -> ! {
+    crate::write_str(what);
+    if let Some(message) = message {
+        crate::write_str(": ");
+        crate::write_str(message);
+    }
+    crate::write_str(" at ");
+    show_location(Location::caller());
+    crate::write_str("\n");
+
+    panic!("{}", what)
+}
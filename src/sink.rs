@@ -0,0 +1,82 @@
+//! Redirection of the text output to something other than the DCC
+
+use core::fmt;
+
+use crate::{registry::Registry, Dcc};
+
+static SINK: Registry<&'static dyn DccSink> = Registry::new();
+
+/// Destination of the text printed by the macros of this crate
+///
+/// By default text goes to the DCC, through `Dcc`'s implementation of this trait. Registering
+/// another sink with `set_sink` redirects `dprint!`, `dprintln!`, the log macros, `Writer` and
+/// the rest of the text API built on top of them, e.g. to a UART, to a RAM buffer or to a test
+/// double, while keeping the same front end.
+///
+/// ``` no_run
+/// use arm_dcc::DccSink;
+///
+/// struct Uart;
+///
+/// impl DccSink for Uart {
+///     fn write_bytes(&self, bytes: &[u8]) {
+///         for byte in bytes {
+///             // e.g. wait for space in the TX FIFO and write `byte` to the data register
+///             # let _ = byte;
+///         }
+///     }
+/// }
+///
+/// arm_dcc::set_sink(&Uart).ok();
+///
+/// // goes to the UART
+/// arm_dcc::dprintln!("Hello, world!");
+/// ```
+///
+/// Only text is redirected: the word level API (`write`, `write_words_framed`, etc.), and what's
+/// built on it, e.g. binary log records and core dumps, is specific to the DCC.
+pub trait DccSink: Sync {
+    /// Sends the bytes of the text that's being printed
+    fn write_bytes(&self, bytes: &[u8]);
+}
+
+/// Sends the bytes with `write_all`, with `openocd::write_all` if the `openocd` feature is enabled
+/// or compressed (see the `compress` module) if the `compress` feature is enabled
+impl DccSink for Dcc {
+    fn write_bytes(&self, bytes: &[u8]) {
+        if cfg!(feature = "openocd") {
+            crate::openocd::write_all(bytes)
+        } else {
+            #[cfg(feature = "compress")]
+            crate::compress::write_text(bytes);
+            #[cfg(not(feature = "compress"))]
+            crate::write_all(bytes)
+        }
+    }
+}
+
+/// The error returned by `set_sink` when a sink was already registered
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SetSinkError;
+
+impl fmt::Display for SetSinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sink was already registered")
+    }
+}
+
+/// Registers the sink that receives the text printed by this crate, instead of the DCC
+///
+/// The sink can only be registered once, usually during initialization; text printed before
+/// that goes to the DCC.
+pub fn set_sink(sink: &'static dyn DccSink) -> Result<(), SetSinkError> {
+    SINK.set(sink).map_err(|_| SetSinkError)
+}
+
+/// Sends text to the registered sink, or to the DCC
+pub(crate) fn write_text(bytes: &[u8]) {
+    match SINK.get() {
+        Some(sink) => sink.write_bytes(bytes),
+        None => Dcc::new().write_bytes(bytes),
+    }
+}
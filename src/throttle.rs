@@ -0,0 +1,61 @@
+//! Rate limiting of the `dprintln_throttled!` macro, by number of calls or by time
+
+use crate::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// State behind the `dprintln_throttled!` macro
+///
+/// The counter and the timestamp are not updated atomically as a pair; an interrupt that prints
+/// from the same call site at the same time may let an extra message through.
+pub struct Throttle {
+    count: AtomicU32,
+    last: AtomicU32,
+    started: AtomicBool,
+}
+
+impl Throttle {
+    /// Creates a throttle that lets the next message through
+    pub const fn new() -> Self {
+        Throttle {
+            count: AtomicU32::new(0),
+            last: AtomicU32::new(0),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns `true` on the first call and then once every `n` calls
+    pub fn every(&self, n: u32) -> bool {
+        let count = self.count.fetch_add(1, Ordering::Relaxed);
+        match count.checked_rem(n) {
+            Some(rem) => rem == 0,
+            // `n == 0` disables throttling
+            None => true,
+        }
+    }
+
+    /// Returns `true` on the first call and then if at least `period` milliseconds have passed
+    /// since it last returned `true`; without a clock (see `set_uptime`) only the first call
+    /// returns `true`
+    pub fn ms(&self, period: u32) -> bool {
+        let first = !self.started.swap(true, Ordering::Relaxed);
+
+        match crate::watchdog::uptime() {
+            // NOTE the uptime is truncated; `wrapping_sub` keeps working after it wraps around
+            Some(now) => {
+                let now = now as u32;
+                if first || now.wrapping_sub(self.last.load(Ordering::Relaxed)) >= period {
+                    self.last.store(now, Ordering::Relaxed);
+                    true
+                } else {
+                    false
+                }
+            }
+            None => first,
+        }
+    }
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle::new()
+    }
+}
@@ -0,0 +1,169 @@
+use core::fmt;
+
+use crate::registry::Registry;
+
+static SOURCE: Registry<&'static dyn TimestampSource> = Registry::new();
+
+/// A clock used as the time base of log lines, `dtime!` and the panic handler
+///
+/// Usually a cycle counter, e.g. the PMCCNTR register of ARMv7 cores, or a free running timer.
+///
+/// ``` ignore
+/// use arm_dcc::TimestampSource;
+///
+/// // Cortex-R5 at 400 MHz; the PMU cycle counter must have been enabled (PMCR.E, PMCNTENSET.C)
+/// struct Pmu;
+///
+/// impl TimestampSource for Pmu {
+///     fn ticks(&self) -> u64 {
+///         let ticks: u32;
+///         unsafe { core::arch::asm!("mrc p15, 0, {}, c9, c13, 0", out(reg) ticks) }
+///         ticks.into()
+///     }
+///
+///     fn bits(&self) -> u32 {
+///         32
+///     }
+///
+///     fn tick_hz(&self) -> u32 {
+///         400_000_000
+///     }
+/// }
+///
+/// arm_dcc::set_timestamp_source(&Pmu).ok();
+///
+/// // prints `[   0.001234] INFO my_app: link up`
+/// arm_dcc::dinfo!("link up");
+/// ```
+pub trait TimestampSource: Sync {
+    /// Returns the current value of the counter
+    fn ticks(&self) -> u64;
+
+    /// Returns the width of the counter in bits; after `2^bits - 1` the counter wraps around to `0`
+    fn bits(&self) -> u32 {
+        64
+    }
+
+    /// Returns the rate of the counter in Hz, or `0` if unknown; raw ticks are printed then
+    fn tick_hz(&self) -> u32;
+}
+
+/// The error returned by `set_timestamp_source` when a source was already registered
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SetTimestampSourceError;
+
+impl fmt::Display for SetTimestampSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a timestamp source was already registered")
+    }
+}
+
+/// Registers the clock that timestamps log lines, `dtime!` and panic messages
+///
+/// Once registered every line printed by `dlog!` and friends, and `logger::DccLogger`, is prefixed
+/// with a timestamp, and binary log records (see the `record` module) carry it instead of the
+/// uptime. The source can only be registered once.
+pub fn set_timestamp_source(
+    source: &'static dyn TimestampSource,
+) -> Result<(), SetTimestampSourceError> {
+    SOURCE.set(source).map_err(|_| SetTimestampSourceError)
+}
+
+/// Reads the clock registered with `set_timestamp_source`
+pub fn timestamp() -> Option<Timestamp> {
+    SOURCE.get().map(|source| Timestamp {
+        ticks: source.ticks(),
+        bits: source.bits(),
+        tick_hz: source.tick_hz(),
+    })
+}
+
+/// A reading of the `TimestampSource`
+///
+/// It's displayed as seconds and microseconds, e.g. `[   0.001234]`, or as raw ticks, e.g.
+/// `[    123456]`, if the rate of the source is unknown.
+#[derive(Clone, Copy, Debug)]
+pub struct Timestamp {
+    ticks: u64,
+    bits: u32,
+    tick_hz: u32,
+}
+
+impl Timestamp {
+    /// Returns the value of the counter
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the rate of the counter in Hz, or `0` if unknown
+    pub fn tick_hz(&self) -> u32 {
+        self.tick_hz
+    }
+
+    /// Returns the number of ticks elapsed since `earlier`, taking the wrap around of the
+    /// counter into account
+    pub fn ticks_since(&self, earlier: Timestamp) -> u64 {
+        let mask = if self.bits >= 64 {
+            u64::MAX
+        } else {
+            (1 << self.bits) - 1
+        };
+        self.ticks.wrapping_sub(earlier.ticks) & mask
+    }
+
+    /// Converts the timestamp to milliseconds, if the rate of the counter is known
+    #[cfg(feature = "binary-log")]
+    pub(crate) fn millis(&self) -> Option<u64> {
+        let (secs, fraction) = self.split(1_000)?;
+        Some(secs * 1_000 + fraction)
+    }
+
+    // whole seconds plus the rest of `ticks` in units of `1 / scale` seconds
+    fn split(&self, scale: u64) -> Option<(u64, u64)> {
+        split(self.ticks, self.tick_hz, scale)
+    }
+}
+
+// NOTE `ticks % tick_hz` is below 2^32 so the multiplication can't overflow
+fn split(ticks: u64, tick_hz: u32, scale: u64) -> Option<(u64, u64)> {
+    if tick_hz == 0 {
+        None
+    } else {
+        let tick_hz = u64::from(tick_hz);
+        Some((ticks / tick_hz, ticks % tick_hz * scale / tick_hz))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.split(1_000_000) {
+            Some((secs, micros)) => write!(f, "[{:4}.{:06}]", secs, micros),
+            None => write!(f, "[{:10}]", self.ticks),
+        }
+    }
+}
+
+/// Implementation detail of `dtime!`
+#[doc(hidden)]
+pub fn elapsed_since(start: Option<Timestamp>) -> Elapsed {
+    Elapsed(start.and_then(|start| {
+        let end = timestamp()?;
+        Some((end.ticks_since(start), end.tick_hz))
+    }))
+}
+
+/// Implementation detail of `dtime!`: the time between two readings of the source
+#[doc(hidden)]
+pub struct Elapsed(Option<(u64, u32)>);
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some((ticks, tick_hz)) => match split(ticks, tick_hz, 1_000_000) {
+                Some((secs, micros)) => write!(f, "{} us", secs * 1_000_000 + micros),
+                None => write!(f, "{} ticks", ticks),
+            },
+            None => f.write_str("? (no timestamp source)"),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+//! Trace points
+
+use crate::openocd;
+
+/// Signals that the trace point `id` was hit
+///
+/// A trace point is a single word, `id << 8`, so it's much cheaper than printing a message and can
+/// be placed in hot code. The word is an OpenOCD trace point request (see the `openocd` module):
+/// OpenOCD counts the hits of each trace point, see its `trace point` command. Only the lower 24
+/// bits of `id` are sent.
+///
+/// ``` no_run
+/// const RX_OVERRUN: u32 = 1;
+///
+/// arm_dcc::trace(RX_OVERRUN);
+/// ```
+///
+/// **NOTE:** This operation is blocking
+#[inline]
+pub fn trace(id: u32) {
+    openocd::trace_point(id)
+}
+
+/// Signals that the trace point `id` was hit and sends a word of data along with it
+///
+/// The trace point is followed by an OpenOCD hex message that holds `word`, so 3 words are sent;
+/// OpenOCD prints the data in hexadecimal.
+///
+/// **NOTE:** This operation is blocking
+#[inline]
+pub fn trace_data(id: u32, word: u32) {
+    openocd::trace_point(id);
+    openocd::write_words(&[word])
+}
@@ -0,0 +1,82 @@
+//! `unwrap` and `expect` that report the failure to the DCC
+
+use core::{
+    fmt::{self, Write},
+    panic::Location,
+};
+
+use crate::Writer;
+
+/// Extension trait for `Result` and `Option` that reports the failure to the DCC before panicking
+///
+/// The error value (formatted with `{:?}`), or the message, and the location of the call are
+/// printed to the DCC so they're visible even when the panic handler doesn't report anything.
+///
+/// ``` no_run
+/// use arm_dcc::UnwrapDcc;
+///
+/// let baud: u32 = "115200".parse().unwrap_dcc();
+/// let divisor = 100_000_000_u32.checked_div(baud).expect_dcc("baud rate can't be zero");
+/// ```
+pub trait UnwrapDcc<T> {
+    /// Returns the contained value, or reports the failure and panics
+    fn unwrap_dcc(self) -> T;
+
+    /// Returns the contained value, or reports `msg` (and the error, if any) and panics
+    fn expect_dcc(self, msg: &str) -> T;
+}
+
+impl<T, E> UnwrapDcc<T> for Result<T, E>
+where
+    E: fmt::Debug,
+{
+    #[track_caller]
+    fn unwrap_dcc(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(e) => failed("called `Result::unwrap_dcc()` on an `Err` value", Some(&e)),
+        }
+    }
+
+    #[track_caller]
+    fn expect_dcc(self, msg: &str) -> T {
+        match self {
+            Ok(value) => value,
+            Err(e) => failed(msg, Some(&e)),
+        }
+    }
+}
+
+impl<T> UnwrapDcc<T> for Option<T> {
+    #[track_caller]
+    fn unwrap_dcc(self) -> T {
+        match self {
+            Some(value) => value,
+            None => failed("called `Option::unwrap_dcc()` on a `None` value", None),
+        }
+    }
+
+    #[track_caller]
+    fn expect_dcc(self, msg: &str) -> T {
+        match self {
+            Some(value) => value,
+            None => failed(msg, None),
+        }
+    }
+}
+
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn failed(msg: &str, error: Option<&dyn fmt::Debug>) -> ! {
+    let location = Location::caller();
+
+    // NOTE these operations never return `Err`
+    Writer.write_str(msg).ok();
+    if let Some(error) = error {
+        write!(Writer, ": {:?}", error).ok();
+    }
+    writeln!(Writer, " at {}", location).ok();
+
+    panic!("{}", msg)
+}
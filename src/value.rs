@@ -0,0 +1,140 @@
+//! Structured values serialized with postcard
+//!
+//! `send_value` serializes any `serde::Serialize` type with postcard and sends it as:
+//!
+//! - a binary words frame (see `write_words_framed`) with `VALUE_MAGIC`
+//! - the serialized value as one or more `VALUE_TAG` frames, which have the layout of binary bytes
+//!   frames (see `write_bytes_framed`)
+//! - a binary words frame with `VALUE_END`, or with `VALUE_ABORT` if serialization failed halfway
+//!
+//! The value is serialized straight into the frames, so no buffer the size of the value is
+//! needed. On the host `ValueDecoder` (`std` feature) collects the serialized bytes of each value
+//! from the decoded word stream; deserialize them with `postcard::from_bytes`.
+//!
+//! ``` ignore
+//! #[derive(serde::Serialize)]
+//! struct Telemetry {
+//!     temperature: i16,
+//!     voltage: u16,
+//! }
+//!
+//! arm_dcc::value::send_value(&Telemetry {
+//!     temperature: 25,
+//!     voltage: 3300,
+//! })
+//! .ok();
+//! ```
+
+use postcard::ser_flavors::Flavor;
+use serde::Serialize;
+
+use crate::{BufferedBytesWriter, VALUE_TAG};
+
+/// First word of a value
+pub const VALUE_MAGIC: u32 = 0x5641_4C00;
+
+/// Last word of a value
+pub const VALUE_END: u32 = 0x5641_4CFF;
+
+/// Last word of a value that couldn't be serialized; the bytes sent so far must be discarded
+pub const VALUE_ABORT: u32 = 0x5641_4CFE;
+
+// NOTE postcard hands out the value in small pieces, often a single byte
+struct Frames(BufferedBytesWriter<64>);
+
+impl Flavor for Frames {
+    type Output = ();
+
+    fn try_push(&mut self, byte: u8) -> postcard::Result<()> {
+        self.0.write(&[byte]);
+        Ok(())
+    }
+
+    fn finalize(mut self) -> postcard::Result<()> {
+        self.0.flush();
+        Ok(())
+    }
+}
+
+/// Serializes `value` with postcard and sends it over the DCC
+///
+/// Returns `Err` if `value` can't be serialized with postcard, e.g. a sequence of unknown length;
+/// the host is told to discard the value in that case.
+///
+/// **NOTE:** This operation is blocking
+pub fn send_value<T>(value: &T) -> postcard::Result<()>
+where
+    T: Serialize + ?Sized,
+{
+    crate::write_words_framed(&[VALUE_MAGIC]);
+
+    let frames = Frames(BufferedBytesWriter::with_tag(VALUE_TAG));
+    let result = postcard::serialize_with_flavor(value, frames);
+
+    let end = if result.is_ok() {
+        VALUE_END
+    } else {
+        VALUE_ABORT
+    };
+    crate::write_words_framed(&[end]);
+    result
+}
+
+#[cfg(feature = "std")]
+pub use self::host::ValueDecoder;
+
+#[cfg(feature = "std")]
+mod host {
+    use std::vec::Vec;
+
+    use crate::decode::Item;
+
+    use super::{VALUE_ABORT, VALUE_END, VALUE_MAGIC};
+
+    /// Collects the values sent with `send_value` from the decoded word stream
+    ///
+    /// ``` ignore
+    /// use arm_dcc::{decode::Decoder, value::ValueDecoder};
+    ///
+    /// let mut decoder = Decoder::new();
+    /// let mut values = ValueDecoder::new();
+    /// for word in words {
+    ///     if let Some(item) = decoder.push(word) {
+    ///         if let Some(bytes) = values.push(&item) {
+    ///             let telemetry: Telemetry = postcard::from_bytes(&bytes)?;
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[derive(Clone, Debug, Default)]
+    pub struct ValueDecoder {
+        // serialized bytes of the value being received; `None` if not receiving a value
+        value: Option<Vec<u8>>,
+    }
+
+    impl ValueDecoder {
+        /// Creates a new decoder
+        pub const fn new() -> Self {
+            ValueDecoder { value: None }
+        }
+
+        /// Feeds the next item of the word stream into the decoder
+        ///
+        /// Returns the serialized bytes of a value once all of them have been received. Items that
+        /// are not part of a value are ignored. A value that contains a corrupted frame, or
+        /// that lost frames, is discarded.
+        pub fn push(&mut self, item: &Item) -> Option<Vec<u8>> {
+            match (*item, &mut self.value) {
+                (Item::Word(VALUE_MAGIC), value) => *value = Some(Vec::new()),
+                (Item::Value(chunk), Some(value)) => value.extend_from_slice(chunk.as_bytes()),
+                (Item::Word(VALUE_END), value) => return value.take(),
+                (Item::Word(VALUE_ABORT), value)
+                | (Item::BadCrc, value)
+                | (Item::Lost(_), value) => *value = None,
+                _ => {}
+            }
+
+            None
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Variable length encoding of integers (unsigned LEB128)
+//!
+//! Each byte carries 7 bits of the value, least significant group first; the most significant bit
+//! of a byte is set if more bytes follow. Small values, which are the common case for timestamp
+//! deltas, lengths and IDs, take fewer bytes than their fixed size encoding: values below 128
+//! take a single byte. Signed integers are zigzag encoded first (`0, -1, 1, -2, ..` become `0, 1,
+//! 2, 3, ..`) so small negative values are short too.
+//!
+//! Use these helpers to build compact binary payloads and send them with `write_bytes_framed`.
+//! (`value::send_value` already gets this encoding from postcard.)
+//!
+//! ```
+//! use arm_dcc::varint;
+//!
+//! let mut buffer = [0; 2 * varint::MAX_LEN_U32];
+//! let mut n = varint::encode_u32(300, &mut buffer);
+//! n += varint::encode_i32(-2, &mut buffer[n..]);
+//! assert_eq!(&buffer[..n], &[0xac, 0x02, 0x03]);
+//!
+//! let (a, m) = varint::decode_u32(&buffer[..n]).unwrap();
+//! let (b, _) = varint::decode_i32(&buffer[m..n]).unwrap();
+//! assert_eq!((a, b), (300, -2));
+//! ```
+
+/// Maximum size of the encoding of a `u32` or an `i32`
+pub const MAX_LEN_U32: usize = 5;
+
+/// Maximum size of the encoding of a `u64` or an `i64`
+pub const MAX_LEN_U64: usize = 10;
+
+/// Error returned by the `decode_*` functions
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The input ends in the middle of the encoding
+    Truncated,
+    /// The encoded value doesn't fit in the integer type
+    Overflow,
+}
+
+/// Encodes `value` into `output` and returns the length of the encoding
+///
+/// # Panics
+///
+/// If `output` is too short; `MAX_LEN_U32` bytes are always enough
+pub fn encode_u32(value: u32, output: &mut [u8]) -> usize {
+    encode_u64(u64::from(value), output)
+}
+
+/// Encodes `value` into `output` and returns the length of the encoding
+///
+/// # Panics
+///
+/// If `output` is too short; `MAX_LEN_U64` bytes are always enough
+pub fn encode_u64(mut value: u64, output: &mut [u8]) -> usize {
+    let mut n = 0;
+    loop {
+        let byte = value as u8 & 0x7f;
+        value >>= 7;
+
+        if value == 0 {
+            output[n] = byte;
+            return n + 1;
+        }
+
+        output[n] = byte | 0x80;
+        n += 1;
+    }
+}
+
+/// Zigzag encodes `value` into `output` and returns the length of the encoding
+///
+/// # Panics
+///
+/// If `output` is too short; `MAX_LEN_U32` bytes are always enough
+pub fn encode_i32(value: i32, output: &mut [u8]) -> usize {
+    encode_u32(((value << 1) ^ (value >> 31)) as u32, output)
+}
+
+/// Zigzag encodes `value` into `output` and returns the length of the encoding
+///
+/// # Panics
+///
+/// If `output` is too short; `MAX_LEN_U64` bytes are always enough
+pub fn encode_i64(value: i64, output: &mut [u8]) -> usize {
+    encode_u64(((value << 1) ^ (value >> 63)) as u64, output)
+}
+
+/// Decodes a `u32` from the start of `input`; returns it along with the length of its encoding
+pub fn decode_u32(input: &[u8]) -> Result<(u32, usize), DecodeError> {
+    let (value, n) = decode_u64(input)?;
+
+    if value > u64::from(u32::MAX) {
+        return Err(DecodeError::Overflow);
+    }
+
+    Ok((value as u32, n))
+}
+
+/// Decodes a `u64` from the start of `input`; returns it along with the length of its encoding
+pub fn decode_u64(input: &[u8]) -> Result<(u64, usize), DecodeError> {
+    let mut value = 0;
+    for (i, byte) in input.iter().enumerate() {
+        let bits = u64::from(byte & 0x7f);
+
+        // NOTE the 10th byte can only carry the most significant bit of the value, and must be
+        // the last one
+        if i == MAX_LEN_U64 - 1 && *byte > 1 {
+            return Err(DecodeError::Overflow);
+        }
+
+        value |= bits << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+
+    Err(DecodeError::Truncated)
+}
+
+/// Decodes a zigzag encoded `i32` from the start of `input`; returns it along with the length of
+/// its encoding
+pub fn decode_i32(input: &[u8]) -> Result<(i32, usize), DecodeError> {
+    let (value, n) = decode_u32(input)?;
+    Ok(((value >> 1) as i32 ^ -((value & 1) as i32), n))
+}
+
+/// Decodes a zigzag encoded `i64` from the start of `input`; returns it along with the length of
+/// its encoding
+pub fn decode_i64(input: &[u8]) -> Result<(i64, usize), DecodeError> {
+    let (value, n) = decode_u64(input)?;
+    Ok(((value >> 1) as i64 ^ -((value & 1) as i64), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_i64, decode_u32, decode_u64, encode_i64, encode_u64, DecodeError, MAX_LEN_U64,
+    };
+
+    #[test]
+    fn extremes() {
+        let mut buffer = [0; MAX_LEN_U64];
+        for value in [0, 1, 127, 128, u64::from(u32::MAX), u64::MAX].iter() {
+            let n = encode_u64(*value, &mut buffer);
+            assert_eq!(decode_u64(&buffer[..n]), Ok((*value, n)));
+        }
+        for value in [0, -1, 1, i64::MIN, i64::MAX].iter() {
+            let n = encode_i64(*value, &mut buffer);
+            assert_eq!(decode_i64(&buffer[..n]), Ok((*value, n)));
+        }
+        assert_eq!(encode_u64(u64::MAX, &mut buffer), MAX_LEN_U64);
+    }
+
+    #[test]
+    fn overflow_10th_byte() {
+        let mut input = [0xff; MAX_LEN_U64];
+
+        // the 10th byte only has room for the most significant bit
+        input[9] = 0x01;
+        assert_eq!(decode_u64(&input), Ok((u64::MAX, MAX_LEN_U64)));
+        input[9] = 0x02;
+        assert_eq!(decode_u64(&input), Err(DecodeError::Overflow));
+
+        // and it must be the last byte, even if the input ends there
+        input[9] = 0x81;
+        assert_eq!(decode_u64(&input), Err(DecodeError::Overflow));
+        assert_eq!(decode_u64(&[0x80; 11]), Err(DecodeError::Overflow));
+    }
+
+    #[test]
+    fn overflow_u32() {
+        assert_eq!(
+            decode_u32(&[0xff, 0xff, 0xff, 0xff, 0x10]),
+            Err(DecodeError::Overflow)
+        );
+        assert_eq!(
+            decode_u32(&[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            Ok((u32::MAX, 5))
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        assert_eq!(decode_u64(&[]), Err(DecodeError::Truncated));
+        assert_eq!(decode_u64(&[0xac]), Err(DecodeError::Truncated));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::atomic::{AtomicU8, FnCell, Ordering};
+
+/// What the busy-wait loops of this crate do between polls of the DCC status
+#[derive(Clone, Copy)]
+pub enum Wait {
+    /// Poll the status register as fast as possible. This is the default
+    Spin,
+    /// Execute `core::hint::spin_loop` (the `YIELD` hint) between polls
+    SpinLoopHint,
+    /// Execute `WFE` between polls
+    ///
+    /// **NOTE:** The DCC doesn't generate events so the core sleeps until the next event (`SEV`)
+    /// or interrupt arrives
+    Wfe,
+    /// Call this function between polls, e.g. to yield to a RTOS scheduler
+    Custom(fn()),
+}
+
+const SPIN: u8 = 0;
+const SPIN_LOOP_HINT: u8 = 1;
+const WFE: u8 = 2;
+const CUSTOM: u8 = 3;
+
+static KIND: AtomicU8 = AtomicU8::new(SPIN);
+static CUSTOM_FN: FnCell<fn()> = FnCell::new();
+
+/// Changes the busy-wait strategy used by `write`, `write_with_timeout` and the API built on top
+/// of them
+///
+/// **NOTE:** Any strategy other than `Wait::Spin` makes `write` poll the DCC status from Rust,
+/// instead of using the (slightly faster) external assembly routine
+pub fn set_wait(wait: Wait) {
+    let kind = match wait {
+        Wait::Spin => SPIN,
+        Wait::SpinLoopHint => SPIN_LOOP_HINT,
+        Wait::Wfe => WFE,
+        Wait::Custom(f) => {
+            CUSTOM_FN.set(f);
+            CUSTOM
+        }
+    };
+
+    KIND.store(kind, Ordering::Release);
+}
+
+/// Returns `true` if the default strategy, `Wait::Spin`, is in use
+pub(crate) fn is_spin() -> bool {
+    KIND.load(Ordering::Relaxed) == SPIN
+}
+
+/// Waits once, according to the current strategy
+pub(crate) fn wait() {
+    match KIND.load(Ordering::Acquire) {
+        SPIN_LOOP_HINT => core::hint::spin_loop(),
+        WFE => crate::backend::wfe(),
+        CUSTOM => {
+            // NOTE `CUSTOM_FN` is set before `KIND` is set to `CUSTOM`
+            if let Some(f) = CUSTOM_FN.get() {
+                f()
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,90 @@
+//! Diagnostics for the early warning interrupt of a watchdog
+//!
+//! `watchdog_report_at` prints where the core was and what the application was doing before the
+//! watchdog resets the device; `set_uptime` registers the clock that this report, the throttled
+//! macros and the binary log records read the time from.
+
+use core::{
+    fmt::{self, Write},
+    mem,
+};
+
+use crate::{atomic::FnCell, Writer};
+
+static UPTIME: FnCell<fn() -> u64> = FnCell::new();
+static STATE: FnCell<fn(&mut Writer) -> fmt::Result> = FnCell::new();
+
+/// Registers the function that `watchdog_report_at` and `dprintln_throttled!` call to get the time
+/// since boot, in milliseconds
+pub fn set_uptime(uptime: fn() -> u64) {
+    UPTIME.set(uptime);
+}
+
+pub(crate) fn uptime() -> Option<u64> {
+    UPTIME.get().map(|uptime| uptime())
+}
+
+/// Registers the function that `watchdog_report_at` calls to print the application state, e.g. the
+/// state machine of each task or the last processed command
+pub fn set_watchdog_state(state: fn(&mut Writer) -> fmt::Result) {
+    STATE.set(state);
+}
+
+/// Reports what the core was doing, at `pc`, when the watchdog was about to expire
+///
+/// Call this from the early warning interrupt of the watchdog (e.g. the pre-timeout interrupt),
+/// which fires some time before the watchdog resets the device, so silent watchdog resets leave
+/// a trace in the DCC log. `pc` and `lr` are the program counter and link register of the
+/// interrupted code, as saved by the entry code of the interrupt handler: this function can't
+/// read them itself since, by the time it runs, the handler has changed the registers. On A and R
+/// profile cores the interrupted PC is the LR of IRQ mode minus 4 and the interrupted LR is the LR
+/// of the mode in SPSR; on M profile cores both are in the exception frame on the stack.
+///
+/// The report is a line with `pc`, `lr` and the uptime (if `set_uptime` was called), followed by
+/// whatever the function registered with `set_watchdog_state` prints.
+///
+/// ``` no_run
+/// use core::fmt::Write;
+///
+/// static mut STATE: u32 = 0;
+///
+/// fn uptime() -> u64 {
+///     // e.g. read a free running timer
+///     0
+/// }
+///
+/// fn state(w: &mut arm_dcc::Writer) -> core::fmt::Result {
+///     writeln!(w, "state = {}", unsafe { STATE })
+/// }
+///
+/// // during initialization
+/// arm_dcc::set_uptime(uptime);
+/// arm_dcc::set_watchdog_state(state);
+///
+/// // in the watchdog early warning interrupt handler, with the `pc` and `lr` saved by its entry
+/// // code
+/// # let (pc, lr) = (0, 0);
+/// arm_dcc::watchdog_report_at(pc, lr);
+/// ```
+pub fn watchdog_report_at(pc: usize, lr: usize) {
+    // NOTE the width includes the `0x` prefix
+    let width = 2 + 2 * mem::size_of::<usize>();
+    // NOTE these operations never return `Err`
+    write!(
+        Writer,
+        "watchdog: pc = {:#0w$x}, lr = {:#0w$x}",
+        pc,
+        lr,
+        w = width
+    )
+    .ok();
+
+    if let Some(uptime) = uptime() {
+        write!(Writer, ", uptime = {} ms", uptime).ok();
+    }
+    Writer.write_str("\n").ok();
+
+    if let Some(state) = STATE.get() {
+        state(&mut Writer).ok();
+    }
+}